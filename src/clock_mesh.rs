@@ -0,0 +1,599 @@
+//! Clock mesh / spine hybrid structures.
+//!
+//! A clock mesh is a grid of horizontal and vertical metal straps over a
+//! region. Each `Sink` connects to the nearest strap through a short stub,
+//! and a zero-skew DME tree drives a set of tap points on the mesh. A mesh
+//! with a single horizontal (or vertical) strap degenerates to a spine.
+//!
+//! Because the mesh contains loops, sink delays cannot be computed with the
+//! tree Elmore formula. `solve_mesh_delays` instead solves the first-moment
+//! (generalized Elmore) equations of the resistive network
+//!
+//! $$\sum_j g_{ij}\,(m_i - m_j) = C_i$$
+//!
+//! for all non-driven nodes, with driven tap nodes fixed to their arrival
+//! time from the top-level tree.
+
+use std::collections::HashMap;
+
+use crate::dme_algorithm::{DMEAlgorithm, ElmoreDelayCalculator, NodeIdx, Sink, Tree};
+use crate::point::Point;
+use crate::technology::WireRC;
+use crate::vlsi_ops::Rectangle;
+
+/// Kind of a node in a clock mesh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshNodeKind {
+    /// Strap intersection or stub attachment point on a strap
+    Strap,
+    /// Strap intersection driven by the top-level tree
+    Tap,
+    /// Clock sink at the far end of a stub
+    Sink,
+}
+
+/// A node of the mesh RC network.
+#[derive(Debug, Clone)]
+pub struct MeshNode {
+    /// Position of this node in the layout
+    pub position: Point<i32, i32>,
+    /// Kind of this node
+    pub kind: MeshNodeKind,
+    /// Lumped capacitance at this node (pin load plus half of each
+    /// incident wire)
+    pub capacitance: f64,
+}
+
+/// A wire segment of the mesh (strap piece or stub).
+#[derive(Debug, Clone)]
+pub struct MeshWire {
+    /// Index of the first end node
+    pub from: usize,
+    /// Index of the second end node
+    pub to: usize,
+    /// Manhattan length of the wire
+    pub length: i32,
+    /// Total wire resistance
+    pub resistance: f64,
+}
+
+/// Builder for a clock mesh driven by a DME tree.
+///
+/// Straps are spread evenly over `region`; `taps_x × taps_y` strap
+/// intersections, also spread evenly, are driven by the top-level tree.
+pub struct ClockMeshBuilder {
+    /// Region covered by the mesh
+    pub region: Rectangle<i32>,
+    /// Number of horizontal straps (at least 1)
+    pub horizontal_straps: usize,
+    /// Number of vertical straps (at least 1)
+    pub vertical_straps: usize,
+    /// Number of tap columns
+    pub taps_x: usize,
+    /// Number of tap rows
+    pub taps_y: usize,
+    /// Per-unit RC of straps and stubs
+    pub mesh_rc: WireRC,
+    /// Per-unit RC of the top-level tree wires
+    pub tree_rc: WireRC,
+    /// Optional clock source position for the top-level tree
+    pub source: Option<Point<i32, i32>>,
+}
+
+impl ClockMeshBuilder {
+    /// Creates a mesh builder with a 2×2 tap grid and the same RC for the
+    /// mesh and the top-level tree.
+    pub fn new(
+        region: Rectangle<i32>,
+        horizontal_straps: usize,
+        vertical_straps: usize,
+        mesh_rc: WireRC,
+    ) -> Self {
+        ClockMeshBuilder {
+            region,
+            horizontal_straps,
+            vertical_straps,
+            taps_x: 2,
+            taps_y: 2,
+            mesh_rc,
+            tree_rc: mesh_rc,
+            source: None,
+        }
+    }
+
+    /// Builds the mesh, the sink stubs and the top-level DME tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sinks` is empty or the builder has no straps or taps.
+    pub fn build(&self, sinks: &[Sink]) -> ClockMesh {
+        assert!(!sinks.is_empty(), "No sinks provided");
+        assert!(
+            self.horizontal_straps > 0 && self.vertical_straps > 0,
+            "Mesh needs at least one horizontal and one vertical strap"
+        );
+        assert!(self.taps_x > 0 && self.taps_y > 0, "Mesh needs taps");
+
+        let ys = spread(
+            self.region.min.ycoord,
+            self.region.max.ycoord,
+            self.horizontal_straps,
+        );
+        let xs = spread(
+            self.region.min.xcoord,
+            self.region.max.xcoord,
+            self.vertical_straps,
+        );
+
+        let mut mesh = ClockMesh {
+            nodes: Vec::new(),
+            wires: Vec::new(),
+            taps: Vec::new(),
+            sinks: Vec::new(),
+            tree: Tree::new(),
+            tree_root: 0,
+        };
+        let mut index: HashMap<(i32, i32), usize> = HashMap::new();
+        let mut on_h: Vec<Vec<usize>> = vec![Vec::new(); ys.len()];
+        let mut on_v: Vec<Vec<usize>> = vec![Vec::new(); xs.len()];
+
+        for (hi, &y) in ys.iter().enumerate() {
+            for (vi, &x) in xs.iter().enumerate() {
+                let idx = mesh.strap_node(&mut index, Point::new(x, y));
+                on_h[hi].push(idx);
+                on_v[vi].push(idx);
+            }
+        }
+
+        // Sink stubs: attach each sink to the closest point on any strap.
+        let mut stubs = Vec::with_capacity(sinks.len());
+        for s in sinks {
+            let cx = s
+                .position
+                .xcoord
+                .clamp(self.region.min.xcoord, self.region.max.xcoord);
+            let cy = s
+                .position
+                .ycoord
+                .clamp(self.region.min.ycoord, self.region.max.ycoord);
+            let (hi, &hy) = nearest(&ys, s.position.ycoord);
+            let (vi, &vx) = nearest(&xs, s.position.xcoord);
+            let h_pt = Point::new(cx, hy);
+            let v_pt = Point::new(vx, cy);
+            let h_dist = manhattan(&s.position, &h_pt);
+            let v_dist = manhattan(&s.position, &v_pt);
+            let attach = if h_dist <= v_dist {
+                let idx = mesh.strap_node(&mut index, h_pt);
+                on_h[hi].push(idx);
+                idx
+            } else {
+                let idx = mesh.strap_node(&mut index, v_pt);
+                on_v[vi].push(idx);
+                idx
+            };
+            stubs.push(attach);
+        }
+
+        for nodes in on_h.iter_mut() {
+            nodes.sort_by_key(|&n| mesh.nodes[n].position.xcoord);
+            nodes.dedup();
+            for pair in nodes.windows(2) {
+                mesh.add_wire(pair[0], pair[1], &self.mesh_rc);
+            }
+        }
+        for nodes in on_v.iter_mut() {
+            nodes.sort_by_key(|&n| mesh.nodes[n].position.ycoord);
+            nodes.dedup();
+            for pair in nodes.windows(2) {
+                mesh.add_wire(pair[0], pair[1], &self.mesh_rc);
+            }
+        }
+
+        for (s, &attach) in sinks.iter().zip(&stubs) {
+            if mesh.nodes[attach].position == s.position {
+                mesh.nodes[attach].capacitance += s.capacitance;
+                mesh.sinks.push((s.name.clone(), attach));
+                continue;
+            }
+            let idx = mesh.nodes.len();
+            mesh.nodes.push(MeshNode {
+                position: s.position,
+                kind: MeshNodeKind::Sink,
+                capacitance: s.capacitance,
+            });
+            mesh.add_wire(attach, idx, &self.mesh_rc);
+            mesh.sinks.push((s.name.clone(), idx));
+        }
+
+        // Tap points, driven by the top-level DME tree.
+        let tap_cols = pick(self.vertical_straps, self.taps_x);
+        let tap_rows = pick(self.horizontal_straps, self.taps_y);
+        for &r in &tap_rows {
+            for &c in &tap_cols {
+                let idx = index[&(xs[c], ys[r])];
+                mesh.nodes[idx].kind = MeshNodeKind::Tap;
+                mesh.taps.push(idx);
+            }
+        }
+
+        let total_cap: f64 = mesh.nodes.iter().map(|n| n.capacitance).sum();
+        let tap_cap = total_cap / mesh.taps.len() as f64;
+        let tap_sinks: Vec<Sink> = mesh
+            .taps
+            .iter()
+            .enumerate()
+            .map(|(i, &t)| Sink::new(&format!("tap{}", i), mesh.nodes[t].position, tap_cap))
+            .collect();
+        let calc = Box::new(ElmoreDelayCalculator::new(
+            self.tree_rc.unit_resistance,
+            self.tree_rc.unit_capacitance,
+        ));
+        let mut dme = match self.source {
+            Some(src) => DMEAlgorithm::with_source(tap_sinks, calc, src),
+            None => DMEAlgorithm::new(tap_sinks, calc),
+        };
+        mesh.tree_root = dme.build_clock_tree();
+        mesh.tree = dme.get_tree().clone();
+        mesh
+    }
+}
+
+/// A built clock mesh together with the DME tree that drives its taps.
+#[derive(Debug, Clone)]
+pub struct ClockMesh {
+    /// All nodes of the mesh RC network
+    pub nodes: Vec<MeshNode>,
+    /// All strap segments and stubs
+    pub wires: Vec<MeshWire>,
+    /// Indices of mesh nodes driven by the top-level tree; tap `i` is leaf
+    /// `i` of `tree`
+    pub taps: Vec<usize>,
+    /// Sink names and the mesh node index of each sink
+    pub sinks: Vec<(String, usize)>,
+    /// Top-level DME tree driving the taps
+    pub tree: Tree,
+    /// Root of the top-level tree
+    pub tree_root: NodeIdx,
+}
+
+/// Timing results of a clock mesh.
+#[derive(Debug, Clone)]
+pub struct MeshAnalysis {
+    /// Sink names and delays from the tree root through the mesh
+    pub sink_delays: Vec<(String, f64)>,
+    /// Maximum sink delay
+    pub max_delay: f64,
+    /// Minimum sink delay
+    pub min_delay: f64,
+    /// Clock skew = max_delay - min_delay
+    pub skew: f64,
+    /// Total wirelength of straps and stubs
+    pub mesh_wirelength: i32,
+    /// Total wirelength of the top-level tree
+    pub tree_wirelength: i32,
+}
+
+impl ClockMesh {
+    fn strap_node(&mut self, index: &mut HashMap<(i32, i32), usize>, pt: Point<i32, i32>) -> usize {
+        *index.entry((pt.xcoord, pt.ycoord)).or_insert_with(|| {
+            self.nodes.push(MeshNode {
+                position: pt,
+                kind: MeshNodeKind::Strap,
+                capacitance: 0.0,
+            });
+            self.nodes.len() - 1
+        })
+    }
+
+    fn add_wire(&mut self, from: usize, to: usize, rc: &WireRC) {
+        let length = manhattan(&self.nodes[from].position, &self.nodes[to].position);
+        let cap = rc.unit_capacitance * length as f64;
+        self.nodes[from].capacitance += cap / 2.0;
+        self.nodes[to].capacitance += cap / 2.0;
+        self.wires.push(MeshWire {
+            from,
+            to,
+            length,
+            resistance: rc.unit_resistance * length as f64,
+        });
+    }
+
+    /// Total wirelength of straps and stubs.
+    pub fn mesh_wirelength(&self) -> i32 {
+        self.wires.iter().map(|w| w.length).sum()
+    }
+
+    /// Computes the delay to every sink: tree delay to each tap, then the
+    /// generalized Elmore delay through the resistive mesh.
+    pub fn analyze(&self) -> MeshAnalysis {
+        let drivers: Vec<(usize, f64)> = self
+            .taps
+            .iter()
+            .enumerate()
+            .map(|(leaf, &t)| (t, self.tree.get(leaf).delay))
+            .collect();
+        let resistors: Vec<(usize, usize, f64)> = self
+            .wires
+            .iter()
+            .map(|w| (w.from, w.to, w.resistance))
+            .collect();
+        let caps: Vec<f64> = self.nodes.iter().map(|n| n.capacitance).collect();
+        let delays = solve_mesh_delays(self.nodes.len(), &resistors, &caps, &drivers);
+
+        let sink_delays: Vec<(String, f64)> = self
+            .sinks
+            .iter()
+            .map(|(name, idx)| (name.clone(), delays[*idx]))
+            .collect();
+        let max_delay = sink_delays
+            .iter()
+            .map(|s| s.1)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let min_delay = sink_delays
+            .iter()
+            .map(|s| s.1)
+            .fold(f64::INFINITY, f64::min);
+        let tree_wirelength = self
+            .tree
+            .iter()
+            .filter(|n| n.parent.is_some())
+            .map(|n| n.wire_length)
+            .sum();
+
+        MeshAnalysis {
+            sink_delays,
+            max_delay,
+            min_delay,
+            skew: max_delay - min_delay,
+            mesh_wirelength: self.mesh_wirelength(),
+            tree_wirelength,
+        }
+    }
+}
+
+/// Solves the first-moment delay equations of an RC network.
+///
+/// * `num_nodes` — number of nodes
+/// * `resistors` — `(a, b, resistance)` for each resistor
+/// * `caps` — grounded capacitance at each node
+/// * `drivers` — `(node, arrival_time)` for each driven node
+///
+/// Returns the delay at every node. For every non-driven node $i$,
+///
+/// $$\sum_j \frac{m_i - m_j}{R_{ij}} = C_i$$
+///
+/// which reduces to the Elmore delay when the network is a tree. The
+/// reduced conductance matrix is symmetric positive definite whenever every
+/// node is connected to a driver, so the system is solved with conjugate
+/// gradients. Zero-resistance resistors are treated as a very large
+/// conductance.
+///
+/// # Panics
+///
+/// Panics if `drivers` is empty.
+pub fn solve_mesh_delays(
+    num_nodes: usize,
+    resistors: &[(usize, usize, f64)],
+    caps: &[f64],
+    drivers: &[(usize, f64)],
+) -> Vec<f64> {
+    assert!(!drivers.is_empty(), "No driven nodes provided");
+    let mut fixed: Vec<Option<f64>> = vec![None; num_nodes];
+    for &(n, t) in drivers {
+        fixed[n] = Some(t);
+    }
+
+    // Unknown numbering for free nodes.
+    let mut var = vec![usize::MAX; num_nodes];
+    let mut free = Vec::new();
+    for (i, f) in fixed.iter().enumerate() {
+        if f.is_none() {
+            var[i] = free.len();
+            free.push(i);
+        }
+    }
+
+    let mut diag = vec![0.0; free.len()];
+    let mut off: Vec<Vec<(usize, f64)>> = vec![Vec::new(); free.len()];
+    let mut rhs: Vec<f64> = free.iter().map(|&i| caps[i]).collect();
+    for &(a, b, r) in resistors {
+        let g = 1.0 / r.max(1e-12);
+        match (fixed[a], fixed[b]) {
+            (None, None) => {
+                let (va, vb) = (var[a], var[b]);
+                diag[va] += g;
+                diag[vb] += g;
+                off[va].push((vb, g));
+                off[vb].push((va, g));
+            }
+            (None, Some(tb)) => {
+                diag[var[a]] += g;
+                rhs[var[a]] += g * tb;
+            }
+            (Some(ta), None) => {
+                diag[var[b]] += g;
+                rhs[var[b]] += g * ta;
+            }
+            (Some(_), Some(_)) => {}
+        }
+    }
+
+    let apply = |x: &[f64], y: &mut [f64]| {
+        for i in 0..x.len() {
+            let mut s = diag[i] * x[i];
+            for &(j, g) in &off[i] {
+                s -= g * x[j];
+            }
+            y[i] = s;
+        }
+    };
+
+    // Jacobi-preconditioned conjugate gradients.
+    let n = free.len();
+    let mut x = vec![0.0; n];
+    let mut r = rhs.clone();
+    let mut z: Vec<f64> = r.iter().zip(&diag).map(|(ri, d)| ri / d).collect();
+    let mut p = z.clone();
+    let mut ap = vec![0.0; n];
+    let mut rz: f64 = r.iter().zip(&z).map(|(a, b)| a * b).sum();
+    let tol = 1e-24 * rhs.iter().map(|v| v * v).sum::<f64>().max(1e-300);
+    for _ in 0..(10 * n + 10) {
+        if r.iter().map(|v| v * v).sum::<f64>() <= tol {
+            break;
+        }
+        apply(&p, &mut ap);
+        let pap: f64 = p.iter().zip(&ap).map(|(a, b)| a * b).sum();
+        if pap <= 0.0 {
+            break;
+        }
+        let alpha = rz / pap;
+        for i in 0..n {
+            x[i] += alpha * p[i];
+            r[i] -= alpha * ap[i];
+        }
+        for i in 0..n {
+            z[i] = r[i] / diag[i];
+        }
+        let rz_new: f64 = r.iter().zip(&z).map(|(a, b)| a * b).sum();
+        let beta = rz_new / rz;
+        rz = rz_new;
+        for i in 0..n {
+            p[i] = z[i] + beta * p[i];
+        }
+    }
+
+    (0..num_nodes)
+        .map(|i| match fixed[i] {
+            Some(t) => t,
+            None => x[var[i]],
+        })
+        .collect()
+}
+
+/// `count` evenly spread integer coordinates over `[lo, hi]`.
+fn spread(lo: i32, hi: i32, count: usize) -> Vec<i32> {
+    if count == 1 {
+        return vec![lo + (hi - lo) / 2];
+    }
+    let span = (hi - lo) as i64;
+    (0..count)
+        .map(|k| lo + (span * k as i64 / (count - 1) as i64) as i32)
+        .collect()
+}
+
+/// `count` evenly spread indices out of `0..total`.
+fn pick(total: usize, count: usize) -> Vec<usize> {
+    let count = count.min(total);
+    if count == 1 {
+        return vec![(total - 1) / 2];
+    }
+    let mut v: Vec<usize> = (0..count).map(|k| k * (total - 1) / (count - 1)).collect();
+    v.dedup();
+    v
+}
+
+fn nearest(coords: &[i32], value: i32) -> (usize, &i32) {
+    coords
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, &c)| (c - value).abs())
+        .expect("Empty coordinate list")
+}
+
+fn manhattan(a: &Point<i32, i32>, b: &Point<i32, i32>) -> i32 {
+    (a.xcoord - b.xcoord).abs() + (a.ycoord - b.ycoord).abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-6, "left={}, right={}", a, b);
+    }
+
+    #[test]
+    fn test_solver_matches_tree_elmore() {
+        // 0 (driver) -R1- 1 (C1) -R2- 2 (C2)
+        let delays = solve_mesh_delays(
+            3,
+            &[(0, 1, 2.0), (1, 2, 3.0)],
+            &[0.0, 1.0, 4.0],
+            &[(0, 0.5)],
+        );
+        approx_eq(delays[0], 0.5);
+        approx_eq(delays[1], 0.5 + 2.0 * 5.0);
+        approx_eq(delays[2], 0.5 + 2.0 * 5.0 + 3.0 * 4.0);
+    }
+
+    #[test]
+    fn test_solver_symmetric_loop() {
+        // Two equal paths from the driver to node 3.
+        let delays = solve_mesh_delays(
+            4,
+            &[(0, 1, 1.0), (0, 2, 1.0), (1, 3, 1.0), (2, 3, 1.0)],
+            &[0.0, 0.0, 0.0, 2.0],
+            &[(0, 0.0)],
+        );
+        approx_eq(delays[1], delays[2]);
+        // Two parallel 2-ohm paths = 1 ohm driving 2 units of capacitance.
+        approx_eq(delays[3], 2.0);
+    }
+
+    #[test]
+    fn test_mesh_structure() {
+        let region = Rectangle::new(Point::new(0, 0), Point::new(100, 100));
+        let builder = ClockMeshBuilder::new(region, 3, 3, WireRC::new(0.1, 0.2));
+        let sinks = vec![
+            Sink::new("a", Point::new(10, 10), 1.0),
+            Sink::new("b", Point::new(50, 50), 1.0),
+        ];
+        let mesh = builder.build(&sinks);
+        assert_eq!(mesh.taps.len(), 4);
+        assert_eq!(mesh.sinks.len(), 2);
+        // Sink "b" sits on a strap intersection; "a" gets a stub node.
+        assert_eq!(mesh.sinks[1].1, 4);
+        assert_eq!(mesh.nodes[mesh.sinks[0].1].kind, MeshNodeKind::Sink);
+        // 3x3 grid straps = 6 * 100, plus a 10-unit stub.
+        assert_eq!(mesh.mesh_wirelength(), 610);
+        for (i, &t) in mesh.taps.iter().enumerate() {
+            assert_eq!(mesh.nodes[t].kind, MeshNodeKind::Tap);
+            assert!(mesh.tree.get(i).is_leaf());
+        }
+    }
+
+    #[test]
+    fn test_mesh_analysis_symmetric_sinks() {
+        let region = Rectangle::new(Point::new(0, 0), Point::new(200, 200));
+        let mut builder = ClockMeshBuilder::new(region, 5, 5, WireRC::new(0.01, 0.02));
+        builder.taps_x = 3;
+        builder.taps_y = 3;
+        let sinks = vec![
+            Sink::new("s1", Point::new(20, 20), 1.0),
+            Sink::new("s2", Point::new(180, 20), 1.0),
+            Sink::new("s3", Point::new(20, 180), 1.0),
+            Sink::new("s4", Point::new(180, 180), 1.0),
+        ];
+        let mesh = builder.build(&sinks);
+        let analysis = mesh.analyze();
+        assert_eq!(analysis.sink_delays.len(), 4);
+        assert!(analysis.min_delay > 0.0);
+        assert!(analysis.skew / analysis.max_delay < 0.02);
+        assert!(analysis.tree_wirelength > 0);
+    }
+
+    #[test]
+    fn test_spine_single_strap() {
+        let region = Rectangle::new(Point::new(0, 0), Point::new(100, 40));
+        let mut builder = ClockMeshBuilder::new(region, 1, 2, WireRC::new(0.1, 0.1));
+        builder.taps_y = 1;
+        let sinks = vec![
+            Sink::new("s1", Point::new(30, 0), 1.0),
+            Sink::new("s2", Point::new(70, 40), 1.0),
+        ];
+        let mesh = builder.build(&sinks);
+        assert_eq!(mesh.taps.len(), 2);
+        let analysis = mesh.analyze();
+        assert!(analysis.skew.abs() < 1e-6);
+    }
+}
@@ -0,0 +1,383 @@
+//! Monte Carlo variation analysis for clock skew.
+//!
+//! `monte_carlo_skew` re-evaluates the Elmore delays of an embedded clock
+//! `Tree` under random per-wire RC and per-sink capacitance variation and
+//! reports the resulting skew distribution. It is meant for offline
+//! robustness comparison of tree topologies, so every run is reproducible
+//! from its seed.
+//!
+//! Each varied quantity is scaled by $1 + \sigma z$, where the standard
+//! normal $z$ mixes a spatially correlated component and an independent
+//! local component:
+//!
+//! $$z = \sqrt{\rho}\, z_{\text{spatial}}(\text{cell}) + \sqrt{1 - \rho}\, z_{\text{local}}$$
+//!
+//! The spatial field is drawn on a grid of `grid_size`-wide cells over the
+//! tree's bounding box: independent normals per cell are averaged over each
+//! 3×3 neighbourhood (and rescaled to unit variance), so nearby wires and
+//! sinks see correlated variation.
+
+use crate::dme_algorithm::{NodeIdx, Tree};
+use crate::point::Point;
+use crate::technology::{collect_sinks, tree_elmore_delays, wire_spans, WireRC};
+
+/// Statistical variation model for clock tree wires and sinks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VariationModel {
+    /// Nominal per-unit wire RC
+    pub wire: WireRC,
+    /// Relative standard deviation of wire resistance
+    pub sigma_resistance: f64,
+    /// Relative standard deviation of wire capacitance
+    pub sigma_capacitance: f64,
+    /// Relative standard deviation of sink pin capacitance
+    pub sigma_sink_capacitance: f64,
+    /// Fraction $\rho \in [0, 1]$ of the variance that is spatially correlated
+    pub spatial_fraction: f64,
+    /// Width of a spatial correlation grid cell
+    pub grid_size: i32,
+}
+
+impl VariationModel {
+    /// Creates a variation model with the given nominal wire RC and no
+    /// variation; set the sigma fields to enable it.
+    pub fn new(wire: WireRC) -> Self {
+        VariationModel {
+            wire,
+            sigma_resistance: 0.0,
+            sigma_capacitance: 0.0,
+            sigma_sink_capacitance: 0.0,
+            spatial_fraction: 0.5,
+            grid_size: 100,
+        }
+    }
+}
+
+/// Skew distribution from a Monte Carlo run.
+#[derive(Debug, Clone)]
+pub struct MonteCarloAnalysis {
+    /// Skew with no variation applied
+    pub nominal_skew: f64,
+    /// Skew of every sample, sorted ascending
+    pub skews: Vec<f64>,
+    /// Mean skew
+    pub mean: f64,
+    /// Standard deviation of skew
+    pub std_dev: f64,
+    /// Sink names with the number of samples in which each was the
+    /// earliest sink, most frequent first
+    pub earliest_counts: Vec<(String, usize)>,
+    /// Sink names with the number of samples in which each was the
+    /// latest sink, most frequent first
+    pub latest_counts: Vec<(String, usize)>,
+}
+
+impl MonteCarloAnalysis {
+    /// Returns the `p`-th percentile (`p` in `[0, 100]`) of the skew
+    /// samples, interpolating linearly between order statistics.
+    pub fn percentile(&self, p: f64) -> f64 {
+        if self.skews.is_empty() {
+            return 0.0;
+        }
+        let rank = (p.clamp(0.0, 100.0) / 100.0) * (self.skews.len() - 1) as f64;
+        let lo = rank.floor() as usize;
+        let hi = rank.ceil() as usize;
+        let frac = rank - lo as f64;
+        self.skews[lo] * (1.0 - frac) + self.skews[hi] * frac
+    }
+}
+
+/// Runs `num_samples` Monte Carlo evaluations of the skew of the tree
+/// rooted at `root`, drawing variation from a generator seeded with
+/// `seed`.
+pub fn monte_carlo_skew(
+    tree: &Tree,
+    root: NodeIdx,
+    model: &VariationModel,
+    num_samples: usize,
+    seed: u64,
+) -> MonteCarloAnalysis {
+    let spans = wire_spans(tree);
+    let mut sinks = Vec::new();
+    collect_sinks(tree, root, &mut sinks);
+
+    // Each wire is sampled at its midpoint; each sink at its position.
+    let wire_sites: Vec<Point<i32, i32>> = tree
+        .iter()
+        .map(|n| match n.parent {
+            Some(p) => {
+                let pp = tree.get(p).position;
+                Point::new(
+                    (n.position.xcoord + pp.xcoord) / 2,
+                    (n.position.ycoord + pp.ycoord) / 2,
+                )
+            }
+            None => n.position,
+        })
+        .collect();
+    let grid = SpatialGrid::new(tree, model.grid_size);
+
+    let nominal_wires: Vec<(f64, f64)> = spans
+        .iter()
+        .map(|&(h, v)| {
+            let len = h + v;
+            (
+                model.wire.unit_resistance * len,
+                model.wire.unit_capacitance * len,
+            )
+        })
+        .collect();
+    let nominal_caps: Vec<f64> = tree.iter().map(|n| n.capacitance).collect();
+    let nominal = tree_elmore_delays(tree, root, &nominal_wires, &nominal_caps);
+    let nominal_skew = skew_of(&nominal, &sinks).0;
+
+    let mut rng = SplitMix64::new(seed);
+    let rho = model.spatial_fraction.clamp(0.0, 1.0);
+    let mut skews = Vec::with_capacity(num_samples);
+    let mut earliest = vec![0usize; tree.len()];
+    let mut latest = vec![0usize; tree.len()];
+    let mut wires = nominal_wires.clone();
+    let mut caps = nominal_caps.clone();
+
+    for _ in 0..num_samples {
+        let r_field = grid.sample(&mut rng);
+        let c_field = grid.sample(&mut rng);
+        let s_field = grid.sample(&mut rng);
+
+        for (i, w) in wires.iter_mut().enumerate() {
+            let cell = grid.cell(&wire_sites[i]);
+            let zr = rho.sqrt() * r_field[cell] + (1.0 - rho).sqrt() * rng.next_normal();
+            let zc = rho.sqrt() * c_field[cell] + (1.0 - rho).sqrt() * rng.next_normal();
+            w.0 = nominal_wires[i].0 * scale(model.sigma_resistance, zr);
+            w.1 = nominal_wires[i].1 * scale(model.sigma_capacitance, zc);
+        }
+        for &s in &sinks {
+            let cell = grid.cell(&tree.get(s).position);
+            let z = rho.sqrt() * s_field[cell] + (1.0 - rho).sqrt() * rng.next_normal();
+            caps[s] = nominal_caps[s] * scale(model.sigma_sink_capacitance, z);
+        }
+
+        let delays = tree_elmore_delays(tree, root, &wires, &caps);
+        let (skew, first, last) = skew_of(&delays, &sinks);
+        skews.push(skew);
+        earliest[first] += 1;
+        latest[last] += 1;
+    }
+
+    let n = skews.len().max(1) as f64;
+    let mean = skews.iter().sum::<f64>() / n;
+    let var = skews.iter().map(|s| (s - mean) * (s - mean)).sum::<f64>() / n;
+    skews.sort_by(|a, b| a.total_cmp(b));
+
+    MonteCarloAnalysis {
+        nominal_skew,
+        skews,
+        mean,
+        std_dev: var.sqrt(),
+        earliest_counts: ranked_counts(tree, &earliest),
+        latest_counts: ranked_counts(tree, &latest),
+    }
+}
+
+/// Multiplicative variation factor, kept strictly positive.
+fn scale(sigma: f64, z: f64) -> f64 {
+    (1.0 + sigma * z).max(1e-3)
+}
+
+/// Returns `(skew, earliest_sink, latest_sink)`.
+fn skew_of(delays: &[f64], sinks: &[NodeIdx]) -> (f64, NodeIdx, NodeIdx) {
+    let mut first = sinks[0];
+    let mut last = sinks[0];
+    for &s in sinks {
+        if delays[s] < delays[first] {
+            first = s;
+        }
+        if delays[s] > delays[last] {
+            last = s;
+        }
+    }
+    (delays[last] - delays[first], first, last)
+}
+
+fn ranked_counts(tree: &Tree, counts: &[usize]) -> Vec<(String, usize)> {
+    let mut ranked: Vec<(String, usize)> = counts
+        .iter()
+        .enumerate()
+        .filter(|(_, &c)| c > 0)
+        .map(|(i, &c)| (tree.get(i).name.clone(), c))
+        .collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+}
+
+/// Grid of spatially correlated standard normals over a tree's bounding box.
+struct SpatialGrid {
+    min_x: i32,
+    min_y: i32,
+    cell_size: i32,
+    cols: usize,
+    rows: usize,
+}
+
+impl SpatialGrid {
+    fn new(tree: &Tree, cell_size: i32) -> Self {
+        let cell_size = cell_size.max(1);
+        let min_x = tree.iter().map(|n| n.position.xcoord).min().unwrap_or(0);
+        let max_x = tree.iter().map(|n| n.position.xcoord).max().unwrap_or(0);
+        let min_y = tree.iter().map(|n| n.position.ycoord).min().unwrap_or(0);
+        let max_y = tree.iter().map(|n| n.position.ycoord).max().unwrap_or(0);
+        SpatialGrid {
+            min_x,
+            min_y,
+            cell_size,
+            cols: ((max_x - min_x) / cell_size + 1) as usize,
+            rows: ((max_y - min_y) / cell_size + 1) as usize,
+        }
+    }
+
+    fn cell(&self, pt: &Point<i32, i32>) -> usize {
+        let c = (((pt.xcoord - self.min_x) / self.cell_size).max(0) as usize).min(self.cols - 1);
+        let r = (((pt.ycoord - self.min_y) / self.cell_size).max(0) as usize).min(self.rows - 1);
+        r * self.cols + c
+    }
+
+    /// Draws one correlated field: 3×3 neighbourhood means of independent
+    /// normals, rescaled to unit variance.
+    fn sample(&self, rng: &mut SplitMix64) -> Vec<f64> {
+        let raw: Vec<f64> = (0..self.rows * self.cols)
+            .map(|_| rng.next_normal())
+            .collect();
+        let mut field = vec![0.0; raw.len()];
+        for r in 0..self.rows {
+            for c in 0..self.cols {
+                let mut sum = 0.0;
+                let mut count = 0usize;
+                for rr in r.saturating_sub(1)..(r + 2).min(self.rows) {
+                    for cc in c.saturating_sub(1)..(c + 2).min(self.cols) {
+                        sum += raw[rr * self.cols + cc];
+                        count += 1;
+                    }
+                }
+                field[r * self.cols + c] = sum / (count as f64).sqrt();
+            }
+        }
+        field
+    }
+}
+
+/// Small seeded generator (SplitMix64) with Box-Muller normal sampling.
+struct SplitMix64 {
+    state: u64,
+    spare: Option<f64>,
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        SplitMix64 {
+            state: seed,
+            spare: None,
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform sample in (0, 1].
+    fn next_f64(&mut self) -> f64 {
+        ((self.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+
+    fn next_normal(&mut self) -> f64 {
+        if let Some(z) = self.spare.take() {
+            return z;
+        }
+        let u1 = self.next_f64();
+        let u2 = self.next_f64();
+        let radius = (-2.0 * u1.ln()).sqrt();
+        let theta = std::f64::consts::TAU * u2;
+        self.spare = Some(radius * theta.sin());
+        radius * theta.cos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dme_algorithm::{DMEAlgorithm, ElmoreDelayCalculator, Sink};
+
+    fn build_tree() -> (Tree, NodeIdx) {
+        let sinks = (0..16)
+            .map(|i| {
+                Sink::new(
+                    &format!("s{}", i),
+                    Point::new((i * 37) % 300, (i * 53) % 300),
+                    1.0,
+                )
+            })
+            .collect();
+        let mut dme = DMEAlgorithm::new(sinks, Box::new(ElmoreDelayCalculator::new(0.1, 0.2)));
+        let root = dme.build_clock_tree();
+        (dme.get_tree().clone(), root)
+    }
+
+    #[test]
+    fn test_no_variation_reproduces_nominal() {
+        let (tree, root) = build_tree();
+        let model = VariationModel::new(WireRC::new(0.1, 0.2));
+        let mc = monte_carlo_skew(&tree, root, &model, 10, 1);
+        assert_eq!(mc.skews.len(), 10);
+        for s in &mc.skews {
+            assert!((s - mc.nominal_skew).abs() < 1e-9);
+        }
+        assert!(mc.std_dev < 1e-9);
+    }
+
+    #[test]
+    fn test_seeded_runs_are_reproducible() {
+        let (tree, root) = build_tree();
+        let mut model = VariationModel::new(WireRC::new(0.1, 0.2));
+        model.sigma_resistance = 0.1;
+        model.sigma_capacitance = 0.05;
+        model.sigma_sink_capacitance = 0.1;
+        let a = monte_carlo_skew(&tree, root, &model, 50, 42);
+        let b = monte_carlo_skew(&tree, root, &model, 50, 42);
+        let c = monte_carlo_skew(&tree, root, &model, 50, 43);
+        assert_eq!(a.skews, b.skews);
+        assert_ne!(a.skews, c.skews);
+    }
+
+    #[test]
+    fn test_distribution_statistics() {
+        let (tree, root) = build_tree();
+        let mut model = VariationModel::new(WireRC::new(0.1, 0.2));
+        model.sigma_resistance = 0.1;
+        model.sigma_sink_capacitance = 0.1;
+        model.grid_size = 50;
+        let mc = monte_carlo_skew(&tree, root, &model, 200, 7);
+        assert!(mc.std_dev > 0.0);
+        assert!(mc.mean > mc.nominal_skew);
+        assert!(mc.percentile(0.0) <= mc.percentile(50.0));
+        assert!(mc.percentile(50.0) <= mc.percentile(99.0));
+        assert_eq!(mc.percentile(100.0), *mc.skews.last().unwrap());
+        let total: usize = mc.latest_counts.iter().map(|c| c.1).sum();
+        assert_eq!(total, 200);
+        let total: usize = mc.earliest_counts.iter().map(|c| c.1).sum();
+        assert_eq!(total, 200);
+        assert!(mc.latest_counts[0].1 >= mc.latest_counts.last().unwrap().1);
+    }
+
+    #[test]
+    fn test_normal_sampler_moments() {
+        let mut rng = SplitMix64::new(123);
+        let samples: Vec<f64> = (0..20000).map(|_| rng.next_normal()).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let var = samples.iter().map(|z| (z - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+        assert!(mean.abs() < 0.05);
+        assert!((var - 1.0).abs() < 0.05);
+    }
+}
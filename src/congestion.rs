@@ -0,0 +1,550 @@
+//! Placement-stage congestion estimation on a bin grid.
+//!
+//! Two estimators spread the expected wire demand of each net over the bins
+//! it may use:
+//!
+//! - **RUDY** (rectangular uniform wire density): the horizontal and
+//!   vertical half-perimeter of the net's bounding box are spread uniformly
+//!   over the box.
+//! - **Probabilistic L/Z**: the net is decomposed into the two-pin
+//!   connections of its MST, and each connection is spread over all of its
+//!   L and Z shaped routes with equal probability.
+//!
+//! Demand is measured in wire length per bin and direction. Capacity is the
+//! number of tracks times the bin pitch, reduced by the fraction of the bin
+//! covered by keepouts. `CongestionMap::to_svg` renders the utilization as a
+//! heatmap.
+
+use crate::interval::Interval;
+use crate::netlist::Netlist;
+use crate::point::Point;
+use crate::rsmt::mst;
+use crate::vlsi_ops::Rectangle;
+
+/// A uniform grid of congestion bins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinGrid {
+    /// Lower-left corner of bin (0, 0)
+    pub origin: Point<i32, i32>,
+    /// Bin width
+    pub bin_width: i32,
+    /// Bin height
+    pub bin_height: i32,
+    /// Number of bin columns
+    pub cols: usize,
+    /// Number of bin rows
+    pub rows: usize,
+}
+
+impl BinGrid {
+    /// Creates a bin grid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the grid is empty or a bin dimension is not positive.
+    pub fn new(
+        origin: Point<i32, i32>,
+        bin_width: i32,
+        bin_height: i32,
+        cols: usize,
+        rows: usize,
+    ) -> Self {
+        assert!(cols > 0 && rows > 0, "Grid must have at least one bin");
+        assert!(
+            bin_width > 0 && bin_height > 0,
+            "Bin dimensions must be positive"
+        );
+        BinGrid {
+            origin,
+            bin_width,
+            bin_height,
+            cols,
+            rows,
+        }
+    }
+
+    /// Number of bins.
+    pub fn num_bins(&self) -> usize {
+        self.cols * self.rows
+    }
+
+    /// The region covered by bin `(col, row)`.
+    pub fn bin_rect(&self, col: usize, row: usize) -> Point<Interval<i32>, Interval<i32>> {
+        let x = self.origin.xcoord + col as i32 * self.bin_width;
+        let y = self.origin.ycoord + row as i32 * self.bin_height;
+        Point::new(
+            Interval::new(x, x + self.bin_width),
+            Interval::new(y, y + self.bin_height),
+        )
+    }
+
+    /// The bin containing `point`, clamped to the grid.
+    pub fn bin_of(&self, point: &Point<i32, i32>) -> (usize, usize) {
+        let col = (point.xcoord - self.origin.xcoord).div_euclid(self.bin_width);
+        let row = (point.ycoord - self.origin.ycoord).div_euclid(self.bin_height);
+        (
+            col.clamp(0, self.cols as i32 - 1) as usize,
+            row.clamp(0, self.rows as i32 - 1) as usize,
+        )
+    }
+
+    fn index(&self, col: usize, row: usize) -> usize {
+        row * self.cols + col
+    }
+}
+
+/// Congestion estimator used by `CongestionMap::add_netlist`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CongestionModel {
+    /// Rectangular uniform wire density over the bounding box
+    #[default]
+    Rudy,
+    /// Equal-probability L and Z routes of the MST connections
+    Probabilistic,
+}
+
+/// Estimated horizontal and vertical wire demand and capacity per bin.
+#[derive(Debug, Clone)]
+pub struct CongestionMap {
+    grid: BinGrid,
+    horizontal_tracks: f64,
+    vertical_tracks: f64,
+    horizontal: Vec<f64>,
+    vertical: Vec<f64>,
+    blocked_area: Vec<f64>,
+}
+
+/// Length of the overlap of `[lo, hi]` with `[a, b]`. A degenerate span
+/// `lo == hi` counts as length 1 in the bin where it lies.
+fn span_overlap(lo: i32, hi: i32, a: i32, b: i32, last: bool) -> f64 {
+    if lo == hi {
+        let inside = a <= lo && (lo < b || (last && lo == b));
+        return if inside { 1.0 } else { 0.0 };
+    }
+    (hi.min(b) - lo.max(a)).max(0) as f64
+}
+
+impl CongestionMap {
+    /// Creates an empty map with the given number of horizontal and vertical
+    /// routing tracks per bin.
+    pub fn new(grid: BinGrid, horizontal_tracks: f64, vertical_tracks: f64) -> Self {
+        let n = grid.num_bins();
+        CongestionMap {
+            grid,
+            horizontal_tracks,
+            vertical_tracks,
+            horizontal: vec![0.0; n],
+            vertical: vec![0.0; n],
+            blocked_area: vec![0.0; n],
+        }
+    }
+
+    /// Returns the bin grid.
+    pub fn grid(&self) -> &BinGrid {
+        &self.grid
+    }
+
+    /// Removes the routing capacity under a keepout, in proportion to the
+    /// area of each bin it covers.
+    pub fn add_keepout(&mut self, region: &Point<Interval<i32>, Interval<i32>>) {
+        for row in 0..self.grid.rows {
+            for col in 0..self.grid.cols {
+                let bin = self.grid.bin_rect(col, row);
+                let w = (region.xcoord.ub.min(bin.xcoord.ub) - region.xcoord.lb.max(bin.xcoord.lb))
+                    .max(0);
+                let h = (region.ycoord.ub.min(bin.ycoord.ub) - region.ycoord.lb.max(bin.ycoord.lb))
+                    .max(0);
+                let i = self.grid.index(col, row);
+                let area = (self.grid.bin_width as f64) * (self.grid.bin_height as f64);
+                self.blocked_area[i] = (self.blocked_area[i] + (w as f64) * (h as f64)).min(area);
+            }
+        }
+    }
+
+    /// Adds the RUDY demand of a net with the given bounding box.
+    ///
+    /// The net's horizontal wire length (box width) and vertical wire length
+    /// (box height) are spread uniformly over the box. A box of zero width
+    /// or height is treated as one unit thick.
+    pub fn add_rudy(&mut self, bbox: &Point<Interval<i32>, Interval<i32>>) {
+        let (xlo, xhi) = (bbox.xcoord.lb, bbox.xcoord.ub);
+        let (ylo, yhi) = (bbox.ycoord.lb, bbox.ycoord.ub);
+        let width = (xhi - xlo) as f64;
+        let height = (yhi - ylo) as f64;
+        let area = width.max(1.0) * height.max(1.0);
+        let (c0, r0) = self.grid.bin_of(&Point::new(xlo, ylo));
+        let (c1, r1) = self.grid.bin_of(&Point::new(xhi, yhi));
+        for row in r0..=r1 {
+            for col in c0..=c1 {
+                let bin = self.grid.bin_rect(col, row);
+                let ox = span_overlap(
+                    xlo,
+                    xhi,
+                    bin.xcoord.lb,
+                    bin.xcoord.ub,
+                    col + 1 == self.grid.cols,
+                );
+                let oy = span_overlap(
+                    ylo,
+                    yhi,
+                    bin.ycoord.lb,
+                    bin.ycoord.ub,
+                    row + 1 == self.grid.rows,
+                );
+                let fraction = ox * oy / area;
+                let i = self.grid.index(col, row);
+                self.horizontal[i] += width * fraction;
+                self.vertical[i] += height * fraction;
+            }
+        }
+    }
+
+    /// Adds the RUDY demand of a net given by its pins.
+    pub fn add_rudy_pins(&mut self, pins: &[Point<i32, i32>]) {
+        let Some(first) = pins.first() else { return };
+        let (mut xlo, mut ylo) = (first.xcoord, first.ycoord);
+        let (mut xhi, mut yhi) = (xlo, ylo);
+        for p in pins {
+            xlo = xlo.min(p.xcoord);
+            xhi = xhi.max(p.xcoord);
+            ylo = ylo.min(p.ycoord);
+            yhi = yhi.max(p.ycoord);
+        }
+        self.add_rudy(&Point::new(
+            Interval::new(xlo, xhi),
+            Interval::new(ylo, yhi),
+        ));
+    }
+
+    /// Adds the RUDY demand of a net bounding rectangle, e.g. from
+    /// `vlsi_ops::bounding_rect`.
+    pub fn add_rudy_rect(&mut self, rect: &Rectangle<i32>) {
+        self.add_rudy(&Point::new(
+            Interval::new(rect.min.xcoord, rect.max.xcoord),
+            Interval::new(rect.min.ycoord, rect.max.ycoord),
+        ));
+    }
+
+    /// Adds the probabilistic L/Z demand of a net given by its pins.
+    ///
+    /// Pins are mapped to bins and joined along the MST of their bins. Each
+    /// connection that is not straight has one route per bend column
+    /// (horizontal-vertical-horizontal) and per interior bend row
+    /// (vertical-horizontal-vertical), all equally likely; the two L routes
+    /// are the end columns, so the end rows are skipped. A route crossing
+    /// a bin uses one bin pitch of wire there, and half a pitch in the bins
+    /// where it starts, ends or bends.
+    pub fn add_probabilistic(&mut self, pins: &[Point<i32, i32>]) {
+        let mut bins: Vec<(usize, usize)> = Vec::new();
+        for p in pins {
+            let b = self.grid.bin_of(p);
+            if !bins.contains(&b) {
+                bins.push(b);
+            }
+        }
+        let pts: Vec<Point<i32, i32>> = bins
+            .iter()
+            .map(|&(c, r)| Point::new(c as i32, r as i32))
+            .collect();
+        for (a, b) in mst(&pts).1 {
+            let ((c0, r0), (c1, r1)) = (bins[a], bins[b]);
+            if c0 == c1 || r0 == r1 {
+                self.add_horizontal_run(r0, c0, c1, 1.0);
+                self.add_vertical_run(c0, r0, r1, 1.0);
+                continue;
+            }
+            let routes = (c0.abs_diff(c1) + 1) + (r0.abs_diff(r1) - 1);
+            let p = 1.0 / routes as f64;
+            for m in c0.min(c1)..=c0.max(c1) {
+                self.add_horizontal_run(r0, c0, m, p);
+                self.add_vertical_run(m, r0, r1, p);
+                self.add_horizontal_run(r1, m, c1, p);
+            }
+            for m in r0.min(r1) + 1..r0.max(r1) {
+                self.add_vertical_run(c0, r0, m, p);
+                self.add_horizontal_run(m, c0, c1, p);
+                self.add_vertical_run(c1, m, r1, p);
+            }
+        }
+    }
+
+    fn add_horizontal_run(&mut self, row: usize, a: usize, b: usize, p: f64) {
+        let pitch = self.grid.bin_width as f64;
+        for col in a.min(b)..a.max(b) {
+            self.horizontal[self.grid.index(col, row)] += 0.5 * pitch * p;
+            self.horizontal[self.grid.index(col + 1, row)] += 0.5 * pitch * p;
+        }
+    }
+
+    fn add_vertical_run(&mut self, col: usize, a: usize, b: usize, p: f64) {
+        let pitch = self.grid.bin_height as f64;
+        for row in a.min(b)..a.max(b) {
+            self.vertical[self.grid.index(col, row)] += 0.5 * pitch * p;
+            self.vertical[self.grid.index(col, row + 1)] += 0.5 * pitch * p;
+        }
+    }
+
+    /// Adds the demand of every net of a netlist.
+    pub fn add_netlist(&mut self, netlist: &Netlist, model: CongestionModel) {
+        for net in netlist.nets() {
+            let pins: Vec<Point<i32, i32>> = net.pins().copied().collect();
+            match model {
+                CongestionModel::Rudy => self.add_rudy_pins(&pins),
+                CongestionModel::Probabilistic => self.add_probabilistic(&pins),
+            }
+        }
+    }
+
+    /// Horizontal wire demand of bin `(col, row)`.
+    pub fn horizontal_demand(&self, col: usize, row: usize) -> f64 {
+        self.horizontal[self.grid.index(col, row)]
+    }
+
+    /// Vertical wire demand of bin `(col, row)`.
+    pub fn vertical_demand(&self, col: usize, row: usize) -> f64 {
+        self.vertical[self.grid.index(col, row)]
+    }
+
+    fn free_fraction(&self, i: usize) -> f64 {
+        let area = (self.grid.bin_width as f64) * (self.grid.bin_height as f64);
+        1.0 - self.blocked_area[i] / area
+    }
+
+    /// Horizontal capacity (track length) of bin `(col, row)`.
+    pub fn horizontal_capacity(&self, col: usize, row: usize) -> f64 {
+        let i = self.grid.index(col, row);
+        self.horizontal_tracks * self.grid.bin_width as f64 * self.free_fraction(i)
+    }
+
+    /// Vertical capacity (track length) of bin `(col, row)`.
+    pub fn vertical_capacity(&self, col: usize, row: usize) -> f64 {
+        let i = self.grid.index(col, row);
+        self.vertical_tracks * self.grid.bin_height as f64 * self.free_fraction(i)
+    }
+
+    /// Utilization of bin `(col, row)`: the larger of the horizontal and
+    /// vertical demand-to-capacity ratios. Demand on zero capacity is
+    /// infinite.
+    pub fn utilization(&self, col: usize, row: usize) -> f64 {
+        let ratio = |demand: f64, capacity: f64| {
+            if demand <= 0.0 {
+                0.0
+            } else if capacity <= 0.0 {
+                f64::INFINITY
+            } else {
+                demand / capacity
+            }
+        };
+        ratio(
+            self.horizontal_demand(col, row),
+            self.horizontal_capacity(col, row),
+        )
+        .max(ratio(
+            self.vertical_demand(col, row),
+            self.vertical_capacity(col, row),
+        ))
+    }
+
+    /// Largest utilization of any bin.
+    pub fn max_utilization(&self) -> f64 {
+        (0..self.grid.rows)
+            .flat_map(|row| (0..self.grid.cols).map(move |col| (col, row)))
+            .map(|(col, row)| self.utilization(col, row))
+            .fold(0.0, f64::max)
+    }
+
+    /// Bins whose utilization exceeds `threshold`, most congested first.
+    pub fn hotspots(&self, threshold: f64) -> Vec<(usize, usize)> {
+        let mut bins: Vec<(f64, (usize, usize))> = (0..self.grid.rows)
+            .flat_map(|row| (0..self.grid.cols).map(move |col| (col, row)))
+            .map(|(col, row)| (self.utilization(col, row), (col, row)))
+            .filter(|&(u, _)| u > threshold)
+            .collect();
+        bins.sort_by(|a, b| b.0.total_cmp(&a.0));
+        bins.into_iter().map(|(_, bin)| bin).collect()
+    }
+
+    /// Generate an SVG heatmap of the bin utilization: green for idle bins
+    /// through yellow to red at full utilization, dark red above capacity.
+    pub fn to_svg(
+        &self,
+        keepouts: Option<&Vec<Point<Interval<i32>, Interval<i32>>>>,
+        width: u32,
+        height: u32,
+        margin: u32,
+    ) -> String {
+        let grid = &self.grid;
+        let range_x = (grid.cols as i32 * grid.bin_width) as f64;
+        let range_y = (grid.rows as i32 * grid.bin_height) as f64;
+
+        let w = (width as f64) - 2.0 * (margin as f64);
+        let h = (height as f64) - 2.0 * (margin as f64);
+        let scale = (w / range_x).min(h / range_y);
+
+        let sx = |x: i32| margin as f64 + (x - grid.origin.xcoord) as f64 * scale;
+        let sy = |y: i32| margin as f64 + (y - grid.origin.ycoord) as f64 * scale;
+
+        let mut svg = String::new();
+        svg.push_str(&format!(
+            r#"<svg width="{}" height="{}" xmlns="http://www.w3.org/2000/svg">"#,
+            width, height
+        ));
+        svg.push_str(r#"<rect width="100%" height="100%" fill="white"/>"#);
+
+        // Draw bins
+        for row in 0..grid.rows {
+            for col in 0..grid.cols {
+                let bin = grid.bin_rect(col, row);
+                let u = self.utilization(col, row);
+                let color = if u > 1.0 {
+                    "rgb(128,0,0)".to_string()
+                } else {
+                    format!("hsl({:.0},100%,50%)", 120.0 * (1.0 - u))
+                };
+                svg.push_str(&format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" fill-opacity="0.7" stroke="gray" stroke-width="0.5"><title>({},{}) {:.2}</title></rect>"#,
+                    sx(bin.xcoord.lb),
+                    sy(bin.ycoord.lb),
+                    grid.bin_width as f64 * scale,
+                    grid.bin_height as f64 * scale,
+                    color,
+                    col,
+                    row,
+                    u
+                ));
+            }
+        }
+
+        // Draw keepouts
+        if let Some(kos) = keepouts {
+            for ko in kos {
+                svg.push_str(&format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="black" stroke-width="1" stroke-dasharray="4"/>"#,
+                    sx(ko.xcoord.lb),
+                    sy(ko.ycoord.lb),
+                    ko.xcoord.length() as f64 * scale,
+                    ko.ycoord.length() as f64 * scale
+                ));
+            }
+        }
+
+        // Statistics
+        svg.push_str(&format!(
+            r#"<text x="20" y="20" font-family="Arial" font-size="10" font-weight="bold">Max utilization: {:.2}</text>"#,
+            self.max_utilization()
+        ));
+        svg.push_str(&format!(
+            r#"<text x="20" y="35" font-family="Arial" font-size="9">Hotspots (&gt;1.0): {}</text>"#,
+            self.hotspots(1.0).len()
+        ));
+
+        svg.push_str("</svg>");
+        svg
+    }
+
+    /// Save the SVG heatmap to a file.
+    pub fn save_svg(
+        &self,
+        keepouts: Option<&Vec<Point<Interval<i32>, Interval<i32>>>>,
+        filename: &str,
+        width: u32,
+        height: u32,
+    ) {
+        let svg = self.to_svg(keepouts, width, height, 50);
+        std::fs::write(filename, svg).expect("Failed to write SVG file");
+        println!("Saved SVG to {}", filename);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::netlist::Net;
+
+    fn map() -> CongestionMap {
+        CongestionMap::new(BinGrid::new(Point::new(0, 0), 10, 10, 4, 4), 2.0, 2.0)
+    }
+
+    fn totals(map: &CongestionMap) -> (f64, f64) {
+        let mut h = 0.0;
+        let mut v = 0.0;
+        for row in 0..4 {
+            for col in 0..4 {
+                h += map.horizontal_demand(col, row);
+                v += map.vertical_demand(col, row);
+            }
+        }
+        (h, v)
+    }
+
+    #[test]
+    fn test_rudy_conserves_wirelength() {
+        let mut m = map();
+        m.add_rudy_pins(&[Point::new(5, 5), Point::new(25, 35), Point::new(15, 20)]);
+        let (h, v) = totals(&m);
+        assert!((h - 20.0).abs() < 1e-9);
+        assert!((v - 30.0).abs() < 1e-9);
+        // Uniform density: a fully covered bin gets 10 * 10 / 600 of each.
+        assert!((m.horizontal_demand(1, 1) - 20.0 / 6.0).abs() < 1e-9);
+        assert_eq!(m.horizontal_demand(3, 3), 0.0);
+
+        // A horizontal two-pin net is one unit thick.
+        let mut m = map();
+        m.add_rudy_rect(&Rectangle::new(Point::new(0, 12), Point::new(40, 12)));
+        let (h, v) = totals(&m);
+        assert!((h - 40.0).abs() < 1e-9);
+        assert_eq!(v, 0.0);
+        assert!((m.horizontal_demand(2, 1) - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_probabilistic_demand() {
+        let mut m = map();
+        m.add_probabilistic(&[Point::new(5, 5), Point::new(35, 5)]);
+        assert_eq!(m.horizontal_demand(0, 0), 5.0);
+        assert_eq!(m.horizontal_demand(1, 0), 10.0);
+        assert_eq!(m.horizontal_demand(3, 0), 5.0);
+
+        let mut m = map();
+        m.add_probabilistic(&[Point::new(5, 5), Point::new(25, 35)]);
+        let (h, v) = totals(&m);
+        assert!((h - 20.0).abs() < 1e-9);
+        assert!((v - 30.0).abs() < 1e-9);
+        // Bins far from both L shapes see less demand than the corners.
+        assert!(m.horizontal_demand(1, 1) < m.horizontal_demand(1, 0));
+        // Five routes, each L once: bend columns 0, 1, 2 and bend rows 1, 2.
+        // Row 0 is left horizontally by the routes bending at columns 1 and 2,
+        // column 0 vertically by those bending at column 0 and rows 1 and 2.
+        assert!((m.horizontal_demand(0, 0) - 2.0 * 5.0 / 5.0).abs() < 1e-9);
+        assert!((m.vertical_demand(0, 0) - 3.0 * 5.0 / 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_keepouts_and_hotspots() {
+        let mut m = map();
+        m.add_keepout(&Point::new(Interval::new(0, 5), Interval::new(0, 10)));
+        assert!((m.horizontal_capacity(0, 0) - 10.0).abs() < 1e-9);
+        assert!((m.vertical_capacity(1, 0) - 20.0).abs() < 1e-9);
+
+        let mut netlist = Netlist::new();
+        for i in 0..4 {
+            netlist
+                .add_net(Net::new(
+                    &format!("n{}", i),
+                    Point::new(1, 2 + i),
+                    vec![Point::new(19, 2 + i)],
+                ))
+                .unwrap();
+        }
+        m.add_netlist(&netlist, CongestionModel::Rudy);
+        let hot = m.hotspots(1.0);
+        assert_eq!(hot[0], (0, 0));
+        assert!(m.utilization(0, 0) > m.utilization(1, 0));
+        assert_eq!(m.utilization(3, 3), 0.0);
+
+        let svg = m.to_svg(None, 400, 400, 20);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<title>").count(), 16);
+        assert!(svg.contains("rgb(128,0,0)"));
+    }
+}
@@ -6,13 +6,18 @@
 //! Nodes are stored in an arena (`Tree`) and referenced by `usize` index,
 //! avoiding `Rc<RefCell<>>` overhead.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use crate::error::{DmeError, DmeResult};
 use crate::generic::MinDist;
 use crate::interval::Interval;
 use crate::manhattan_arc::ManhattanArc;
 use crate::point::Point;
 
+/// $\ln 9$: ratio between the 10%-90% transition time and the Elmore time
+/// constant of a single-pole RC response.
+const SLEW_LN9: f64 = 2.197_224_577_336_219_6;
+
 /// A clock sink with name, position, and capacitance.
 #[derive(Debug, Clone)]
 pub struct Sink {
@@ -58,6 +63,8 @@ pub struct TreeNode {
     pub delay: f64,
     /// Load capacitance at this node
     pub capacitance: f64,
+    /// Signal transition time (slew) at this node
+    pub slew: f64,
     /// Whether this node's wire needs elongation to satisfy timing
     pub need_elongation: bool,
 }
@@ -77,6 +84,7 @@ impl TreeNode {
             wire_length: 0,
             delay: 0.0,
             capacitance: 0.0,
+            slew: 0.0,
             need_elongation: false,
         }
     }
@@ -97,6 +105,11 @@ pub struct Tree {
     nodes: Vec<TreeNode>,
     /// Index of the root node, if the tree has been built.
     pub root: Option<NodeIdx>,
+    /// Index of the clock source node, if the tree was built with a source.
+    /// The source has the root as its only (left) child.
+    pub source: Option<NodeIdx>,
+    /// Output resistance of the driver at the source node.
+    pub driver_resistance: f64,
 }
 
 impl Tree {
@@ -341,6 +354,47 @@ impl DelayCalculator for ElmoreDelayCalculator {
     }
 }
 
+/// Slew (transition time) model used to propagate transition times
+/// through the clock tree.
+///
+/// Both models use the 10%-90% step-response approximation
+/// $t_{\text{slew}} = \ln 9 \cdot \tau$ for an RC stage with Elmore
+/// delay $\tau$, combined with the input ramp as a root sum of squares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SlewModel {
+    /// PERI (Probability-distribution-function Extension for Ramp Inputs):
+    /// the source ramp combined with the step slew of the whole
+    /// source-to-node Elmore delay $t_{\text{node}}$,
+    ///
+    /// $$s = \sqrt{s_{\text{src}}^2 + (\ln 9 \cdot t_{\text{node}})^2}$$
+    #[default]
+    Peri,
+    /// Cascaded per-wire degradation: each wire combines its input slew with
+    /// the step slew of its own delay $t_{\text{wire}}$,
+    ///
+    /// $$s_{\text{child}} = \sqrt{s_{\text{parent}}^2 + (\ln 9 \cdot t_{\text{wire}})^2}$$
+    ///
+    /// This is more optimistic than PERI for deep trees.
+    Cascaded,
+}
+
+/// How the root of the merging tree is placed on its merging segment when
+/// a clock source is given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RootEmbedding {
+    /// The point of the segment nearest to the source (shortest root wire).
+    #[default]
+    Nearest,
+    /// The point of the segment with the lowest source latency
+    /// $R_d (C_{wire} + C_{root})$ plus the Elmore delay of the root wire,
+    /// chosen among points sampled along the segment.
+    MinLatency,
+}
+
+/// Number of intervals the root merging segment is split into when
+/// sampling candidates for `RootEmbedding::MinLatency`.
+const ROOT_SAMPLES: i32 = 8;
+
 /// Results of clock skew analysis.
 #[derive(Debug, Clone)]
 pub struct SkewAnalysis {
@@ -356,6 +410,20 @@ pub struct SkewAnalysis {
     pub total_wirelength: i32,
     /// Name of the delay model used (e.g. "LinearDelayCalculator")
     pub delay_model: String,
+    /// Individual slews for each sink, in the same order as `sink_delays`
+    pub sink_slews: Vec<f64>,
+    /// Worst (largest) slew among all sinks
+    pub worst_slew: f64,
+    /// Names of all nodes whose slew exceeds the max-slew limit, if one is set
+    pub slew_violations: Vec<String>,
+    /// Delay from the clock source to the analyzed root: driver plus root
+    /// wire (zero when the tree has no source node). It is not included in
+    /// the sink delays, and the root wire is not included in
+    /// `total_wirelength`.
+    pub source_latency: f64,
+    /// Largest delay from the clock source to a sink,
+    /// `max_delay + source_latency`
+    pub max_latency: f64,
 }
 
 /// Detailed tree statistics collected from a clock tree.
@@ -423,6 +491,14 @@ pub struct DMEAlgorithm {
     delay_calculator: Box<dyn DelayCalculator>,
     node_id: i32,
     source: Option<Point<i32, i32>>,
+    slew_model: SlewModel,
+    source_slew: f64,
+    max_slew: Option<f64>,
+    driver_resistance: f64,
+    root_embedding: RootEmbedding,
+    cluster_roots: Vec<NodeIdx>,
+    /// Embedded subtree roots treated as fixed leaves by the top-level merge
+    super_sinks: HashSet<NodeIdx>,
     tree: Tree,
 }
 
@@ -439,10 +515,26 @@ impl DMEAlgorithm {
             delay_calculator: calculator,
             node_id: 0,
             source: None,
+            slew_model: SlewModel::default(),
+            source_slew: 0.0,
+            max_slew: None,
+            driver_resistance: 0.0,
+            root_embedding: RootEmbedding::default(),
+            cluster_roots: Vec::new(),
+            super_sinks: HashSet::new(),
             tree: Tree::new(),
         }
     }
 
+    /// Fallible version of `new`.
+    ///
+    /// Returns an error if `sinks` is empty, contains duplicate names or
+    /// coincident positions, or has a NaN, infinite or negative capacitance.
+    pub fn try_new(sinks: Vec<Sink>, calculator: Box<dyn DelayCalculator>) -> DmeResult<Self> {
+        validate_sinks(&sinks)?;
+        Ok(Self::new(sinks, calculator))
+    }
+
     /// Creates a new DME algorithm with a specified clock source position.
     ///
     /// # Panics
@@ -459,15 +551,87 @@ impl DMEAlgorithm {
             delay_calculator: calculator,
             node_id: 0,
             source: Some(source),
+            slew_model: SlewModel::default(),
+            source_slew: 0.0,
+            max_slew: None,
+            driver_resistance: 0.0,
+            root_embedding: RootEmbedding::default(),
+            cluster_roots: Vec::new(),
+            super_sinks: HashSet::new(),
             tree: Tree::new(),
         }
     }
 
+    /// Fallible version of `with_source`, with the same checks as `try_new`.
+    pub fn try_with_source(
+        sinks: Vec<Sink>,
+        calculator: Box<dyn DelayCalculator>,
+        source: Point<i32, i32>,
+    ) -> DmeResult<Self> {
+        validate_sinks(&sinks)?;
+        Ok(Self::with_source(sinks, calculator, source))
+    }
+
+    /// Sets the output resistance of the clock driver at the source.
+    ///
+    /// Only used when a source is given; the driver adds
+    /// $R_d \cdot C_{src}$ to every sink delay, where $C_{src}$ is the total
+    /// load including the root wire.
+    pub fn set_driver_resistance(&mut self, resistance: f64) {
+        self.driver_resistance = resistance;
+    }
+
+    /// Sets how the root is placed on its merging segment relative to the
+    /// source.
+    pub fn set_root_embedding(&mut self, embedding: RootEmbedding) {
+        self.root_embedding = embedding;
+    }
+
+    /// Sets the slew model used to propagate transition times.
+    pub fn set_slew_model(&mut self, model: SlewModel) {
+        self.slew_model = model;
+    }
+
+    /// Sets the input transition time at the clock source (root).
+    pub fn set_source_slew(&mut self, slew: f64) {
+        self.source_slew = slew;
+    }
+
+    /// Sets the maximum allowed slew. Nodes exceeding it are reported by
+    /// `slew_violations` and in `SkewAnalysis::slew_violations`.
+    pub fn set_max_slew(&mut self, max_slew: Option<f64>) {
+        self.max_slew = max_slew;
+    }
+
+    /// Returns the maximum allowed slew, if one is set.
+    pub fn max_slew(&self) -> Option<f64> {
+        self.max_slew
+    }
+
+    /// Returns the indices of all nodes whose slew exceeds the max-slew
+    /// limit, in depth-first order from `root`.
+    ///
+    /// Returns an empty list if no limit is set. The list is intended to
+    /// drive buffer insertion: each violating node marks a subtree whose
+    /// driving wire must be split by a repeater.
+    pub fn slew_violations(&self, root: NodeIdx) -> Vec<NodeIdx> {
+        let mut violations = Vec::new();
+        if let Some(limit) = self.max_slew {
+            collect_slew_violations(&self.tree, root, limit, &mut violations);
+        }
+        violations
+    }
+
     /// Returns a reference to the constructed tree.
     pub fn get_tree(&self) -> &Tree {
         &self.tree
     }
 
+    /// Returns the delay model used to build the tree.
+    pub fn delay_calculator(&self) -> &dyn DelayCalculator {
+        self.delay_calculator.as_ref()
+    }
+
     /// Returns a mutable reference to the constructed tree.
     pub fn get_tree_mut(&mut self) -> &mut Tree {
         &mut self.tree
@@ -475,27 +639,234 @@ impl DMEAlgorithm {
 
     /// Builds the clock tree and returns the root index.
     pub fn build_clock_tree(&mut self) -> NodeIdx {
+        let root = self.build_topology();
+        self.embed_tree(root)
+            .expect("Generated merging topology is malformed");
+        root
+    }
+
+    /// Fallible version of `build_clock_tree`.
+    ///
+    /// Validates the sinks as `try_new` does before building.
+    pub fn try_build_clock_tree(&mut self) -> DmeResult<NodeIdx> {
+        validate_sinks(&self.sinks)?;
+        let root = self.build_topology();
+        self.embed_tree(root)?;
+        Ok(root)
+    }
+
+    /// Resets the tree and adds one leaf per sink, in sink order.
+    fn add_sink_leaves(&mut self) {
         self.node_id = 0;
         self.tree = Tree::new();
+        self.cluster_roots.clear();
+        self.super_sinks.clear();
 
         for s in &self.sinks {
             let mut node = TreeNode::new(&s.name, s.position);
             node.capacitance = s.capacitance;
             self.tree.add(node);
         }
+    }
 
+    fn build_topology(&mut self) -> NodeIdx {
+        self.add_sink_leaves();
         let leaf_indices: Vec<NodeIdx> = (0..self.tree.len()).collect();
-        let root = self.build_merging_tree(&leaf_indices, false);
+        self.build_merging_tree(&leaf_indices, false)
+    }
 
+    /// Runs bottom-up merging, top-down embedding and delay/slew
+    /// computation over the merging topology rooted at `root`.
+    fn embed_tree(&mut self, root: NodeIdx) -> DmeResult<()> {
         let mut merging_segments: HashMap<NodeIdx, ManhattanArc<Interval<i32>>> = HashMap::new();
-        self.compute_merging_segment(root, &mut merging_segments);
-        self.embed_node(root, None, &merging_segments);
-        self.compute_delays(root, 0.0);
+        self.compute_merging_segment(root, &mut merging_segments)?;
+
+        let root_segment = merging_segments[&root];
+        let mut position = match self.source {
+            Some(src) => root_segment.nearest_point_to(&src),
+            None => root_segment.get_upper_corner(),
+        };
+        if let (Some(src), RootEmbedding::MinLatency) = (self.source, self.root_embedding) {
+            // Every point of the segment has the same delay to the sinks, so
+            // only the driver and the root wire differ.
+            let load = self.tree.get(root).capacitance;
+            let latency = |p: &Point<i32, i32>| {
+                let length = p.min_dist_with(&src) as i32;
+                self.driver_resistance
+                    * (self.delay_calculator.calculate_wire_capacitance(length) + load)
+                    + self.delay_calculator.calculate_wire_delay(length, load)
+            };
+            let mut best_latency = latency(&position);
+            for candidate in root_candidates(&root_segment, position) {
+                let candidate_latency = latency(&candidate);
+                if candidate_latency < best_latency {
+                    best_latency = candidate_latency;
+                    position = candidate;
+                }
+            }
+        }
+        self.embed_from(root, position, &merging_segments);
 
         self.tree.root = Some(root);
+        Ok(())
+    }
+
+    /// Places the root at `position`, embeds the rest of the tree, connects
+    /// the source node (if any) and computes capacitances, delays and slews.
+    fn embed_from(
+        &mut self,
+        root: NodeIdx,
+        position: Point<i32, i32>,
+        segments: &HashMap<NodeIdx, ManhattanArc<Interval<i32>>>,
+    ) {
+        self.tree.get_mut(root).position = position;
+        self.embed_node(root, None, segments);
+        self.compute_capacitances(root);
+
+        let top = match self.source {
+            Some(src) => self.connect_source(root, src),
+            None => root,
+        };
+        let driver_delay = if top == root {
+            0.0
+        } else {
+            self.driver_resistance * self.tree.get(top).capacitance
+        };
+        self.compute_delays(top, driver_delay);
+        self.compute_slews(top, self.source_slew);
+    }
+
+    /// Adds (or reuses) the source node and wires it to `root`. Returns the
+    /// source node index.
+    fn connect_source(&mut self, root: NodeIdx, src: Point<i32, i32>) -> NodeIdx {
+        let idx = match self.tree.source {
+            Some(idx) => idx,
+            None => {
+                let idx = self.tree.add(TreeNode::new("source", src));
+                self.tree.source = Some(idx);
+                idx
+            }
+        };
+        let wire_length = self.tree.get(root).position.min_dist_with(&src) as i32;
+        let load = self.tree.get(root).capacitance
+            + self
+                .delay_calculator
+                .calculate_wire_capacitance(wire_length);
+        {
+            let node = self.tree.get_mut(root);
+            node.parent = Some(idx);
+            node.wire_length = wire_length;
+        }
+        let source = self.tree.get_mut(idx);
+        source.left = Some(root);
+        source.capacitance = load;
+        self.tree.driver_resistance = self.driver_resistance;
+        idx
+    }
+
+    /// Builds a hierarchical clock tree from a partition of the sinks into
+    /// clusters and returns the root index.
+    ///
+    /// Each cluster (a list of indices into the sink list) is merged and
+    /// embedded on its own, with its local root placed nearest to the
+    /// cluster centroid. The embedded local roots then act as super-sinks at
+    /// fixed positions: the top-level merge sees only them, each carrying the
+    /// capacitance and internal latency of its cluster, and balances them like
+    /// sinks with prescribed delays. Merging cost is thus that of the clusters
+    /// plus a tree over their roots. The result is one `Tree` that
+    /// `analyze_skew` and `get_tree_statistics` handle as usual; the local
+    /// roots are available from `cluster_roots`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `clusters` does not partition the sink indices.
+    pub fn build_clustered_clock_tree(&mut self, clusters: &[Vec<usize>]) -> NodeIdx {
+        if let Err(e) = self.check_partition(clusters) {
+            panic!("{}", e);
+        }
+        let root = self
+            .build_clustered_topology(clusters)
+            .expect("Generated merging topology is malformed");
+        self.embed_tree(root)
+            .expect("Generated merging topology is malformed");
         root
     }
 
+    /// Fallible version of `build_clustered_clock_tree`.
+    ///
+    /// Validates the sinks as `try_new` does, and returns
+    /// `DmeError::MalformedTopology` if `clusters` does not partition them.
+    pub fn try_build_clustered_clock_tree(
+        &mut self,
+        clusters: &[Vec<usize>],
+    ) -> DmeResult<NodeIdx> {
+        validate_sinks(&self.sinks)?;
+        self.check_partition(clusters)?;
+        let root = self.build_clustered_topology(clusters)?;
+        self.embed_tree(root)?;
+        Ok(root)
+    }
+
+    fn check_partition(&self, clusters: &[Vec<usize>]) -> DmeResult<()> {
+        let mut seen = vec![false; self.sinks.len()];
+        for &i in clusters.iter().flatten() {
+            if i >= seen.len() {
+                return Err(DmeError::MalformedTopology(format!(
+                    "cluster member {} is not a sink index",
+                    i
+                )));
+            }
+            if seen[i] {
+                return Err(DmeError::MalformedTopology(format!(
+                    "sink {} appears in more than one cluster",
+                    self.sinks[i].name
+                )));
+            }
+            seen[i] = true;
+        }
+        if let Some(i) = seen.iter().position(|&b| !b) {
+            return Err(DmeError::MalformedTopology(format!(
+                "sink {} is not in any cluster",
+                self.sinks[i].name
+            )));
+        }
+        Ok(())
+    }
+
+    fn build_clustered_topology(&mut self, clusters: &[Vec<usize>]) -> DmeResult<NodeIdx> {
+        self.add_sink_leaves();
+
+        let mut local_roots = Vec::with_capacity(clusters.len());
+        for cluster in clusters.iter().filter(|c| !c.is_empty()) {
+            let local = self.build_merging_tree(cluster, false);
+            // Merge and embed the cluster on its own, placing the local root
+            // on its merging segment nearest to the cluster centroid.
+            let mut segments = HashMap::new();
+            let root_segment = self.compute_merging_segment(local, &mut segments)?;
+            let n = cluster.len() as i64;
+            let (sx, sy) = cluster.iter().fold((0i64, 0i64), |(ax, ay), &i| {
+                let p = self.sinks[i].position;
+                (ax + p.xcoord as i64, ay + p.ycoord as i64)
+            });
+            let centroid = Point::new((sx / n) as i32, (sy / n) as i32);
+            self.tree.get_mut(local).position = root_segment.nearest_point_to(&centroid);
+            self.embed_node(local, None, &segments);
+            local_roots.push(local);
+        }
+        // The embedded local roots are the super-sinks of the top level,
+        // keeping their merge-time delay and capacitance.
+        self.super_sinks = local_roots.iter().cloned().collect();
+        let root = self.build_merging_tree(&local_roots, false);
+        self.cluster_roots = local_roots;
+        Ok(root)
+    }
+
+    /// Returns the local root of every cluster from the last call to
+    /// `build_clustered_clock_tree` (empty after `build_clock_tree`).
+    pub fn cluster_roots(&self) -> &[NodeIdx] {
+        &self.cluster_roots
+    }
+
     /// Build a balanced merging tree by recursive bipartition.
     fn build_merging_tree(&mut self, node_ids: &[NodeIdx], vertical: bool) -> NodeIdx {
         if node_ids.len() == 1 {
@@ -542,8 +913,8 @@ impl DMEAlgorithm {
         &mut self,
         node: NodeIdx,
         segments: &mut HashMap<NodeIdx, ManhattanArc<Interval<i32>>>,
-    ) -> ManhattanArc<Interval<i32>> {
-        if self.tree.get(node).is_leaf() {
+    ) -> DmeResult<ManhattanArc<Interval<i32>>> {
+        if self.tree.get(node).is_leaf() || self.super_sinks.contains(&node) {
             let pos = self.tree.get(node).position;
             let ms1 = ManhattanArc::from_point(pos);
             let ms = ManhattanArc::new(
@@ -551,22 +922,21 @@ impl DMEAlgorithm {
                 Interval::new(ms1.ycoord(), ms1.ycoord()),
             );
             segments.insert(node, ms);
-            return ms;
+            return Ok(ms);
         }
 
-        let left = self
-            .tree
-            .get(node)
-            .left
-            .expect("Internal node missing left child");
-        let right = self
-            .tree
-            .get(node)
-            .right
-            .expect("Internal node missing right child");
+        let (left, right) = match (self.tree.get(node).left, self.tree.get(node).right) {
+            (Some(l), Some(r)) => (l, r),
+            _ => {
+                return Err(DmeError::MalformedTopology(format!(
+                    "internal node {} has only one child",
+                    self.tree.get(node).name
+                )))
+            }
+        };
 
-        let left_ms = self.compute_merging_segment(left, segments);
-        let right_ms = self.compute_merging_segment(right, segments);
+        let left_ms = self.compute_merging_segment(left, segments)?;
+        let right_ms = self.compute_merging_segment(right, segments)?;
 
         let distance = left_ms.min_dist_with(&right_ms) as i32;
 
@@ -631,7 +1001,7 @@ impl DMEAlgorithm {
             lc + rc + wire_cap
         };
 
-        merged_segment
+        Ok(merged_segment)
     }
 
     fn embed_node(
@@ -644,15 +1014,8 @@ impl DMEAlgorithm {
             .get(&node)
             .expect("Merging segment not found for node");
 
-        if parent_segment.is_none() {
-            if let Some(src) = self.source {
-                let nearest = node_segment.nearest_point_to(&src);
-                self.tree.get_mut(node).position = nearest;
-            } else {
-                let upper = node_segment.get_upper_corner();
-                self.tree.get_mut(node).position = upper;
-            }
-        } else {
+        // The root is placed by the caller.
+        if parent_segment.is_some() {
             let parent_pos = self
                 .tree
                 .get(node)
@@ -666,6 +1029,11 @@ impl DMEAlgorithm {
             }
         }
 
+        // Super-sink subtrees are already embedded.
+        if self.super_sinks.contains(&node) {
+            return;
+        }
+
         let left = self.tree.get(node).left;
         let right = self.tree.get(node).right;
 
@@ -677,6 +1045,20 @@ impl DMEAlgorithm {
         }
     }
 
+    /// Recomputes internal node capacitances bottom-up from the embedded
+    /// wire lengths, which can differ from the merging distance by rounding.
+    fn compute_capacitances(&mut self, node: NodeIdx) -> f64 {
+        let (left, right) = (self.tree.get(node).left, self.tree.get(node).right);
+        if let (Some(l), Some(r)) = (left, right) {
+            let lc = self.compute_capacitances(l);
+            let rc = self.compute_capacitances(r);
+            let wl = self.tree.get(l).wire_length + self.tree.get(r).wire_length;
+            self.tree.get_mut(node).capacitance =
+                lc + rc + self.delay_calculator.calculate_wire_capacitance(wl);
+        }
+        self.tree.get(node).capacitance
+    }
+
     fn compute_delays(&mut self, node: NodeIdx, parent_delay: f64) {
         let has_parent = self.tree.get(node).parent.is_some();
         if has_parent {
@@ -685,7 +1067,7 @@ impl DMEAlgorithm {
             let wire_delay = self.delay_calculator.calculate_wire_delay(wl, cap);
             self.tree.get_mut(node).delay = parent_delay + wire_delay;
         } else {
-            self.tree.get_mut(node).delay = 0.0;
+            self.tree.get_mut(node).delay = parent_delay;
         }
 
         let current_delay = self.tree.get(node).delay;
@@ -700,13 +1082,73 @@ impl DMEAlgorithm {
         }
     }
 
+    fn compute_slews(&mut self, node: NodeIdx, parent_slew: f64) {
+        let slew = if self.tree.get(node).parent.is_none() {
+            self.source_slew
+        } else {
+            match self.slew_model {
+                SlewModel::Peri => {
+                    let step = SLEW_LN9 * self.tree.get(node).delay;
+                    (self.source_slew * self.source_slew + step * step).sqrt()
+                }
+                SlewModel::Cascaded => {
+                    let wl = self.tree.get(node).wire_length;
+                    let cap = self.tree.get(node).capacitance;
+                    let step = SLEW_LN9 * self.delay_calculator.calculate_wire_delay(wl, cap);
+                    (parent_slew * parent_slew + step * step).sqrt()
+                }
+            }
+        };
+        self.tree.get_mut(node).slew = slew;
+
+        let left = self.tree.get(node).left;
+        let right = self.tree.get(node).right;
+
+        if let Some(l) = left {
+            self.compute_slews(l, slew);
+        }
+        if let Some(r) = right {
+            self.compute_slews(r, slew);
+        }
+    }
+
     /// Analyze clock skew from the constructed tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no sink delays can be collected from `root`.
     pub fn analyze_skew(&self, root: NodeIdx) -> SkewAnalysis {
+        self.skew_analysis(root).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fallible version of `analyze_skew`.
+    ///
+    /// Checks the topology below `root` first (index bounds, binary
+    /// internal nodes, consistent parent links and no cycles), so it is
+    /// safe to call after hand-editing the tree via `get_tree_mut`.
+    pub fn try_analyze_skew(&self, root: NodeIdx) -> DmeResult<SkewAnalysis> {
+        check_topology(&self.tree, root)?;
+        self.skew_analysis(root)
+    }
+
+    fn skew_analysis(&self, root: NodeIdx) -> DmeResult<SkewAnalysis> {
+        // With a source node, delays and wirelength are reported from the
+        // root as before, and the driver plus root wire go to `source_latency`.
+        let node = self.tree.get(root);
+        let (source_latency, source_wire) = match node.parent {
+            Some(p) if self.tree.source == Some(p) => (node.delay, node.wire_length),
+            _ => (0.0, 0),
+        };
         let mut sink_delays = Vec::new();
         collect_sink_delays(&self.tree, root, &mut sink_delays);
+        for delay in &mut sink_delays {
+            *delay -= source_latency;
+        }
+        let mut sink_slews = Vec::new();
+        collect_sink_slews(&self.tree, root, &mut sink_slews);
 
         if sink_delays.is_empty() {
-            panic!("No sink delays collected");
+            return Err(DmeError::NoSinkDelays);
         }
 
         let max_delay = sink_delays
@@ -715,18 +1157,29 @@ impl DMEAlgorithm {
             .fold(f64::NEG_INFINITY, f64::max);
         let min_delay = sink_delays.iter().cloned().fold(f64::INFINITY, f64::min);
         let skew = max_delay - min_delay;
-        let total_wl = total_wirelength(&self.tree, root);
+        let total_wl = total_wirelength(&self.tree, root) - source_wire;
         #[allow(clippy::incompatible_msrv)]
         let delay_model = std::any::type_name_of_val(&*self.delay_calculator).to_string();
-
-        SkewAnalysis {
+        let worst_slew = sink_slews.iter().cloned().fold(0.0, f64::max);
+        let slew_violations = self
+            .slew_violations(root)
+            .into_iter()
+            .map(|idx| self.tree.get(idx).name.clone())
+            .collect();
+
+        Ok(SkewAnalysis {
             max_delay,
             min_delay,
             skew,
             sink_delays,
             total_wirelength: total_wl,
             delay_model,
-        }
+            sink_slews,
+            worst_slew,
+            slew_violations,
+            source_latency,
+            max_latency: max_delay + source_latency,
+        })
     }
 }
 
@@ -734,6 +1187,101 @@ impl DMEAlgorithm {
 // Free helper functions (work with &Tree + NodeIdx)
 // ---------------------------------------------------------------------------
 
+/// Candidate root positions on a merging segment: `first`, followed by
+/// `ROOT_SAMPLES + 1` points evenly spaced between the segment's corners in
+/// rotated space.
+fn root_candidates(
+    segment: &ManhattanArc<Interval<i32>>,
+    first: Point<i32, i32>,
+) -> Vec<Point<i32, i32>> {
+    let (rx, ry) = (segment.xcoord(), segment.ycoord());
+    let mut candidates = vec![first];
+    for t in 0..=ROOT_SAMPLES {
+        let x = rx.lb + (rx.ub - rx.lb) * t / ROOT_SAMPLES;
+        let y = ry.lb + (ry.ub - ry.lb) * t / ROOT_SAMPLES;
+        let p = segment.nearest_point_to(&Point::new((x + y) / 2, (y - x) / 2));
+        if !candidates.contains(&p) {
+            candidates.push(p);
+        }
+    }
+    candidates
+}
+
+/// Checks that sinks are non-empty, uniquely named, at distinct positions
+/// and have finite, non-negative capacitance.
+fn validate_sinks(sinks: &[Sink]) -> DmeResult<()> {
+    if sinks.is_empty() {
+        return Err(DmeError::EmptySinks);
+    }
+    let mut names: HashMap<&str, usize> = HashMap::new();
+    let mut positions: HashMap<(i32, i32), usize> = HashMap::new();
+    for (i, s) in sinks.iter().enumerate() {
+        if !s.capacitance.is_finite() || s.capacitance < 0.0 {
+            return Err(DmeError::InvalidCapacitance {
+                sink: s.name.clone(),
+                value: s.capacitance,
+            });
+        }
+        if names.insert(&s.name, i).is_some() {
+            return Err(DmeError::DuplicateSinkName(s.name.clone()));
+        }
+        if let Some(j) = positions.insert((s.position.xcoord, s.position.ycoord), i) {
+            return Err(DmeError::CoincidentSinks {
+                first: sinks[j].name.clone(),
+                second: s.name.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks the topology below `root`: indices in range, every internal node
+/// has both children, child-to-parent links agree, and no node is reached
+/// twice (which rules out cycles and shared subtrees).
+fn check_topology(tree: &Tree, root: NodeIdx) -> DmeResult<()> {
+    if root >= tree.len() {
+        return Err(DmeError::MalformedTopology(format!(
+            "root index {} out of range",
+            root
+        )));
+    }
+    let mut visited = vec![false; tree.len()];
+    let mut stack = vec![root];
+    while let Some(idx) = stack.pop() {
+        if visited[idx] {
+            return Err(DmeError::MalformedTopology(format!(
+                "node {} is reachable twice (cycle or shared subtree)",
+                tree.get(idx).name
+            )));
+        }
+        visited[idx] = true;
+        let node = tree.get(idx);
+        if node.left.is_some() != node.right.is_some() {
+            return Err(DmeError::MalformedTopology(format!(
+                "internal node {} has only one child",
+                node.name
+            )));
+        }
+        for child in [node.left, node.right].into_iter().flatten() {
+            if child >= tree.len() {
+                return Err(DmeError::MalformedTopology(format!(
+                    "node {} has out-of-range child {}",
+                    node.name, child
+                )));
+            }
+            if tree.get(child).parent != Some(idx) {
+                return Err(DmeError::MalformedTopology(format!(
+                    "node {} does not point back to its parent {}",
+                    tree.get(child).name,
+                    node.name
+                )));
+            }
+            stack.push(child);
+        }
+    }
+    Ok(())
+}
+
 fn collect_sink_delays(tree: &Tree, node: NodeIdx, sink_delays: &mut Vec<f64>) {
     if tree.get(node).is_leaf() {
         sink_delays.push(tree.get(node).delay);
@@ -746,6 +1294,30 @@ fn collect_sink_delays(tree: &Tree, node: NodeIdx, sink_delays: &mut Vec<f64>) {
     }
 }
 
+fn collect_sink_slews(tree: &Tree, node: NodeIdx, sink_slews: &mut Vec<f64>) {
+    if tree.get(node).is_leaf() {
+        sink_slews.push(tree.get(node).slew);
+    }
+    if let Some(l) = tree.get(node).left {
+        collect_sink_slews(tree, l, sink_slews);
+    }
+    if let Some(r) = tree.get(node).right {
+        collect_sink_slews(tree, r, sink_slews);
+    }
+}
+
+fn collect_slew_violations(tree: &Tree, node: NodeIdx, limit: f64, out: &mut Vec<NodeIdx>) {
+    if tree.get(node).slew > limit {
+        out.push(node);
+    }
+    if let Some(l) = tree.get(node).left {
+        collect_slew_violations(tree, l, limit, out);
+    }
+    if let Some(r) = tree.get(node).right {
+        collect_slew_violations(tree, r, limit, out);
+    }
+}
+
 fn total_wirelength(tree: &Tree, node: NodeIdx) -> i32 {
     let mut total = tree.get(node).wire_length;
     if let Some(l) = tree.get(node).left {
@@ -1044,6 +1616,312 @@ mod tests {
         assert!(!r_el);
     }
 
+    #[test]
+    fn test_peri_slew_matches_delay() {
+        let sinks = make_sinks(8);
+        let calc = Box::new(ElmoreDelayCalculator::new(0.1, 0.1));
+        let (dme, analysis) = run_tree(sinks, calc);
+        assert_eq!(analysis.sink_slews.len(), analysis.sink_delays.len());
+        for (slew, delay) in analysis.sink_slews.iter().zip(&analysis.sink_delays) {
+            approx_eq(*slew, SLEW_LN9 * delay);
+        }
+        let root = dme.get_tree().root.unwrap();
+        assert_eq!(dme.get_tree().get(root).slew, 0.0);
+        assert!(analysis.slew_violations.is_empty());
+    }
+
+    #[test]
+    fn test_cascaded_slew_with_source_slew() {
+        let sinks = vec![
+            Sink::new("s1", Point::new(0, 0), 1.0),
+            Sink::new("s2", Point::new(10, 0), 1.0),
+        ];
+        let mut dme = DMEAlgorithm::new(sinks, Box::new(LinearDelayCalculator::new(1.0, 0.1)));
+        dme.set_slew_model(SlewModel::Cascaded);
+        dme.set_source_slew(3.0);
+        let root = dme.build_clock_tree();
+        let analysis = dme.analyze_skew(root);
+        // Each sink hangs off a 5-unit wire with delay 5.0.
+        let expected = (9.0 + (SLEW_LN9 * 5.0).powi(2)).sqrt();
+        for slew in &analysis.sink_slews {
+            approx_eq(*slew, expected);
+        }
+        approx_eq(analysis.worst_slew, expected);
+    }
+
+    #[test]
+    fn test_peri_slew_hand_computed() {
+        // Two pairs 10 apart horizontally, 20 apart vertically: each sink has
+        // a 5-unit wire to its pair's merge point and a 10-unit wire above.
+        let sinks = vec![
+            Sink::new("s1", Point::new(0, 0), 1.0),
+            Sink::new("s2", Point::new(10, 0), 1.0),
+            Sink::new("s3", Point::new(0, 20), 1.0),
+            Sink::new("s4", Point::new(10, 20), 1.0),
+        ];
+        let slews = |model| {
+            let mut dme = DMEAlgorithm::new(
+                sinks.clone(),
+                Box::new(LinearDelayCalculator::new(1.0, 0.1)),
+            );
+            dme.set_slew_model(model);
+            dme.set_source_slew(3.0);
+            let root = dme.build_clock_tree();
+            let analysis = dme.analyze_skew(root);
+            for delay in &analysis.sink_delays {
+                approx_eq(*delay, 15.0);
+            }
+            analysis.sink_slews
+        };
+        // PERI: ln 9 times the full 15-unit delay, RSS with the source ramp
+        let peri = (9.0f64 + (SLEW_LN9 * 15.0f64).powi(2)).sqrt();
+        for slew in slews(SlewModel::Peri) {
+            approx_eq(slew, peri);
+        }
+        let cascaded = (9.0 + (SLEW_LN9 * 10.0).powi(2) + (SLEW_LN9 * 5.0).powi(2)).sqrt();
+        for slew in slews(SlewModel::Cascaded) {
+            approx_eq(slew, cascaded);
+        }
+        assert!(cascaded < peri);
+    }
+
+    #[test]
+    fn test_max_slew_violations() {
+        let sinks = vec![
+            Sink::new("s1", Point::new(0, 0), 1.0),
+            Sink::new("s2", Point::new(10, 0), 1.0),
+            Sink::new("s3", Point::new(100, 0), 1.0),
+        ];
+        let mut dme = DMEAlgorithm::new(sinks, Box::new(LinearDelayCalculator::new(1.0, 0.1)));
+        dme.set_max_slew(Some(20.0));
+        let root = dme.build_clock_tree();
+        let violations = dme.slew_violations(root);
+        assert!(!violations.is_empty());
+        for &idx in &violations {
+            assert!(dme.get_tree().get(idx).slew > 20.0);
+        }
+        let analysis = dme.analyze_skew(root);
+        assert_eq!(analysis.slew_violations.len(), violations.len());
+
+        dme.set_max_slew(None);
+        assert!(dme.slew_violations(root).is_empty());
+    }
+
+    #[test]
+    fn test_try_new_rejects_bad_sinks() {
+        let calc = || Box::new(LinearDelayCalculator::new(0.5, 0.1));
+        assert_eq!(
+            DMEAlgorithm::try_new(vec![], calc()).err(),
+            Some(DmeError::EmptySinks)
+        );
+
+        let dup = vec![
+            Sink::new("s1", Point::new(0, 0), 1.0),
+            Sink::new("s1", Point::new(5, 0), 1.0),
+        ];
+        assert_eq!(
+            DMEAlgorithm::try_new(dup, calc()).err(),
+            Some(DmeError::DuplicateSinkName("s1".to_string()))
+        );
+
+        let same_pos = vec![
+            Sink::new("a", Point::new(3, 3), 1.0),
+            Sink::new("b", Point::new(3, 3), 1.0),
+        ];
+        assert!(matches!(
+            DMEAlgorithm::try_with_source(same_pos, calc(), Point::new(0, 0)),
+            Err(DmeError::CoincidentSinks { .. })
+        ));
+
+        for bad in [f64::NAN, -1.0, f64::INFINITY] {
+            let sinks = vec![Sink::new("s", Point::new(0, 0), bad)];
+            assert!(matches!(
+                DMEAlgorithm::try_new(sinks, calc()),
+                Err(DmeError::InvalidCapacitance { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_try_build_and_analyze() {
+        let mut dme = DMEAlgorithm::try_new(
+            make_sinks(8),
+            Box::new(LinearDelayCalculator::new(0.5, 0.1)),
+        )
+        .unwrap();
+        let root = dme.try_build_clock_tree().unwrap();
+        let analysis = dme.try_analyze_skew(root).unwrap();
+        assert_eq!(analysis.sink_delays.len(), 8);
+        assert!(dme.try_analyze_skew(dme.get_tree().len()).is_err());
+    }
+
+    #[test]
+    fn test_try_build_rejects_bad_sinks_added_via_new() {
+        let sinks = vec![
+            Sink::new("a", Point::new(0, 0), 1.0),
+            Sink::new("a", Point::new(1, 0), 1.0),
+        ];
+        let mut dme = DMEAlgorithm::new(sinks, Box::new(LinearDelayCalculator::new(0.5, 0.1)));
+        assert_eq!(
+            dme.try_build_clock_tree(),
+            Err(DmeError::DuplicateSinkName("a".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_try_analyze_detects_malformed_tree() {
+        let mut dme = DMEAlgorithm::new(
+            make_sinks(4),
+            Box::new(LinearDelayCalculator::new(0.5, 0.1)),
+        );
+        let root = dme.build_clock_tree();
+
+        // Drop one child: internal node with a single child.
+        let mut broken = dme.get_tree().clone();
+        broken.get_mut(root).right = None;
+        *dme.get_tree_mut() = broken;
+        assert!(matches!(
+            dme.try_analyze_skew(root),
+            Err(DmeError::MalformedTopology(_))
+        ));
+
+        // Create a cycle: a leaf pointing back at the root.
+        let root = dme.build_clock_tree();
+        let leaf = dme.get_tree().get(root).left.unwrap();
+        let leaf = dme.get_tree().get(leaf).left.unwrap();
+        dme.get_tree_mut().get_mut(leaf).left = Some(root);
+        dme.get_tree_mut().get_mut(leaf).right = Some(root);
+        assert!(matches!(
+            dme.try_analyze_skew(root),
+            Err(DmeError::MalformedTopology(_))
+        ));
+    }
+
+    #[test]
+    fn test_try_build_clustered_rejects_bad_partition() {
+        let mut dme = DMEAlgorithm::new(
+            make_sinks(4),
+            Box::new(LinearDelayCalculator::new(0.5, 0.1)),
+        );
+        let missing = vec![vec![0, 1], vec![2]];
+        assert!(matches!(
+            dme.try_build_clustered_clock_tree(&missing),
+            Err(DmeError::MalformedTopology(_))
+        ));
+        let repeated = vec![vec![0, 1], vec![1, 2, 3]];
+        assert!(dme.try_build_clustered_clock_tree(&repeated).is_err());
+        let out_of_range = vec![vec![0, 1, 2, 3, 9]];
+        assert!(dme.try_build_clustered_clock_tree(&out_of_range).is_err());
+        let ok = vec![vec![0, 1], vec![2, 3]];
+        assert!(dme.try_build_clustered_clock_tree(&ok).is_ok());
+    }
+
+    #[test]
+    fn test_clusters_embedded_locally() {
+        let cluster_a = vec![
+            Sink::new("a0", Point::new(0, 0), 1.0),
+            Sink::new("a1", Point::new(10, 0), 1.0),
+            Sink::new("a2", Point::new(0, 30), 1.0),
+            Sink::new("a3", Point::new(10, 30), 2.0),
+        ];
+        let cluster_b = vec![
+            Sink::new("b0", Point::new(500, 400), 1.0),
+            Sink::new("b1", Point::new(540, 400), 3.0),
+        ];
+        let mut sinks = cluster_a.clone();
+        sinks.extend(cluster_b.clone());
+        let mut dme = DMEAlgorithm::new(sinks, Box::new(LinearDelayCalculator::new(1.0, 0.1)));
+        let root = dme.build_clustered_clock_tree(&[vec![0, 1, 2, 3], vec![4, 5]]);
+
+        fn subtree_wirelength(tree: &Tree, node: NodeIdx) -> i32 {
+            let n = tree.get(node);
+            [n.left, n.right]
+                .iter()
+                .flatten()
+                .map(|&c| tree.get(c).wire_length + subtree_wirelength(tree, c))
+                .sum()
+        }
+        // Each cluster's subtree is exactly the tree DME builds for it alone
+        for (&local, alone) in dme.cluster_roots().iter().zip([cluster_a, cluster_b]) {
+            let (flat, analysis) = run_tree(alone, Box::new(LinearDelayCalculator::new(1.0, 0.1)));
+            let flat_root = flat.get_tree().root.unwrap();
+            assert_eq!(
+                subtree_wirelength(dme.get_tree(), local),
+                subtree_wirelength(flat.get_tree(), flat_root)
+            );
+            assert_eq!(analysis.skew, 0.0);
+        }
+        let analysis = dme.analyze_skew(root);
+        assert!(analysis.skew.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_source_node_and_root_wire() {
+        let sinks = make_sinks(8);
+        let calc = Box::new(ElmoreDelayCalculator::new(0.1, 0.2));
+        let mut dme = DMEAlgorithm::with_source(sinks, calc, Point::new(-100, -100));
+        dme.set_driver_resistance(2.0);
+        let root = dme.build_clock_tree();
+        let tree = dme.get_tree();
+
+        let src = tree.source.expect("source node");
+        assert_eq!(tree.get(src).left, Some(root));
+        assert_eq!(tree.get(root).parent, Some(src));
+        let root_wire = tree.get(root).wire_length;
+        assert_eq!(
+            root_wire,
+            tree.get(root)
+                .position
+                .min_dist_with(&Point::new(-100, -100)) as i32
+        );
+        let src_cap = tree.get(root).capacitance + 0.2 * root_wire as f64;
+        approx_eq(tree.get(src).capacitance, src_cap);
+        approx_eq(tree.get(src).delay, 2.0 * src_cap);
+
+        let analysis = dme.analyze_skew(root);
+        assert!(analysis.source_latency > tree.get(src).delay);
+        let mut delays = Vec::new();
+        collect_sink_delays(tree, root, &mut delays);
+        let first = delays[0] - analysis.source_latency;
+        approx_eq(analysis.sink_delays[0], first);
+        assert_eq!(
+            analysis.total_wirelength,
+            total_wirelength(tree, root) - root_wire
+        );
+        assert!(analysis.skew / (analysis.max_delay + analysis.source_latency) < 0.02);
+        assert_eq!(dme.verify(root), vec![]);
+
+        let mut no_source = DMEAlgorithm::new(
+            make_sinks(8),
+            Box::new(ElmoreDelayCalculator::new(0.1, 0.2)),
+        );
+        let root = no_source.build_clock_tree();
+        assert!(no_source.get_tree().source.is_none());
+        assert_eq!(no_source.analyze_skew(root).source_latency, 0.0);
+    }
+
+    #[test]
+    fn test_min_latency_root_embedding() {
+        let build = |embedding: RootEmbedding| {
+            let calc = Box::new(ElmoreDelayCalculator::new(0.1, 0.2));
+            let mut dme = DMEAlgorithm::with_source(make_sinks(16), calc, Point::new(500, 50));
+            dme.set_driver_resistance(1.0);
+            dme.set_root_embedding(embedding);
+            let root = dme.build_clock_tree();
+            assert_eq!(dme.verify(root), vec![]);
+            let analysis = dme.analyze_skew(root);
+            approx_eq(
+                analysis.max_latency,
+                analysis.max_delay + analysis.source_latency,
+            );
+            analysis
+        };
+        let nearest = build(RootEmbedding::Nearest);
+        let min_latency = build(RootEmbedding::MinLatency);
+        assert!(min_latency.source_latency <= nearest.source_latency + 1e-9);
+        assert!(min_latency.max_latency <= nearest.max_latency + 1e-9);
+    }
+
     /// Helper: approximate float equality within 1e-9.
     fn approx_eq(a: f64, b: f64) {
         assert!((a - b).abs() < 1e-9, "left={}, right={}", a, b);
@@ -0,0 +1,704 @@
+//! Design rule checking on layered rectangles and rectilinear polygons.
+//!
+//! A `Layout` holds named layers of shapes; a `RuleDeck` holds named rules.
+//! `RuleDeck::check` reports every violation as a `Violation` with the rule
+//! name and a marker rectangle locating it.
+//!
+//! The shapes of a layer are merged first (`vlsi_ops::union_rectangles`),
+//! so rules see the drawn geometry rather than how it was entered: two
+//! abutting rectangles form one wire. The merged geometry is split into
+//! connected components (`vlsi_ops::OverlapGraph`) and indexed with an
+//! `RTree` for neighbour queries. Rules:
+//!
+//! - **Min width**: every horizontal and vertical run of material is at least
+//!   `width` long; the marker is the narrow part.
+//! - **Min spacing**: distinct components are at least `spacing` apart,
+//!   measured between closest edges or, for diagonal neighbours, corner to
+//!   corner in the chosen `SpacingMetric`; the markers cover the gaps.
+//! - **Min area**: every component has at least `area`; the marker is its
+//!   bounding box.
+//! - **Min enclosure**: the `outer` layer covers the `inner` layer grown by
+//!   `enclosure` on all sides; the marker is the grown inner piece.
+//! - **End-of-line spacing**: a line end, i.e. an edge shorter than
+//!   `eol_width` with convex corners at both ends, has no material within
+//!   `spacing` in front of it; the marker spans the gap.
+//! - **Density**: the covered fraction of every `window` square, stepped by
+//!   `step` over the layout's bounding box, lies in `[min, max]`; the marker
+//!   is the window.
+
+use std::collections::HashMap;
+
+use crate::point::Point;
+use crate::rpolygon::RPolygon;
+use crate::rpolygon_cut::rpolygon_cut_slabs;
+use crate::rtree::RTree;
+use crate::vlsi_ops::{
+    bounding_rect, density_windows, total_area, union_rectangles, OverlapGraph, Rectangle,
+};
+
+/// How corner-to-corner spacing is measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpacingMetric {
+    /// Straight-line distance between the corners
+    #[default]
+    Euclidean,
+    /// Sum of the x and y gaps
+    Manhattan,
+}
+
+/// The check a rule performs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleKind {
+    /// Minimum width of the material on `layer`
+    MinWidth { layer: String, width: i32 },
+    /// Minimum spacing between shapes on `layer`
+    MinSpacing {
+        layer: String,
+        spacing: i32,
+        metric: SpacingMetric,
+    },
+    /// Minimum area of each shape on `layer`
+    MinArea { layer: String, area: i64 },
+    /// Minimum enclosure of `inner` by `outer`
+    MinEnclosure {
+        inner: String,
+        outer: String,
+        enclosure: i32,
+    },
+    /// Minimum spacing in front of line ends narrower than `eol_width`
+    EndOfLine {
+        layer: String,
+        eol_width: i32,
+        spacing: i32,
+    },
+    /// Density range in square windows of side `window`, stepped by `step`
+    Density {
+        layer: String,
+        window: i32,
+        step: i32,
+        min: f64,
+        max: f64,
+    },
+}
+
+/// A named design rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    /// Rule name reported with violations
+    pub name: String,
+    /// What is checked
+    pub kind: RuleKind,
+}
+
+/// A rule violation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Name of the violated rule
+    pub rule: String,
+    /// Layer of the offending shapes (the inner layer for enclosure rules)
+    pub layer: String,
+    /// Region of the violation
+    pub marker: Rectangle<i32>,
+}
+
+/// Layered shapes to check.
+#[derive(Debug, Clone, Default)]
+pub struct Layout {
+    layers: HashMap<String, Vec<Rectangle<i32>>>,
+}
+
+impl Layout {
+    pub fn new() -> Self {
+        Layout::default()
+    }
+
+    /// Adds a rectangle to `layer`.
+    pub fn add_rect(&mut self, layer: &str, rect: Rectangle<i32>) {
+        self.layers.entry(layer.to_string()).or_default().push(rect);
+    }
+
+    /// Adds a rectilinear polygon to `layer`, which may be given in
+    /// compressed form (consecutive vertices joined horizontally first).
+    pub fn add_polygon(&mut self, layer: &str, polygon: &RPolygon<i32>) {
+        let shapes = self.layers.entry(layer.to_string()).or_default();
+        for piece in rpolygon_cut_slabs(polygon) {
+            shapes.push(Rectangle::new(
+                Point::new(piece.xcoord.lb, piece.ycoord.lb),
+                Point::new(piece.xcoord.ub, piece.ycoord.ub),
+            ));
+        }
+    }
+
+    /// Rectangles on `layer` as added (polygons cut into rectangles).
+    pub fn shapes(&self, layer: &str) -> &[Rectangle<i32>] {
+        self.layers.get(layer).map_or(&[], |v| v.as_slice())
+    }
+
+    /// Bounding box of all layers, or `None` for an empty layout.
+    pub fn bounding_box(&self) -> Option<Rectangle<i32>> {
+        let boxes: Vec<Rectangle<i32>> = self
+            .layers
+            .values()
+            .filter_map(|shapes| bounding_rect(shapes))
+            .collect();
+        bounding_rect(&boxes)
+    }
+}
+
+/// Converts to `i64` coordinates so areas cannot overflow.
+fn widen(rect: &Rectangle<i32>) -> Rectangle<i64> {
+    Rectangle::new(
+        Point::new(rect.min.xcoord as i64, rect.min.ycoord as i64),
+        Point::new(rect.max.xcoord as i64, rect.max.ycoord as i64),
+    )
+}
+
+/// Converts back a rectangle lying within `i32` geometry.
+fn narrow(rect: &Rectangle<i64>) -> Rectangle<i32> {
+    Rectangle::new(
+        Point::new(rect.min.xcoord as i32, rect.min.ycoord as i32),
+        Point::new(rect.max.xcoord as i32, rect.max.ycoord as i32),
+    )
+}
+
+fn transpose(rect: &Rectangle<i32>) -> Rectangle<i32> {
+    Rectangle::new(
+        Point::new(rect.min.ycoord, rect.min.xcoord),
+        Point::new(rect.max.ycoord, rect.max.xcoord),
+    )
+}
+
+/// Interior overlap: a shared region of positive area.
+fn overlaps_interior(a: &Rectangle<i32>, b: &Rectangle<i32>) -> bool {
+    a.min.xcoord < b.max.xcoord
+        && b.min.xcoord < a.max.xcoord
+        && a.min.ycoord < b.max.ycoord
+        && b.min.ycoord < a.max.ycoord
+}
+
+/// Gap between two intervals, or the shared part if they meet.
+fn gap(alo: i32, ahi: i32, blo: i32, bhi: i32) -> (i32, i32) {
+    if ahi < blo {
+        (ahi, blo)
+    } else if bhi < alo {
+        (bhi, alo)
+    } else {
+        (alo.max(blo), ahi.min(bhi))
+    }
+}
+
+/// Merged geometry of one layer.
+struct LayerGeometry {
+    /// Union as rectangles with disjoint interiors, cut into x slabs
+    pieces: Vec<Rectangle<i32>>,
+    /// Component of each piece
+    component: Vec<usize>,
+    components: Vec<Vec<usize>>,
+    index: RTree<i32>,
+}
+
+impl LayerGeometry {
+    fn new(shapes: &[Rectangle<i32>]) -> Self {
+        let pieces = union_rectangles(shapes);
+        let components = OverlapGraph::new(&pieces).components();
+        let mut component = vec![0; pieces.len()];
+        for (c, group) in components.iter().enumerate() {
+            for &p in group {
+                component[p] = c;
+            }
+        }
+        let index = RTree::bulk_load(&pieces);
+        LayerGeometry {
+            pieces,
+            component,
+            components,
+            index,
+        }
+    }
+
+    /// Returns `true` if material covers part of `rect` with positive area.
+    fn covers_any(&self, rect: &Rectangle<i32>) -> bool {
+        self.index
+            .query(rect)
+            .iter()
+            .any(|&p| overlaps_interior(&self.pieces[p], rect))
+    }
+}
+
+/// Narrow vertical runs of `pieces` (x slabs): pieces lower than `width`.
+fn narrow_runs(pieces: &[Rectangle<i32>], width: i32) -> Vec<Rectangle<i32>> {
+    pieces
+        .iter()
+        .filter(|p| p.max.ycoord - p.min.ycoord < width)
+        .cloned()
+        .collect()
+}
+
+/// Gaps in front of the top and bottom line ends of `geometry`.
+fn eol_gaps(geometry: &LayerGeometry, eol_width: i32, spacing: i32) -> Vec<Rectangle<i32>> {
+    let cell = |x0: i32, x1: i32, y0: i32, y1: i32| {
+        geometry.covers_any(&Rectangle::new(Point::new(x0, y0), Point::new(x1, y1)))
+    };
+    let mut gaps = Vec::new();
+    for p in &geometry.pieces {
+        let (xl, xr) = (p.min.xcoord, p.max.xcoord);
+        if xr - xl >= eol_width {
+            continue;
+        }
+        // Top edge: convex corners when the cells beside its ends are empty
+        let (yb, yt) = (p.min.ycoord, p.max.ycoord);
+        if !cell(xl - 1, xl, yt - 1, yt) && !cell(xr, xr + 1, yt - 1, yt) {
+            let front = Rectangle::new(Point::new(xl, yt), Point::new(xr, yt + spacing));
+            let hit = geometry
+                .index
+                .query(&front)
+                .into_iter()
+                .map(|q| &geometry.pieces[q])
+                .filter(|q| overlaps_interior(q, &front))
+                .map(|q| q.min.ycoord)
+                .min();
+            if let Some(y) = hit {
+                gaps.push(Rectangle::new(Point::new(xl, yt), Point::new(xr, y)));
+            }
+        }
+        if !cell(xl - 1, xl, yb, yb + 1) && !cell(xr, xr + 1, yb, yb + 1) {
+            let front = Rectangle::new(Point::new(xl, yb - spacing), Point::new(xr, yb));
+            let hit = geometry
+                .index
+                .query(&front)
+                .into_iter()
+                .map(|q| &geometry.pieces[q])
+                .filter(|q| overlaps_interior(q, &front))
+                .map(|q| q.max.ycoord)
+                .max();
+            if let Some(y) = hit {
+                gaps.push(Rectangle::new(Point::new(xl, y), Point::new(xr, yb)));
+            }
+        }
+    }
+    gaps
+}
+
+/// An ordered collection of rules.
+#[derive(Debug, Clone, Default)]
+pub struct RuleDeck {
+    rules: Vec<Rule>,
+}
+
+impl RuleDeck {
+    pub fn new() -> Self {
+        RuleDeck::default()
+    }
+
+    /// Adds a rule.
+    pub fn add(&mut self, name: &str, kind: RuleKind) -> &mut Self {
+        self.rules.push(Rule {
+            name: name.to_string(),
+            kind,
+        });
+        self
+    }
+
+    /// The rules in insertion order.
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Checks `layout` against every rule.
+    ///
+    /// Violations are grouped by rule in deck order; within a rule they are
+    /// sorted by marker and duplicates are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use physdes::drc::{Layout, RuleDeck, RuleKind, SpacingMetric};
+    /// use physdes::{Point, vlsi_ops::Rectangle};
+    ///
+    /// let mut layout = Layout::new();
+    /// layout.add_rect("M1", Rectangle::new(Point::new(0, 0), Point::new(10, 10)));
+    /// layout.add_rect("M1", Rectangle::new(Point::new(13, 13), Point::new(23, 23)));
+    ///
+    /// let mut deck = RuleDeck::new();
+    /// deck.add(
+    ///     "M1.S.1",
+    ///     RuleKind::MinSpacing {
+    ///         layer: "M1".to_string(),
+    ///         spacing: 5,
+    ///         metric: SpacingMetric::Euclidean,
+    ///     },
+    /// );
+    /// // The corners are sqrt(18) apart
+    /// let violations = deck.check(&layout);
+    /// assert_eq!(violations.len(), 1);
+    /// assert_eq!(
+    ///     violations[0].marker,
+    ///     Rectangle::new(Point::new(10, 10), Point::new(13, 13))
+    /// );
+    /// ```
+    pub fn check(&self, layout: &Layout) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for rule in &self.rules {
+            let (layer, mut markers) = self.run(rule, layout);
+            markers.sort_by_key(|r| (r.min.xcoord, r.min.ycoord, r.max.xcoord, r.max.ycoord));
+            markers.dedup();
+            violations.extend(markers.into_iter().map(|marker| Violation {
+                rule: rule.name.clone(),
+                layer: layer.to_string(),
+                marker,
+            }));
+        }
+        violations
+    }
+
+    fn run<'a>(&self, rule: &'a Rule, layout: &Layout) -> (&'a str, Vec<Rectangle<i32>>) {
+        match &rule.kind {
+            RuleKind::MinWidth { layer, width } => {
+                let shapes = layout.shapes(layer);
+                let mut markers = narrow_runs(&union_rectangles(shapes), *width);
+                let transposed: Vec<Rectangle<i32>> = shapes.iter().map(transpose).collect();
+                markers.extend(
+                    narrow_runs(&union_rectangles(&transposed), *width)
+                        .iter()
+                        .map(transpose),
+                );
+                (layer, markers)
+            }
+            RuleKind::MinSpacing {
+                layer,
+                spacing,
+                metric,
+            } => {
+                let geometry = LayerGeometry::new(layout.shapes(layer));
+                let mut markers = Vec::new();
+                for (a, pa) in geometry.pieces.iter().enumerate() {
+                    let window = Rectangle::new(
+                        Point::new(pa.min.xcoord - spacing, pa.min.ycoord - spacing),
+                        Point::new(pa.max.xcoord + spacing, pa.max.ycoord + spacing),
+                    );
+                    for b in geometry.index.query(&window) {
+                        if b <= a || geometry.component[a] == geometry.component[b] {
+                            continue;
+                        }
+                        let pb = &geometry.pieces[b];
+                        let (x0, x1) =
+                            gap(pa.min.xcoord, pa.max.xcoord, pb.min.xcoord, pb.max.xcoord);
+                        let (y0, y1) =
+                            gap(pa.min.ycoord, pa.max.ycoord, pb.min.ycoord, pb.max.ycoord);
+                        let dx = if pa.max.xcoord < pb.min.xcoord || pb.max.xcoord < pa.min.xcoord {
+                            (x1 - x0) as i64
+                        } else {
+                            0
+                        };
+                        let dy = if pa.max.ycoord < pb.min.ycoord || pb.max.ycoord < pa.min.ycoord {
+                            (y1 - y0) as i64
+                        } else {
+                            0
+                        };
+                        let s = *spacing as i64;
+                        let too_close = match metric {
+                            SpacingMetric::Euclidean => dx * dx + dy * dy < s * s,
+                            SpacingMetric::Manhattan => dx + dy < s,
+                        };
+                        if too_close {
+                            markers.push(Rectangle::new(Point::new(x0, y0), Point::new(x1, y1)));
+                        }
+                    }
+                }
+                // Join the gaps of neighbouring slabs into one marker
+                (layer, union_rectangles(&markers))
+            }
+            RuleKind::MinArea { layer, area } => {
+                let geometry = LayerGeometry::new(layout.shapes(layer));
+                let markers = geometry
+                    .components
+                    .iter()
+                    .filter_map(|group| {
+                        let pieces: Vec<Rectangle<i32>> =
+                            group.iter().map(|&p| geometry.pieces[p].clone()).collect();
+                        let covered: i64 = pieces.iter().map(|p| widen(p).area()).sum();
+                        (covered < *area).then(|| bounding_rect(&pieces).unwrap())
+                    })
+                    .collect();
+                (layer, markers)
+            }
+            RuleKind::MinEnclosure {
+                inner,
+                outer,
+                enclosure,
+            } => {
+                let outer_shapes = union_rectangles(layout.shapes(outer));
+                let index = RTree::bulk_load(&outer_shapes);
+                let markers = union_rectangles(layout.shapes(inner))
+                    .iter()
+                    .map(|p| {
+                        Rectangle::new(
+                            Point::new(p.min.xcoord - enclosure, p.min.ycoord - enclosure),
+                            Point::new(p.max.xcoord + enclosure, p.max.ycoord + enclosure),
+                        )
+                    })
+                    .filter(|grown| {
+                        let clipped: Vec<Rectangle<i64>> = index
+                            .query(grown)
+                            .into_iter()
+                            .filter_map(|o| outer_shapes[o].intersect(grown))
+                            .map(|c| widen(&c))
+                            .collect();
+                        total_area(&clipped) < widen(grown).area()
+                    })
+                    .collect();
+                (inner, markers)
+            }
+            RuleKind::EndOfLine {
+                layer,
+                eol_width,
+                spacing,
+            } => {
+                let shapes = layout.shapes(layer);
+                let mut markers = eol_gaps(&LayerGeometry::new(shapes), *eol_width, *spacing);
+                let transposed: Vec<Rectangle<i32>> = shapes.iter().map(transpose).collect();
+                markers.extend(
+                    eol_gaps(&LayerGeometry::new(&transposed), *eol_width, *spacing)
+                        .iter()
+                        .map(transpose),
+                );
+                (layer, markers)
+            }
+            RuleKind::Density {
+                layer,
+                window,
+                step,
+                min,
+                max,
+            } => {
+                let markers = match layout.bounding_box().map(|r| widen(&r)) {
+                    Some(region) if region.area() > 0 => {
+                        let pieces: Vec<Rectangle<i64>> = union_rectangles(layout.shapes(layer))
+                            .iter()
+                            .map(widen)
+                            .collect();
+                        density_windows(&pieces, &region, *window as i64, *step as i64)
+                            .into_iter()
+                            .filter(|(_, d)| d < min || d > max)
+                            .map(|(w, _)| narrow(&w))
+                            .collect()
+                    }
+                    _ => Vec::new(),
+                };
+                (layer, markers)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x1: i32, y1: i32, x2: i32, y2: i32) -> Rectangle<i32> {
+        Rectangle::new(Point::new(x1, y1), Point::new(x2, y2))
+    }
+
+    fn markers(deck: &RuleDeck, layout: &Layout) -> Vec<(String, Rectangle<i32>)> {
+        deck.check(layout)
+            .into_iter()
+            .map(|v| (v.rule, v.marker))
+            .collect()
+    }
+
+    #[test]
+    fn test_width_spacing_and_area() {
+        let mut layout = Layout::new();
+        // Two abutting rectangles form one 40 x 10 wire
+        layout.add_rect("M1", rect(0, 0, 20, 10));
+        layout.add_rect("M1", rect(20, 0, 40, 10));
+        // An L-shaped polygon with a 4 wide arm
+        layout.add_polygon(
+            "M1",
+            &RPolygon::new(&[
+                Point::new(0, 20),
+                Point::new(30, 20),
+                Point::new(30, 24),
+                Point::new(10, 24),
+                Point::new(10, 40),
+                Point::new(0, 40),
+            ]),
+        );
+        // A small square diagonal to the wire's corner
+        layout.add_rect("M1", rect(43, 13, 46, 16));
+
+        let mut deck = RuleDeck::new();
+        deck.add(
+            "W",
+            RuleKind::MinWidth {
+                layer: "M1".to_string(),
+                width: 5,
+            },
+        )
+        .add(
+            "S",
+            RuleKind::MinSpacing {
+                layer: "M1".to_string(),
+                spacing: 11,
+                metric: SpacingMetric::Euclidean,
+            },
+        )
+        .add(
+            "A",
+            RuleKind::MinArea {
+                layer: "M1".to_string(),
+                area: 50,
+            },
+        );
+        assert_eq!(
+            markers(&deck, &layout),
+            vec![
+                ("W".to_string(), rect(10, 20, 30, 24)),
+                ("W".to_string(), rect(43, 13, 46, 16)),
+                // Wire to L above it, then the square's corner gap
+                ("S".to_string(), rect(0, 10, 30, 20)),
+                ("S".to_string(), rect(40, 10, 43, 13)),
+                ("A".to_string(), rect(43, 13, 46, 16)),
+            ]
+        );
+
+        // Corner to corner the square is 3 + 3 away in Manhattan distance
+        let mut manhattan = RuleDeck::new();
+        manhattan.add(
+            "S",
+            RuleKind::MinSpacing {
+                layer: "M1".to_string(),
+                spacing: 6,
+                metric: SpacingMetric::Manhattan,
+            },
+        );
+        assert!(manhattan.check(&layout).is_empty());
+        manhattan.add(
+            "S2",
+            RuleKind::MinSpacing {
+                layer: "M1".to_string(),
+                spacing: 5,
+                metric: SpacingMetric::Euclidean,
+            },
+        );
+        assert_eq!(
+            markers(&manhattan, &layout),
+            vec![("S2".to_string(), rect(40, 10, 43, 13))]
+        );
+    }
+
+    #[test]
+    fn test_add_compressed_polygon() {
+        // The L shape of `test_width_spacing_and_area` with implied corners
+        let mut layout = Layout::new();
+        layout.add_polygon(
+            "M1",
+            &RPolygon::new(&[
+                Point::new(0, 20),
+                Point::new(30, 24),
+                Point::new(10, 40),
+                Point::new(0, 40),
+            ]),
+        );
+        assert_eq!(
+            layout.shapes("M1"),
+            &[rect(0, 20, 30, 24), rect(0, 24, 10, 40)]
+        );
+    }
+
+    #[test]
+    fn test_enclosure_eol_and_density() {
+        let mut layout = Layout::new();
+        layout.add_rect("M1", rect(0, 0, 40, 40));
+        layout.add_rect("V1", rect(10, 10, 14, 14));
+        layout.add_rect("V1", rect(36, 10, 40, 14));
+        // A vertical 4 wide line ending 3 below a horizontal wire
+        layout.add_rect("M2", rect(50, 0, 54, 30));
+        layout.add_rect("M2", rect(45, 33, 70, 43));
+
+        let mut deck = RuleDeck::new();
+        deck.add(
+            "ENC",
+            RuleKind::MinEnclosure {
+                inner: "V1".to_string(),
+                outer: "M1".to_string(),
+                enclosure: 2,
+            },
+        )
+        .add(
+            "EOL",
+            RuleKind::EndOfLine {
+                layer: "M2".to_string(),
+                eol_width: 6,
+                spacing: 5,
+            },
+        )
+        .add(
+            "DEN",
+            RuleKind::Density {
+                layer: "M1".to_string(),
+                window: 35,
+                step: 35,
+                min: 0.2,
+                max: 1.0,
+            },
+        );
+        let violations = deck.check(&layout);
+        assert_eq!(violations[0].layer, "V1");
+        assert_eq!(
+            violations
+                .iter()
+                .map(|v| (v.rule.as_str(), v.marker.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("ENC", rect(34, 8, 42, 16)),
+                ("EOL", rect(50, 30, 54, 33)),
+                ("DEN", rect(35, 0, 70, 35)),
+                ("DEN", rect(35, 8, 70, 43)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_area_rules_large_coordinates() {
+        // Areas of 50 000 DBU squares overflow i32
+        let mut layout = Layout::new();
+        layout.add_rect("M1", rect(0, 0, 50_000, 50_000));
+        layout.add_rect("V1", rect(10, 10, 49_990, 49_990));
+        layout.add_rect("M2", rect(50_000, 0, 100_000, 50_000));
+
+        let mut deck = RuleDeck::new();
+        deck.add(
+            "A",
+            RuleKind::MinArea {
+                layer: "M1".to_string(),
+                area: 3_000_000_000,
+            },
+        )
+        .add(
+            "ENC",
+            RuleKind::MinEnclosure {
+                inner: "V1".to_string(),
+                outer: "M1".to_string(),
+                enclosure: 10,
+            },
+        )
+        .add(
+            "DEN",
+            RuleKind::Density {
+                layer: "M1".to_string(),
+                window: 50_000,
+                step: 50_000,
+                min: 0.5,
+                max: 1.0,
+            },
+        );
+        assert_eq!(
+            markers(&deck, &layout),
+            vec![
+                ("A".to_string(), rect(0, 0, 50_000, 50_000)),
+                ("DEN".to_string(), rect(50_000, 0, 100_000, 50_000)),
+            ]
+        );
+    }
+}
@@ -4,6 +4,8 @@
 
 use std::fmt;
 
+use crate::global_router::NodeId;
+
 /// Error type for geometric operations
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum GeomError {
@@ -58,6 +60,214 @@ impl std::error::Error for GeomError {}
 /// A specialized `Result` type for geometric operations that may return a `GeomError`.
 pub type GeomResult<T> = Result<T, GeomError>;
 
+/// Error type for DME clock tree construction and analysis
+#[derive(Debug, Clone, PartialEq)]
+pub enum DmeError {
+    /// No sinks were provided
+    EmptySinks,
+
+    /// Two sinks share the same name
+    DuplicateSinkName(String),
+
+    /// Two sinks are placed at the same position
+    CoincidentSinks {
+        /// Name of the first sink
+        first: String,
+        /// Name of the second sink
+        second: String,
+    },
+
+    /// A sink capacitance is NaN, infinite or negative
+    InvalidCapacitance {
+        /// Name of the sink
+        sink: String,
+        /// The offending capacitance
+        value: f64,
+    },
+
+    /// The tree topology is malformed (missing children, broken links,
+    /// cycles, or a partition that does not cover the sinks)
+    MalformedTopology(String),
+
+    /// No sink delays could be collected from the tree
+    NoSinkDelays,
+}
+
+impl fmt::Display for DmeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DmeError::EmptySinks => write!(f, "No sinks provided"),
+            DmeError::DuplicateSinkName(name) => write!(f, "Duplicate sink name: {}", name),
+            DmeError::CoincidentSinks { first, second } => {
+                write!(f, "Coincident sinks: {} and {}", first, second)
+            }
+            DmeError::InvalidCapacitance { sink, value } => {
+                write!(f, "Invalid capacitance {} for sink {}", value, sink)
+            }
+            DmeError::MalformedTopology(msg) => write!(f, "Malformed topology: {}", msg),
+            DmeError::NoSinkDelays => write!(f, "No sink delays collected"),
+        }
+    }
+}
+
+impl std::error::Error for DmeError {}
+
+/// A specialized `Result` type for DME operations that may return a `DmeError`.
+pub type DmeResult<T> = Result<T, DmeError>;
+
+/// Error type for routing tree edits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoutingError {
+    /// No node with this handle exists (it may have been removed)
+    NodeNotFound(NodeId),
+
+    /// The operation would detach or reparent the source
+    SourceNode,
+
+    /// Both handles name the same node
+    SameNode(NodeId),
+
+    /// The new parent lies in the subtree of the node being moved
+    WouldCreateCycle {
+        /// The node being moved
+        node: NodeId,
+        /// The requested parent, a descendant of `node`
+        new_parent: NodeId,
+    },
+
+    /// The nodes to merge are at different positions
+    NotCoincident {
+        /// First node passed to the merge
+        first: NodeId,
+        /// Second node passed to the merge
+        second: NodeId,
+    },
+
+    /// Both nodes to merge are pins (source or terminal)
+    TwoPins {
+        /// First node passed to the merge
+        first: NodeId,
+        /// Second node passed to the merge
+        second: NodeId,
+    },
+
+    /// The split point is outside the bounding box of the edge
+    OffEdge(NodeId),
+}
+
+impl fmt::Display for RoutingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoutingError::NodeNotFound(id) => write!(f, "Node not found: {}", id),
+            RoutingError::SourceNode => write!(f, "Operation not allowed on the source"),
+            RoutingError::SameNode(id) => write!(f, "Both operands are node {}", id),
+            RoutingError::WouldCreateCycle { node, new_parent } => {
+                write!(
+                    f,
+                    "Moving {} under {} would create a cycle",
+                    node, new_parent
+                )
+            }
+            RoutingError::NotCoincident { first, second } => {
+                write!(f, "Nodes {} and {} are not coincident", first, second)
+            }
+            RoutingError::TwoPins { first, second } => {
+                write!(f, "Nodes {} and {} are both pins", first, second)
+            }
+            RoutingError::OffEdge(id) => {
+                write!(f, "Split point is off the edge into node {}", id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RoutingError {}
+
+/// A specialized `Result` type for routing tree edits that may return a `RoutingError`.
+pub type RoutingResult<T> = Result<T, RoutingError>;
+
+/// Error type for netlist construction
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetlistError {
+    /// Two nets share the same name
+    DuplicateNet(String),
+
+    /// A net has no sinks
+    EmptyNet(String),
+
+    /// A net weight or criticality is NaN, infinite or negative
+    InvalidWeight {
+        /// Name of the net
+        net: String,
+        /// The offending weight or criticality
+        value: f64,
+    },
+}
+
+impl fmt::Display for NetlistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetlistError::DuplicateNet(name) => write!(f, "Duplicate net name: {}", name),
+            NetlistError::EmptyNet(name) => write!(f, "Net {} has no sinks", name),
+            NetlistError::InvalidWeight { net, value } => {
+                write!(f, "Invalid weight {} for net {}", value, net)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NetlistError {}
+
+/// A specialized `Result` type for netlist construction that may return a `NetlistError`.
+pub type NetlistResult<T> = Result<T, NetlistError>;
+
+/// Error type for importing routed nets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportError {
+    /// The input is not well-formed JSON
+    Syntax {
+        /// Line of the error in the input, starting at 1
+        line: usize,
+        /// Column of the error in the line, starting at 1
+        column: usize,
+        /// What was expected or found
+        message: String,
+    },
+
+    /// A field is missing or has the wrong type
+    Schema(String),
+
+    /// Two nodes share the same id
+    DuplicateNode(String),
+
+    /// An edge refers to a node that does not exist
+    UnknownNode(String),
+
+    /// The nodes and edges do not form a tree rooted at the source
+    InvalidTree(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Syntax {
+                line,
+                column,
+                message,
+            } => write!(f, "Syntax error at {}:{}: {}", line, column, message),
+            ImportError::Schema(msg) => write!(f, "Schema error: {}", msg),
+            ImportError::DuplicateNode(id) => write!(f, "Duplicate node id: {}", id),
+            ImportError::UnknownNode(id) => write!(f, "Unknown node id: {}", id),
+            ImportError::InvalidTree(msg) => write!(f, "Invalid tree: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// A specialized `Result` type for imports that may return an `ImportError`.
+pub type ImportResult<T> = Result<T, ImportError>;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -92,6 +302,50 @@ mod tests {
         assert_eq!(format!("{}", err), "Numerical error: division by zero");
     }
 
+    #[test]
+    fn test_dme_error_display() {
+        assert_eq!(format!("{}", DmeError::EmptySinks), "No sinks provided");
+        assert_eq!(
+            format!("{}", DmeError::DuplicateSinkName("s1".to_string())),
+            "Duplicate sink name: s1"
+        );
+        let err = DmeError::CoincidentSinks {
+            first: "a".to_string(),
+            second: "b".to_string(),
+        };
+        assert_eq!(format!("{}", err), "Coincident sinks: a and b");
+        let err = DmeError::InvalidCapacitance {
+            sink: "s2".to_string(),
+            value: -1.0,
+        };
+        assert_eq!(format!("{}", err), "Invalid capacitance -1 for sink s2");
+    }
+
+    #[test]
+    fn test_netlist_error_display() {
+        let err = NetlistError::DuplicateNet("n1".to_string());
+        assert_eq!(format!("{}", err), "Duplicate net name: n1");
+        let err = NetlistError::EmptyNet("n2".to_string());
+        assert_eq!(format!("{}", err), "Net n2 has no sinks");
+        let err = NetlistError::InvalidWeight {
+            net: "n3".to_string(),
+            value: -2.0,
+        };
+        assert_eq!(format!("{}", err), "Invalid weight -2 for net n3");
+    }
+
+    #[test]
+    fn test_import_error_display() {
+        let err = ImportError::Syntax {
+            line: 1,
+            column: 7,
+            message: "expected ':'".to_string(),
+        };
+        assert_eq!(format!("{}", err), "Syntax error at 1:7: expected ':'");
+        let err = ImportError::UnknownNode("steiner_9".to_string());
+        assert_eq!(format!("{}", err), "Unknown node id: steiner_9");
+    }
+
     #[test]
     fn test_invalid_point_display() {
         let err = GeomError::InvalidPoint("negative coordinates".to_string());
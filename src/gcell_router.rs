@@ -0,0 +1,738 @@
+//! Multi-net global routing on a GCell grid with capacities.
+//!
+//! The layout is divided into a grid of global cells (GCells). Adjacent
+//! GCells are joined by a horizontal or vertical routing edge with a track
+//! capacity. Nets are routed as follows:
+//!
+//! 1. Each net is decomposed into two-pin connections along the MST of its
+//!    pin GCells, and each connection is pattern routed with the cheapest L
+//!    or Z shape.
+//! 2. Negotiation-based rip-up and reroute (PathFinder): every net crossing
+//!    an overflowing edge is ripped up and maze routed again. Overflowing
+//!    edges accumulate a history cost and the present-congestion penalty
+//!    grows each iteration, so nets negotiate for contested edges.
+//!
+//! The result of each net is a `GlobalRoutingTree` whose Steiner nodes are
+//! the GCell centres at bends and branch points.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use crate::global_router::GlobalRoutingTree;
+use crate::interval::Interval;
+use crate::point::Point;
+use crate::rsmt::mst;
+
+/// A grid of global routing cells with per-edge capacities.
+#[derive(Debug, Clone)]
+pub struct GCellGrid {
+    /// Lower-left corner of GCell (0, 0)
+    pub origin: Point<i32, i32>,
+    /// Width of a GCell
+    pub gcell_width: i32,
+    /// Height of a GCell
+    pub gcell_height: i32,
+    /// Number of GCell columns
+    pub cols: usize,
+    /// Number of GCell rows
+    pub rows: usize,
+    /// Capacity of each edge: horizontal edges first, then vertical
+    capacity: Vec<u32>,
+}
+
+impl GCellGrid {
+    /// Creates a grid with uniform horizontal and vertical edge capacities.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the grid is empty or a GCell dimension is not positive.
+    pub fn new(
+        origin: Point<i32, i32>,
+        gcell_width: i32,
+        gcell_height: i32,
+        cols: usize,
+        rows: usize,
+        horizontal_capacity: u32,
+        vertical_capacity: u32,
+    ) -> Self {
+        assert!(cols > 0 && rows > 0, "Grid must have at least one GCell");
+        assert!(
+            gcell_width > 0 && gcell_height > 0,
+            "GCell dimensions must be positive"
+        );
+        let num_h = (cols - 1) * rows;
+        let num_v = cols * (rows - 1);
+        let mut capacity = vec![horizontal_capacity; num_h];
+        capacity.extend(std::iter::repeat(vertical_capacity).take(num_v));
+        GCellGrid {
+            origin,
+            gcell_width,
+            gcell_height,
+            cols,
+            rows,
+            capacity,
+        }
+    }
+
+    fn num_horizontal_edges(&self) -> usize {
+        (self.cols - 1) * self.rows
+    }
+
+    /// Total number of routing edges.
+    pub fn num_edges(&self) -> usize {
+        self.capacity.len()
+    }
+
+    /// Index of the horizontal edge between GCells `(col, row)` and
+    /// `(col + 1, row)`.
+    pub fn horizontal_edge(&self, col: usize, row: usize) -> usize {
+        row * (self.cols - 1) + col
+    }
+
+    /// Index of the vertical edge between GCells `(col, row)` and
+    /// `(col, row + 1)`.
+    pub fn vertical_edge(&self, col: usize, row: usize) -> usize {
+        self.num_horizontal_edges() + row * self.cols + col
+    }
+
+    /// Returns `true` if `edge` is a horizontal edge.
+    pub fn is_horizontal(&self, edge: usize) -> bool {
+        edge < self.num_horizontal_edges()
+    }
+
+    /// Capacity of an edge.
+    pub fn capacity(&self, edge: usize) -> u32 {
+        self.capacity[edge]
+    }
+
+    /// Sets the capacity of an edge.
+    pub fn set_capacity(&mut self, edge: usize, capacity: u32) {
+        self.capacity[edge] = capacity;
+    }
+
+    /// Sets the capacity of every edge between two GCells whose centres lie
+    /// inside `region` to zero, e.g. for a routing blockage.
+    pub fn block(&mut self, region: &Point<Interval<i32>, Interval<i32>>) {
+        use crate::generic::Contain;
+
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                if !region.contains(&self.center(col, row)) {
+                    continue;
+                }
+                if col + 1 < self.cols && region.contains(&self.center(col + 1, row)) {
+                    let e = self.horizontal_edge(col, row);
+                    self.capacity[e] = 0;
+                }
+                if row + 1 < self.rows && region.contains(&self.center(col, row + 1)) {
+                    let e = self.vertical_edge(col, row);
+                    self.capacity[e] = 0;
+                }
+            }
+        }
+    }
+
+    /// The region covered by the grid.
+    pub fn extent(&self) -> Point<Interval<i32>, Interval<i32>> {
+        Point::new(
+            Interval::new(
+                self.origin.xcoord,
+                self.origin.xcoord + self.cols as i32 * self.gcell_width,
+            ),
+            Interval::new(
+                self.origin.ycoord,
+                self.origin.ycoord + self.rows as i32 * self.gcell_height,
+            ),
+        )
+    }
+
+    /// The GCell containing `point`, clamped to the grid.
+    pub fn gcell_of(&self, point: &Point<i32, i32>) -> (usize, usize) {
+        let col = (point.xcoord - self.origin.xcoord).div_euclid(self.gcell_width);
+        let row = (point.ycoord - self.origin.ycoord).div_euclid(self.gcell_height);
+        (
+            col.clamp(0, self.cols as i32 - 1) as usize,
+            row.clamp(0, self.rows as i32 - 1) as usize,
+        )
+    }
+
+    /// Centre of GCell `(col, row)`.
+    pub fn center(&self, col: usize, row: usize) -> Point<i32, i32> {
+        Point::new(
+            self.origin.xcoord + col as i32 * self.gcell_width + self.gcell_width / 2,
+            self.origin.ycoord + row as i32 * self.gcell_height + self.gcell_height / 2,
+        )
+    }
+
+    /// Length of an edge: the GCell pitch in its direction.
+    pub fn edge_length(&self, edge: usize) -> i32 {
+        if self.is_horizontal(edge) {
+            self.gcell_width
+        } else {
+            self.gcell_height
+        }
+    }
+
+    fn cell_id(&self, col: usize, row: usize) -> usize {
+        row * self.cols + col
+    }
+
+    fn cell_coords(&self, cell: usize) -> (usize, usize) {
+        (cell % self.cols, cell / self.cols)
+    }
+
+    /// Neighbouring cells of `cell` with the connecting edge.
+    fn neighbors(&self, cell: usize) -> Vec<(usize, usize)> {
+        let (col, row) = self.cell_coords(cell);
+        let mut out = Vec::with_capacity(4);
+        if col > 0 {
+            out.push((cell - 1, self.horizontal_edge(col - 1, row)));
+        }
+        if col + 1 < self.cols {
+            out.push((cell + 1, self.horizontal_edge(col, row)));
+        }
+        if row > 0 {
+            out.push((cell - self.cols, self.vertical_edge(col, row - 1)));
+        }
+        if row + 1 < self.rows {
+            out.push((cell + self.cols, self.vertical_edge(col, row)));
+        }
+        out
+    }
+
+    /// The two cells joined by `edge`.
+    fn edge_cells(&self, edge: usize) -> (usize, usize) {
+        if self.is_horizontal(edge) {
+            let (row, col) = (edge / (self.cols - 1), edge % (self.cols - 1));
+            let a = self.cell_id(col, row);
+            (a, a + 1)
+        } else {
+            let e = edge - self.num_horizontal_edges();
+            let (row, col) = (e / self.cols, e % self.cols);
+            let a = self.cell_id(col, row);
+            (a, a + self.cols)
+        }
+    }
+}
+
+/// A net to be routed on the grid.
+#[derive(Debug, Clone)]
+pub struct GridNet {
+    /// Net name
+    pub name: String,
+    /// Driver position
+    pub source: Point<i32, i32>,
+    /// Sink positions
+    pub terminals: Vec<Point<i32, i32>>,
+}
+
+impl GridNet {
+    pub fn new(name: &str, source: Point<i32, i32>, terminals: Vec<Point<i32, i32>>) -> Self {
+        GridNet {
+            name: name.to_string(),
+            source,
+            terminals,
+        }
+    }
+}
+
+/// Parameters of negotiation-based rip-up and reroute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NegotiationConfig {
+    /// Maximum number of rip-up and reroute iterations
+    pub max_iterations: usize,
+    /// History cost added to an edge for each iteration it overflows
+    pub history_increment: f64,
+    /// Initial weight of the present-congestion penalty
+    pub present_factor: f64,
+    /// Factor by which the present-congestion weight grows per iteration
+    pub present_growth: f64,
+}
+
+impl Default for NegotiationConfig {
+    fn default() -> Self {
+        NegotiationConfig {
+            max_iterations: 30,
+            history_increment: 1.0,
+            present_factor: 0.5,
+            present_growth: 1.5,
+        }
+    }
+}
+
+/// Result of routing all nets on the grid.
+#[derive(Clone)]
+pub struct GridRoutingResult {
+    /// Routing tree of each net, in input order
+    pub trees: Vec<GlobalRoutingTree>,
+    /// Grid edges used by each net, in input order
+    pub net_edges: Vec<Vec<usize>>,
+    /// Number of nets using each edge
+    pub usage: Vec<u32>,
+    /// Sum over edges of usage in excess of capacity
+    pub total_overflow: u32,
+    /// Largest overflow on any single edge
+    pub max_overflow: u32,
+    /// Total grid wirelength (edges weighted by GCell pitch)
+    pub wirelength: i64,
+    /// Number of rip-up and reroute iterations performed
+    pub iterations: usize,
+}
+
+/// Multi-net global router on a `GCellGrid`.
+pub struct GridGlobalRouter {
+    grid: GCellGrid,
+    nets: Vec<GridNet>,
+    /// Negotiation parameters
+    pub config: NegotiationConfig,
+}
+
+/// Dijkstra queue entry ordered by increasing cost.
+#[derive(PartialEq)]
+struct QueueEntry {
+    cost: f64,
+    cell: usize,
+}
+
+impl Eq for QueueEntry {}
+
+impl Ord for QueueEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.cell.cmp(&self.cell))
+    }
+}
+
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Congestion state shared by pattern and maze routing.
+struct CongestionCost<'a> {
+    grid: &'a GCellGrid,
+    usage: Vec<u32>,
+    history: Vec<f64>,
+    present_factor: f64,
+}
+
+impl CongestionCost<'_> {
+    /// PathFinder cost of adding one more net to `edge`:
+    ///
+    /// $$c_e = (1 + h_e)\left(1 + p \cdot \max(0,\; u_e + 1 - C_e)\right)$$
+    fn edge_cost(&self, edge: usize) -> f64 {
+        let excess = (self.usage[edge] + 1).saturating_sub(self.grid.capacity(edge));
+        (1.0 + self.history[edge]) * (1.0 + self.present_factor * excess as f64)
+    }
+
+    fn overflow(&self) -> (u32, u32) {
+        self.usage
+            .iter()
+            .enumerate()
+            .map(|(e, &u)| u.saturating_sub(self.grid.capacity(e)))
+            .fold((0, 0), |(total, max), o| (total + o, max.max(o)))
+    }
+
+    fn add(&mut self, edges: &[usize]) {
+        for &e in edges {
+            self.usage[e] += 1;
+        }
+    }
+
+    fn remove(&mut self, edges: &[usize]) {
+        for &e in edges {
+            self.usage[e] -= 1;
+        }
+    }
+}
+
+impl GridGlobalRouter {
+    pub fn new(grid: GCellGrid, nets: Vec<GridNet>) -> Self {
+        GridGlobalRouter {
+            grid,
+            nets,
+            config: NegotiationConfig::default(),
+        }
+    }
+
+    /// Returns the routing grid.
+    pub fn grid(&self) -> &GCellGrid {
+        &self.grid
+    }
+
+    /// Routes all nets: pattern routing followed by negotiated rip-up and
+    /// reroute until there is no overflow or the iteration limit is reached.
+    pub fn route(&self) -> GridRoutingResult {
+        let grid = &self.grid;
+        let mut cost = CongestionCost {
+            grid,
+            usage: vec![0; grid.num_edges()],
+            history: vec![0.0; grid.num_edges()],
+            present_factor: self.config.present_factor,
+        };
+
+        let pin_cells: Vec<Vec<usize>> = self.nets.iter().map(|n| self.net_cells(n)).collect();
+        let mut net_edges: Vec<Vec<usize>> = pin_cells
+            .iter()
+            .map(|cells| {
+                let edges = self.pattern_route(cells, &cost);
+                cost.add(&edges);
+                edges
+            })
+            .collect();
+
+        let mut iterations = 0;
+        while iterations < self.config.max_iterations {
+            let (total, _) = cost.overflow();
+            if total == 0 {
+                break;
+            }
+            iterations += 1;
+            let overflowing: Vec<bool> = (0..grid.num_edges())
+                .map(|e| cost.usage[e] > grid.capacity(e))
+                .collect();
+            for (e, &over) in overflowing.iter().enumerate() {
+                if over {
+                    cost.history[e] += self.config.history_increment;
+                }
+            }
+            cost.present_factor *= self.config.present_growth;
+
+            for (i, cells) in pin_cells.iter().enumerate() {
+                if !net_edges[i].iter().any(|&e| overflowing[e]) {
+                    continue;
+                }
+                cost.remove(&net_edges[i]);
+                net_edges[i] = self.maze_route(cells, &cost);
+                cost.add(&net_edges[i]);
+            }
+        }
+
+        let (total_overflow, max_overflow) = cost.overflow();
+        let wirelength = net_edges
+            .iter()
+            .flatten()
+            .map(|&e| grid.edge_length(e) as i64)
+            .sum();
+        let trees = self
+            .nets
+            .iter()
+            .zip(&net_edges)
+            .map(|(net, edges)| self.build_tree(net, edges))
+            .collect();
+
+        GridRoutingResult {
+            trees,
+            net_edges,
+            usage: cost.usage,
+            total_overflow,
+            max_overflow,
+            wirelength,
+            iterations,
+        }
+    }
+
+    /// Distinct pin cells of a net, source cell first.
+    fn net_cells(&self, net: &GridNet) -> Vec<usize> {
+        let mut cells: Vec<usize> = Vec::new();
+        for p in std::iter::once(&net.source).chain(&net.terminals) {
+            let (c, r) = self.grid.gcell_of(p);
+            let id = self.grid.cell_id(c, r);
+            if !cells.contains(&id) {
+                cells.push(id);
+            }
+        }
+        cells
+    }
+
+    /// Routes the MST connections of the pin cells with the cheapest L or Z
+    /// pattern each. Returns the distinct edges used.
+    fn pattern_route(&self, cells: &[usize], cost: &CongestionCost) -> Vec<usize> {
+        let pts: Vec<Point<i32, i32>> = cells
+            .iter()
+            .map(|&c| {
+                let (col, row) = self.grid.cell_coords(c);
+                Point::new(col as i32, row as i32)
+            })
+            .collect();
+        let mut used = HashSet::new();
+        for (a, b) in mst(&pts).1 {
+            let path = self.best_pattern(cells[a], cells[b], cost);
+            used.extend(path);
+        }
+        let mut edges: Vec<usize> = used.into_iter().collect();
+        edges.sort_unstable();
+        edges
+    }
+
+    /// Cheapest of all L and Z shapes between two cells.
+    fn best_pattern(&self, from: usize, to: usize, cost: &CongestionCost) -> Vec<usize> {
+        let (c0, r0) = self.grid.cell_coords(from);
+        let (c1, r1) = self.grid.cell_coords(to);
+        let mut best: Option<(f64, Vec<usize>)> = None;
+        let mut consider = |path: Vec<usize>| {
+            let c: f64 = path.iter().map(|&e| cost.edge_cost(e)).sum();
+            if best.as_ref().map_or(true, |(bc, _)| c < *bc) {
+                best = Some((c, path));
+            }
+        };
+        // Horizontal-vertical-horizontal, bending at column `m`.
+        for m in c0.min(c1)..=c0.max(c1) {
+            let mut path = self.horizontal_run(r0, c0, m);
+            path.extend(self.vertical_run(m, r0, r1));
+            path.extend(self.horizontal_run(r1, m, c1));
+            consider(path);
+        }
+        // Vertical-horizontal-vertical, bending at row `m`.
+        for m in r0.min(r1)..=r0.max(r1) {
+            let mut path = self.vertical_run(c0, r0, m);
+            path.extend(self.horizontal_run(m, c0, c1));
+            path.extend(self.vertical_run(c1, m, r1));
+            consider(path);
+        }
+        best.map(|(_, p)| p).unwrap_or_default()
+    }
+
+    fn horizontal_run(&self, row: usize, a: usize, b: usize) -> Vec<usize> {
+        (a.min(b)..a.max(b))
+            .map(|c| self.grid.horizontal_edge(c, row))
+            .collect()
+    }
+
+    fn vertical_run(&self, col: usize, a: usize, b: usize) -> Vec<usize> {
+        (a.min(b)..a.max(b))
+            .map(|r| self.grid.vertical_edge(col, r))
+            .collect()
+    }
+
+    /// Maze routes a net: pins are connected one at a time by Dijkstra from
+    /// every cell already in the net's tree to the nearest unconnected pin.
+    fn maze_route(&self, cells: &[usize], cost: &CongestionCost) -> Vec<usize> {
+        let n = self.grid.cols * self.grid.rows;
+        let mut in_tree = vec![false; n];
+        in_tree[cells[0]] = true;
+        let mut remaining: HashSet<usize> = cells[1..].iter().copied().collect();
+        let mut edges = Vec::new();
+
+        while !remaining.is_empty() {
+            let mut dist = vec![f64::INFINITY; n];
+            let mut prev: Vec<Option<(usize, usize)>> = vec![None; n];
+            let mut heap = BinaryHeap::new();
+            for (cell, _) in in_tree.iter().enumerate().filter(|(_, &t)| t) {
+                dist[cell] = 0.0;
+                heap.push(QueueEntry { cost: 0.0, cell });
+            }
+            let mut reached = None;
+            while let Some(QueueEntry { cost: d, cell }) = heap.pop() {
+                if d > dist[cell] {
+                    continue;
+                }
+                if remaining.contains(&cell) {
+                    reached = Some(cell);
+                    break;
+                }
+                for (next, edge) in self.grid.neighbors(cell) {
+                    let nd = d + cost.edge_cost(edge);
+                    if nd < dist[next] {
+                        dist[next] = nd;
+                        prev[next] = Some((cell, edge));
+                        heap.push(QueueEntry {
+                            cost: nd,
+                            cell: next,
+                        });
+                    }
+                }
+            }
+            let target = reached.expect("Grid is connected");
+            remaining.remove(&target);
+            let mut cell = target;
+            while !in_tree[cell] {
+                in_tree[cell] = true;
+                let (p, e) = prev[cell].expect("Path back to the tree");
+                edges.push(e);
+                cell = p;
+            }
+        }
+        edges.sort_unstable();
+        edges
+    }
+
+    /// Converts a net's grid edges into a `GlobalRoutingTree`: Steiner nodes
+    /// at GCell centres where the route bends or branches, and terminals at
+    /// their pin positions hanging off their GCell's node.
+    fn build_tree(&self, net: &GridNet, edges: &[usize]) -> GlobalRoutingTree {
+        let grid = &self.grid;
+        let mut adjacency: HashMap<usize, Vec<usize>> = HashMap::new();
+        for &e in edges {
+            let (a, b) = grid.edge_cells(e);
+            adjacency.entry(a).or_default().push(b);
+            adjacency.entry(b).or_default().push(a);
+        }
+        let cell_of = |p: &Point<i32, i32>| {
+            let (c, r) = grid.gcell_of(p);
+            grid.cell_id(c, r)
+        };
+        let mut pins_at: HashMap<usize, Vec<Point<i32, i32>>> = HashMap::new();
+        for t in &net.terminals {
+            pins_at.entry(cell_of(t)).or_default().push(*t);
+        }
+
+        // Spanning tree of the routed cells, rooted at the source cell.
+        let root = cell_of(&net.source);
+        let mut parent: HashMap<usize, usize> = HashMap::new();
+        let mut order = vec![root];
+        let mut seen: HashSet<usize> = HashSet::from([root]);
+        let mut i = 0;
+        while i < order.len() {
+            let v = order[i];
+            i += 1;
+            for &u in adjacency.get(&v).map(Vec::as_slice).unwrap_or(&[]) {
+                if seen.insert(u) {
+                    parent.insert(u, v);
+                    order.push(u);
+                }
+            }
+        }
+        // Keep only cells on the way from a pin cell to the root.
+        let mut needed: HashSet<usize> = HashSet::from([root]);
+        for &cell in pins_at.keys() {
+            let mut c = cell;
+            while needed.insert(c) {
+                match parent.get(&c) {
+                    Some(&p) => c = p,
+                    None => break,
+                }
+            }
+        }
+        let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+        for &v in &order {
+            if v != root && needed.contains(&v) {
+                children.entry(parent[&v]).or_default().push(v);
+            }
+        }
+
+        let mut tree = GlobalRoutingTree::new(net.source);
+        let root_center = {
+            let (c, r) = grid.cell_coords(root);
+            grid.center(c, r)
+        };
+        let root_id = if root_center == net.source {
+            "source".to_string()
+        } else {
+            tree.insert_steiner_node(root_center, Some("source"))
+        };
+
+        let direction = |a: usize, b: usize| {
+            let (ca, ra) = grid.cell_coords(a);
+            let (cb, rb) = grid.cell_coords(b);
+            (cb as i64 - ca as i64, rb as i64 - ra as i64)
+        };
+        // (cell, id of the node its route hangs off, incoming direction)
+        let mut stack = vec![(root, root_id, None)];
+        while let Some((cell, hang_id, incoming)) = stack.pop() {
+            let kids = children.get(&cell).cloned().unwrap_or_default();
+            let pins = pins_at.get(&cell).cloned().unwrap_or_default();
+            let straight = kids.len() == 1 && Some(direction(cell, kids[0])) == incoming;
+            let node_id = if incoming.is_none() || !pins.is_empty() || !straight {
+                if incoming.is_none() {
+                    hang_id
+                } else {
+                    let (c, r) = grid.cell_coords(cell);
+                    tree.insert_steiner_node(grid.center(c, r), Some(&hang_id))
+                }
+            } else {
+                hang_id
+            };
+            for p in pins {
+                tree.insert_terminal_node(p, Some(&node_id));
+            }
+            for k in kids {
+                stack.push((k, node_id.clone(), Some(direction(cell, k))));
+            }
+        }
+        tree.update_path_lengths();
+        tree.worst_wirelength = tree.calculate_worst_wirelength();
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(cols: usize, rows: usize, cap: u32) -> GCellGrid {
+        GCellGrid::new(Point::new(0, 0), 10, 10, cols, rows, cap, cap)
+    }
+
+    #[test]
+    fn test_grid_indexing() {
+        let g = grid(4, 3, 2);
+        assert_eq!(g.num_edges(), 3 * 3 + 4 * 2);
+        assert_eq!(g.gcell_of(&Point::new(25, 5)), (2, 0));
+        assert_eq!(g.gcell_of(&Point::new(-5, 100)), (0, 2));
+        assert_eq!(g.center(1, 1), Point::new(15, 15));
+        let e = g.vertical_edge(3, 1);
+        assert!(!g.is_horizontal(e));
+        assert_eq!(g.edge_cells(e), (g.cell_id(3, 1), g.cell_id(3, 2)));
+        let h = g.horizontal_edge(2, 2);
+        assert_eq!(g.edge_cells(h), (g.cell_id(2, 2), g.cell_id(3, 2)));
+    }
+
+    #[test]
+    fn test_single_net_pattern_route() {
+        let net = GridNet::new(
+            "n0",
+            Point::new(5, 5),
+            vec![Point::new(45, 5), Point::new(45, 35)],
+        );
+        let router = GridGlobalRouter::new(grid(5, 4, 1), vec![net]);
+        let result = router.route();
+        assert_eq!(result.total_overflow, 0);
+        assert_eq!(result.wirelength, 70);
+        assert_eq!(result.iterations, 0);
+        let tree = &result.trees[0];
+        assert_eq!(tree.get_all_terminals().len(), 2);
+        assert_eq!(tree.calculate_total_wirelength(), 70);
+    }
+
+    #[test]
+    fn test_negotiation_removes_overflow() {
+        // Three parallel nets share one row of capacity-one edges; the
+        // negotiation must spread them over neighbouring rows.
+        let nets: Vec<GridNet> = (0..3)
+            .map(|i| {
+                GridNet::new(
+                    &format!("n{}", i),
+                    Point::new(5, 25),
+                    vec![Point::new(75, 25)],
+                )
+            })
+            .collect();
+        let router = GridGlobalRouter::new(grid(8, 5, 1), nets);
+        let result = router.route();
+        assert_eq!(result.total_overflow, 0);
+        assert_eq!(result.max_overflow, 0);
+        assert!(result.iterations > 0);
+        assert!(result.wirelength > 3 * 70);
+        for (edges, tree) in result.net_edges.iter().zip(&result.trees) {
+            let grid_wl: i32 = edges.len() as i32 * 10;
+            assert_eq!(tree.calculate_total_wirelength(), grid_wl);
+        }
+    }
+
+    #[test]
+    fn test_blocked_region_reports_overflow() {
+        let mut g = grid(3, 1, 1);
+        g.block(&Point::new(Interval::new(0, 30), Interval::new(0, 10)));
+        let net = GridNet::new("n0", Point::new(5, 5), vec![Point::new(25, 5)]);
+        let mut router = GridGlobalRouter::new(g, vec![net]);
+        router.config.max_iterations = 3;
+        let result = router.route();
+        assert_eq!(result.total_overflow, 2);
+        assert_eq!(result.max_overflow, 1);
+        assert_eq!(result.iterations, 3);
+    }
+}
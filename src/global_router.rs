@@ -8,12 +8,20 @@
 use std::collections::HashMap;
 use std::fmt;
 
+use crate::error::{RoutingError, RoutingResult};
 use crate::generic::{Contain, MinDist};
 use crate::interval::{Hull, Interval};
+use crate::keepout::KeepoutSet;
+use crate::maze_router::MazeRouter;
 use crate::point::Point;
 
 /// Type of a routing node.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum NodeType {
     Steiner,
     Terminal,
@@ -30,11 +38,26 @@ impl fmt::Display for NodeType {
     }
 }
 
+/// Stable handle of a node in a `GlobalRoutingTree`.
+///
+/// Unlike vector indices, a `NodeId` remains valid when other nodes are
+/// removed, and it is never reused within a tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(u32);
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
 /// A node in the routing tree.
 #[derive(Debug, Clone)]
 pub struct RoutingNode {
     /// Unique identifier for this node
     pub id: String,
+    /// Stable handle, assigned when the node is added to a tree
+    handle: Option<NodeId>,
     /// Type of this node (Source, Steiner, or Terminal)
     pub node_type: NodeType,
     /// Position of this node in the layout
@@ -43,12 +66,16 @@ pub struct RoutingNode {
     pub children: Vec<usize>,
     /// Index of the parent node, if any
     pub parent: Option<usize>,
-    /// Load capacitance at this node
+    /// Downstream load capacitance at this node
     pub capacitance: f64,
     /// Signal delay at this node
     pub delay: f64,
     /// Path length from source to this node
     pub path_length: i32,
+    /// Pin capacitance of a terminal (zero for other nodes)
+    pub pin_capacitance: f64,
+    /// Metal layer index (0 for a purely 2D tree)
+    pub layer: usize,
 }
 
 impl RoutingNode {
@@ -56,6 +83,7 @@ impl RoutingNode {
     pub fn new(id: &str, node_type: NodeType, pt: Point<i32, i32>) -> Self {
         RoutingNode {
             id: id.to_string(),
+            handle: None,
             node_type,
             pt,
             children: Vec::new(),
@@ -63,9 +91,17 @@ impl RoutingNode {
             capacitance: 0.0,
             delay: 0.0,
             path_length: 0,
+            pin_capacitance: 0.0,
+            layer: 0,
         }
     }
 
+    /// Returns the stable handle of this node, or `None` if it has not been
+    /// added to a tree.
+    pub fn handle(&self) -> Option<NodeId> {
+        self.handle
+    }
+
     /// Computes the Manhattan distance to another routing node.
     ///
     /// $$d = |x_1 - x_2| + |y_1 - y_2|$$
@@ -74,10 +110,34 @@ impl RoutingNode {
     }
 }
 
+/// Electrical parameters for Elmore delay analysis of a routing tree.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ElmoreParams {
+    /// Output resistance of the driver at the source
+    pub driver_resistance: f64,
+    /// Wire resistance per unit length
+    pub unit_resistance: f64,
+    /// Wire capacitance per unit length
+    pub unit_capacitance: f64,
+}
+
+impl ElmoreParams {
+    pub fn new(driver_resistance: f64, unit_resistance: f64, unit_capacitance: f64) -> Self {
+        ElmoreParams {
+            driver_resistance,
+            unit_resistance,
+            unit_capacitance,
+        }
+    }
+}
+
 /// A rectilinear Steiner routing tree with support for keepout avoidance.
+#[derive(Clone)]
 pub struct GlobalRoutingTree {
     nodes: Vec<RoutingNode>,
     node_map: HashMap<String, usize>,
+    handles: HashMap<NodeId, usize>,
+    next_handle: u32,
     source_idx: usize,
     next_steiner_id: i32,
     next_terminal_id: i32,
@@ -87,7 +147,8 @@ pub struct GlobalRoutingTree {
 
 impl GlobalRoutingTree {
     pub fn new(source_position: Point<i32, i32>) -> Self {
-        let source = RoutingNode::new("source", NodeType::Source, source_position);
+        let mut source = RoutingNode::new("source", NodeType::Source, source_position);
+        source.handle = Some(NodeId(0));
         let mut nodes = Vec::new();
         let mut node_map = HashMap::new();
         node_map.insert("source".to_string(), 0usize);
@@ -95,6 +156,8 @@ impl GlobalRoutingTree {
         GlobalRoutingTree {
             nodes,
             node_map,
+            handles: HashMap::from([(NodeId(0), 0usize)]),
+            next_handle: 1,
             source_idx: 0,
             next_steiner_id: 1,
             next_terminal_id: 1,
@@ -112,8 +175,12 @@ impl GlobalRoutingTree {
         &mut self.nodes[self.source_idx]
     }
 
-    fn add_node(&mut self, node: RoutingNode) -> usize {
+    fn add_node(&mut self, mut node: RoutingNode) -> usize {
         let idx = self.nodes.len();
+        let handle = NodeId(self.next_handle);
+        node.handle = Some(handle);
+        self.next_handle += 1;
+        self.handles.insert(handle, idx);
         self.node_map.insert(node.id.clone(), idx);
         self.nodes.push(node);
         idx
@@ -229,14 +296,15 @@ impl GlobalRoutingTree {
         id
     }
 
-    /// Find the nearest insertion point for a terminal, avoiding keepouts.
+    /// Find the nearest insertion point for a terminal, avoiding hard
+    /// keepouts and adding the penalties of soft ones to the distance.
     /// Returns `(parent_node_idx, nearest_node_idx)` where parent_node is `Some` when a Steiner
     /// point needs to be inserted on the branch between parent and nearest.
     fn _find_insertion_point(
         &self,
         point: Point<i32, i32>,
         allowed_wirelength: i32,
-        keepouts: &Option<Vec<Point<Interval<i32>, Interval<i32>>>>,
+        keepouts: &KeepoutSet,
     ) -> (Option<usize>, usize) {
         let mut nearest_node = self.source_idx;
         let mut parent_node: Option<usize> = None;
@@ -252,33 +320,25 @@ impl GlobalRoutingTree {
             for ci in (0..child_count).rev() {
                 let child_idx = self.nodes[node_idx].children[ci];
                 let possible_path = self.nodes[node_idx].pt.hull_with(&self.nodes[child_idx].pt);
-                let distance = possible_path.min_dist_with(&point) as i32;
+                let mut distance = possible_path.min_dist_with(&point) as i32;
                 let nearest_pt = possible_path.nearest_to(&point);
 
                 // Check keepouts
-                if let Some(ref kos) = *keepouts {
-                    let mut blocked = false;
-                    let path1 = nearest_pt.hull_with(&point);
-                    let path2 = nearest_pt.hull_with(&self.nodes[node_idx].pt);
-                    let path3 = nearest_pt.hull_with(&self.nodes[child_idx].pt);
-                    for ko in kos {
-                        if ko.contains(&nearest_pt)
-                            || ko.blocks(&path1)
-                            || ko.blocks(&path2)
-                            || ko.blocks(&path3)
-                        {
-                            blocked = true;
-                            break;
-                        }
-                    }
-                    if blocked {
-                        continue;
+                if !keepouts.is_empty() {
+                    let paths = [
+                        nearest_pt.hull_with(&point),
+                        nearest_pt.hull_with(&self.nodes[node_idx].pt),
+                        nearest_pt.hull_with(&self.nodes[child_idx].pt),
+                    ];
+                    match keepouts.connection_cost(&nearest_pt, &paths) {
+                        Some(penalty) => distance += penalty,
+                        None => continue,
                     }
                 }
 
                 let path_length = self.nodes[node_idx].path_length
                     + self.nodes[node_idx].pt.min_dist_with(&nearest_pt) as i32
-                    + distance;
+                    + nearest_pt.min_dist_with(&point) as i32;
 
                 let mut update = false;
                 if path_length <= allowed_wirelength {
@@ -318,20 +378,35 @@ impl GlobalRoutingTree {
         (parent_node, nearest_node)
     }
 
-    fn _insert_terminal_impl(
+    /// Inserts a terminal at the nearest keepout-free point of the tree
+    /// whose path length stays within `allowed_wirelength`, inserting a
+    /// Steiner point on a branch if needed. Soft keepouts count their
+    /// penalty towards the distance. Returns the terminal id.
+    pub fn insert_terminal_with_keepouts(
         &mut self,
         point: Point<i32, i32>,
         allowed_wirelength: i32,
-        keepouts: Option<Vec<Point<Interval<i32>, Interval<i32>>>>,
-    ) {
+        keepouts: &KeepoutSet,
+    ) -> String {
+        let (parent_node, nearest_node) =
+            self._find_insertion_point(point, allowed_wirelength, keepouts);
+        let idx = self.attach_terminal(point, parent_node, nearest_node);
+        self.nodes[idx].id.clone()
+    }
+
+    /// Adds a terminal at `point` and connects it to `nearest_idx`, or, when
+    /// `parent_node` is given, to a new Steiner point on the branch from
+    /// `parent_node` to `nearest_idx`. Returns the terminal's index.
+    fn attach_terminal(
+        &mut self,
+        point: Point<i32, i32>,
+        parent_node: Option<usize>,
+        nearest_idx: usize,
+    ) -> usize {
         let terminal_id = format!("terminal_{}", self.next_terminal_id);
         self.next_terminal_id += 1;
         let terminal_idx = self.add_node(RoutingNode::new(&terminal_id, NodeType::Terminal, point));
 
-        let (parent_node, nearest_node) =
-            self._find_insertion_point(point, allowed_wirelength, &keepouts);
-
-        let nearest_idx = nearest_node;
         match parent_node {
             None => {
                 self.nodes[terminal_idx].parent = Some(nearest_idx);
@@ -373,6 +448,492 @@ impl GlobalRoutingTree {
                     self.nodes[steiner_idx].path_length + dist_st;
             }
         }
+        terminal_idx
+    }
+
+    /// All ways to connect `point` to the tree clear of hard keepouts, as
+    /// `(parent_node, nearest_node)` pairs in the form used by
+    /// `attach_terminal`: every node, plus the nearest point on every branch
+    /// when it lies strictly inside the branch.
+    fn connection_candidates(
+        &self,
+        point: Point<i32, i32>,
+        keepouts: &KeepoutSet,
+    ) -> Vec<(Option<usize>, usize)> {
+        let blocked = |paths: &[Point<Interval<i32>, Interval<i32>>], pt: &Point<i32, i32>| {
+            keepouts.connection_cost(pt, paths).is_none()
+        };
+
+        let mut candidates = Vec::new();
+        let mut stack = vec![self.source_idx];
+        while let Some(node_idx) = stack.pop() {
+            let node_pt = self.nodes[node_idx].pt;
+            if !blocked(&[node_pt.hull_with(&point)], &node_pt) {
+                candidates.push((None, node_idx));
+            }
+            for &child_idx in &self.nodes[node_idx].children {
+                let child_pt = self.nodes[child_idx].pt;
+                let nearest_pt = node_pt.hull_with(&child_pt).nearest_to(&point);
+                if nearest_pt != node_pt
+                    && nearest_pt != child_pt
+                    && !blocked(
+                        &[
+                            nearest_pt.hull_with(&point),
+                            nearest_pt.hull_with(&node_pt),
+                            nearest_pt.hull_with(&child_pt),
+                        ],
+                        &nearest_pt,
+                    )
+                {
+                    candidates.push((Some(node_idx), child_idx));
+                }
+                stack.push(child_idx);
+            }
+        }
+        candidates
+    }
+
+    /// Inserts a terminal where it minimizes the weighted sum of Elmore
+    /// delays of the terminals already in `weights` plus the new one.
+    ///
+    /// Every keepout-free connection (to a node, or to a new Steiner point on
+    /// a branch) is tried; ties are broken by total wirelength. `weights`
+    /// holds `(handle, weight)` of previously inserted terminals and the new
+    /// terminal's entry is appended. Returns the terminal's id, or `None`
+    /// (leaving the tree unchanged) if every connection is blocked.
+    pub fn insert_terminal_timing_driven(
+        &mut self,
+        point: Point<i32, i32>,
+        pin_capacitance: f64,
+        weight: f64,
+        params: &ElmoreParams,
+        weights: &mut Vec<(NodeId, f64)>,
+        keepouts: Option<Vec<Point<Interval<i32>, Interval<i32>>>>,
+    ) -> Option<String> {
+        let keepouts = KeepoutSet::from_rects(keepouts.as_deref().unwrap_or(&[]));
+        self.insert_terminal_timing_driven_avoiding(
+            point,
+            pin_capacitance,
+            weight,
+            params,
+            weights,
+            &keepouts,
+        )
+    }
+
+    /// Like `insert_terminal_timing_driven`, with a `KeepoutSet`: only
+    /// connections clear of hard keepouts are tried.
+    ///
+    /// Candidates are scored without modifying the tree. Adding a load
+    /// $\Delta C$ at a point $x$ delays terminal $t$ by
+    /// $\Delta C \left(R_d + r L(\mathrm{lca}(t, x))\right)$, where $L$ is the
+    /// path length from the source, so the weighted cost grows by
+    ///
+    /// $$\Delta C \left(R_d W + r \sum_{e \in \mathrm{path}(x)} L_e W_e\right)$$
+    ///
+    /// with $W_e$ the weight below edge $e$. Splitting a branch does not
+    /// change any delay, so each candidate costs $O(1)$ after one $O(n)$ pass.
+    pub fn insert_terminal_timing_driven_avoiding(
+        &mut self,
+        point: Point<i32, i32>,
+        pin_capacitance: f64,
+        weight: f64,
+        params: &ElmoreParams,
+        weights: &mut Vec<(NodeId, f64)>,
+        keepouts: &KeepoutSet,
+    ) -> Option<String> {
+        let candidates = self.connection_candidates(point, keepouts);
+        if candidates.is_empty() {
+            return None;
+        }
+        self.compute_elmore_delays(params);
+
+        // Weight below each node, then path length and the weighted
+        // path-length sum `A` from the source down to each node.
+        let n = self.nodes.len();
+        let mut below = vec![0.0; n];
+        for &(handle, w) in weights.iter() {
+            if let Some(&idx) = self.handles.get(&handle) {
+                below[idx] += w;
+            }
+        }
+        let mut order = vec![self.source_idx];
+        let mut k = 0;
+        while k < order.len() {
+            order.extend_from_slice(&self.nodes[order[k]].children);
+            k += 1;
+        }
+        for &idx in order.iter().rev() {
+            if let Some(p) = self.nodes[idx].parent {
+                below[p] += below[idx];
+            }
+        }
+        let (mut path, mut acc) = (vec![0.0; n], vec![0.0; n]);
+        for &idx in &order {
+            if let Some(p) = self.nodes[idx].parent {
+                let len = self.nodes[p].manhattan_distance(&self.nodes[idx]) as f64;
+                path[idx] = path[p] + len;
+                acc[idx] = acc[p] + len * below[idx];
+            }
+        }
+        let total_weight = below[self.source_idx];
+        let (rd, r, c) = (
+            params.driver_resistance,
+            params.unit_resistance,
+            params.unit_capacitance,
+        );
+
+        let mut best: Option<(f64, i32, Option<usize>, usize)> = None;
+        for (parent_node, nearest_node) in candidates {
+            // Attachment point with its delay, path length and `A`
+            let (at, delay, path_len, a) = match parent_node {
+                None => {
+                    let node = &self.nodes[nearest_node];
+                    (node.pt, node.delay, path[nearest_node], acc[nearest_node])
+                }
+                Some(parent) => {
+                    let (pp, cp) = (self.nodes[parent].pt, self.nodes[nearest_node].pt);
+                    let at = pp.hull_with(&cp).nearest_to(&point);
+                    let up = pp.min_dist_with(&at) as f64;
+                    let down = at.min_dist_with(&cp) as f64;
+                    let load = c * (up / 2.0 + down) + self.nodes[nearest_node].capacitance;
+                    (
+                        at,
+                        self.nodes[parent].delay + r * up * load,
+                        path[parent] + up,
+                        acc[parent] + up * below[nearest_node],
+                    )
+                }
+            };
+            let wire = at.min_dist_with(&point) as i32;
+            let len = wire as f64;
+            let added = c * len + pin_capacitance;
+            let own =
+                delay + added * (rd + r * path_len) + r * len * (c * len / 2.0 + pin_capacitance);
+            let cost = added * (rd * total_weight + r * a) + weight * own;
+            let better = match &best {
+                None => true,
+                Some((best_cost, best_wire, _, _)) => {
+                    let tol = 1e-12 * best_cost.abs().max(1.0);
+                    cost < best_cost - tol || (cost <= best_cost + tol && wire < *best_wire)
+                }
+            };
+            if better {
+                best = Some((cost, wire, parent_node, nearest_node));
+            }
+        }
+
+        let (_, _, parent_node, nearest_node) = best?;
+        let idx = self.attach_terminal(point, parent_node, nearest_node);
+        self.nodes[idx].pin_capacitance = pin_capacitance;
+        self.compute_elmore_delays(params);
+        weights.push((self.handle_at(idx), weight));
+        Some(self.nodes[idx].id.clone())
+    }
+
+    /// Connects a terminal at `point` to the nearest reachable point of the
+    /// tree by the shortest path around the router's blockages (see
+    /// `MazeRouter::route`). A path may end on a node or in the middle of a
+    /// branch, which is then split by a new Steiner node. Every bend of the
+    /// path becomes a Steiner node, so each new edge is a straight wire.
+    /// Returns the terminal id, or `None` (leaving the tree unchanged) if no
+    /// point of the tree can be reached.
+    pub fn insert_terminal_maze(
+        &mut self,
+        point: Point<i32, i32>,
+        router: &MazeRouter,
+    ) -> Option<String> {
+        // Besides the nodes, target the points where a search line (the
+        // terminal's coordinates or a blockage escape line) crosses a
+        // straight branch, and the nearest point of any other branch.
+        let (mut xs, mut ys) = (vec![point.xcoord], vec![point.ycoord]);
+        for blockage in router.blockages() {
+            let (bx, by) = blockage.escape_lines();
+            xs.extend(bx);
+            ys.extend(by);
+        }
+        let mut targets: Vec<Point<i32, i32>> = self.nodes.iter().map(|n| n.pt).collect();
+        let mut branch_of = Vec::new();
+        for (idx, node) in self.nodes.iter().enumerate() {
+            let Some(p) = node.parent else { continue };
+            let (a, b) = (self.nodes[p].pt, node.pt);
+            let hull = a.hull_with(&b);
+            let on_branch: Vec<Point<i32, i32>> = if a.ycoord == b.ycoord {
+                xs.iter()
+                    .filter(|&&x| hull.xcoord.lb <= x && x <= hull.xcoord.ub)
+                    .map(|&x| Point::new(x, a.ycoord))
+                    .collect()
+            } else if a.xcoord == b.xcoord {
+                ys.iter()
+                    .filter(|&&y| hull.ycoord.lb <= y && y <= hull.ycoord.ub)
+                    .map(|&y| Point::new(a.xcoord, y))
+                    .collect()
+            } else {
+                vec![hull.nearest_to(&point)]
+            };
+            for pt in on_branch {
+                if pt != a && pt != b {
+                    targets.push(pt);
+                    branch_of.push(idx);
+                }
+            }
+        }
+
+        let path = router.route(point, &targets)?;
+        let mut parent_id = match path.target.checked_sub(self.nodes.len()) {
+            None => self.nodes[path.target].id.clone(),
+            Some(k) => {
+                let child = self.handle_at(branch_of[k]);
+                let handle = self
+                    .split_edge(child, targets[path.target])
+                    .expect("Target lies on the branch");
+                self.nodes[self.handles[&handle]].id.clone()
+            }
+        };
+        for &bend in path.bends().iter().rev() {
+            parent_id = self.insert_steiner_node(bend, Some(&parent_id));
+        }
+        let id = self.insert_terminal_node(point, Some(&parent_id));
+        self.update_path_lengths();
+        Some(id)
+    }
+
+    /// Sets the pin capacitance of a node (normally a terminal).
+    pub fn set_pin_capacitance(&mut self, node_id: &str, capacitance: f64) {
+        let idx = *self.node_map.get(node_id).expect("Node not found");
+        self.nodes[idx].pin_capacitance = capacitance;
+    }
+
+    /// Returns the node with the given id, if any.
+    pub fn get_node(&self, node_id: &str) -> Option<&RoutingNode> {
+        self.node_map.get(node_id).map(|&idx| &self.nodes[idx])
+    }
+
+    /// Handle of the source node.
+    pub fn source_id(&self) -> NodeId {
+        self.handle_at(self.source_idx)
+    }
+
+    /// Handle of the node with the given string id, if any.
+    pub fn node_id(&self, node_id: &str) -> Option<NodeId> {
+        self.node_map.get(node_id).map(|&idx| self.handle_at(idx))
+    }
+
+    /// Returns the node with the given handle, if it still exists.
+    pub fn node(&self, id: NodeId) -> Option<&RoutingNode> {
+        self.handles.get(&id).map(|&idx| &self.nodes[idx])
+    }
+
+    /// Handle of the node at index `idx`.
+    pub(crate) fn handle_at(&self, idx: usize) -> NodeId {
+        self.nodes[idx].handle.expect("Node in tree has a handle")
+    }
+
+    pub(crate) fn index(&self, id: NodeId) -> RoutingResult<usize> {
+        self.handles
+            .get(&id)
+            .copied()
+            .ok_or(RoutingError::NodeNotFound(id))
+    }
+
+    /// Handle of the parent of `id` (`None` for the source).
+    pub fn parent_of(&self, id: NodeId) -> RoutingResult<Option<NodeId>> {
+        let idx = self.index(id)?;
+        Ok(self.nodes[idx].parent.map(|p| self.handle_at(p)))
+    }
+
+    /// Handles of the children of `id`.
+    pub fn children_of(&self, id: NodeId) -> RoutingResult<Vec<NodeId>> {
+        let idx = self.index(id)?;
+        Ok(self.nodes[idx]
+            .children
+            .iter()
+            .map(|&c| self.handle_at(c))
+            .collect())
+    }
+
+    /// Returns `true` if `idx` is `root` or one of its descendants.
+    fn in_subtree(&self, mut idx: usize, root: usize) -> bool {
+        loop {
+            if idx == root {
+                return true;
+            }
+            match self.nodes[idx].parent {
+                Some(p) => idx = p,
+                None => return false,
+            }
+        }
+    }
+
+    /// Adds a Steiner node at `point` under `parent`.
+    pub fn add_steiner(&mut self, point: Point<i32, i32>, parent: NodeId) -> RoutingResult<NodeId> {
+        let parent_id = self.nodes[self.index(parent)?].id.clone();
+        let id = self.insert_steiner_node(point, Some(&parent_id));
+        self.update_path_lengths();
+        Ok(self.handle_at(self.node_map[&id]))
+    }
+
+    /// Adds a terminal at `point` under `parent`.
+    pub fn add_terminal(
+        &mut self,
+        point: Point<i32, i32>,
+        parent: NodeId,
+    ) -> RoutingResult<NodeId> {
+        let parent_id = self.nodes[self.index(parent)?].id.clone();
+        let id = self.insert_terminal_node(point, Some(&parent_id));
+        self.update_path_lengths();
+        Ok(self.handle_at(self.node_map[&id]))
+    }
+
+    /// Removes `id` and all its descendants. Returns the removed handles.
+    pub fn delete_subtree(&mut self, id: NodeId) -> RoutingResult<Vec<NodeId>> {
+        let idx = self.index(id)?;
+        if idx == self.source_idx {
+            return Err(RoutingError::SourceNode);
+        }
+        let mut keep = vec![true; self.nodes.len()];
+        let mut removed = Vec::new();
+        let mut stack = vec![idx];
+        while let Some(v) = stack.pop() {
+            keep[v] = false;
+            removed.push(self.handle_at(v));
+            stack.extend(self.nodes[v].children.iter().copied());
+        }
+        if let Some(p) = self.nodes[idx].parent {
+            self.nodes[p].children.retain(|&c| c != idx);
+        }
+        self.compact(&keep);
+        Ok(removed)
+    }
+
+    /// Moves `id` with its subtree under `new_parent`.
+    pub fn reparent(&mut self, id: NodeId, new_parent: NodeId) -> RoutingResult<()> {
+        let idx = self.index(id)?;
+        let parent_idx = self.index(new_parent)?;
+        if idx == self.source_idx {
+            return Err(RoutingError::SourceNode);
+        }
+        if self.in_subtree(parent_idx, idx) {
+            return Err(RoutingError::WouldCreateCycle {
+                node: id,
+                new_parent,
+            });
+        }
+        self.reparent_index(idx, parent_idx);
+        self.update_path_lengths();
+        Ok(())
+    }
+
+    /// Moves node `id` to `point`, keeping its connections.
+    pub fn move_node(&mut self, id: NodeId, point: Point<i32, i32>) -> RoutingResult<()> {
+        let idx = self.index(id)?;
+        self.nodes[idx].pt = point;
+        self.update_path_lengths();
+        Ok(())
+    }
+
+    /// Merges two nodes at the same position into one. The survivor is the
+    /// pin if one of them is a pin, otherwise `first`; it takes over the
+    /// other node's children (and its parent, if the other node was its
+    /// ancestor). Returns the surviving handle.
+    pub fn merge_coincident(&mut self, first: NodeId, second: NodeId) -> RoutingResult<NodeId> {
+        let (a, b) = (self.index(first)?, self.index(second)?);
+        if a == b {
+            return Err(RoutingError::SameNode(first));
+        }
+        if self.nodes[a].pt != self.nodes[b].pt {
+            return Err(RoutingError::NotCoincident { first, second });
+        }
+        let is_pin = |idx: usize| self.nodes[idx].node_type != NodeType::Steiner;
+        let (keep, drop) = match (is_pin(a), is_pin(b)) {
+            (true, true) => return Err(RoutingError::TwoPins { first, second }),
+            (false, true) => (b, a),
+            _ => (a, b),
+        };
+        if self.in_subtree(keep, drop) {
+            let p = self.nodes[drop]
+                .parent
+                .expect("A Steiner node has a parent");
+            self.reparent_index(keep, p);
+        }
+        for c in self.nodes[drop].children.clone() {
+            self.reparent_index(c, keep);
+        }
+        if let Some(p) = self.nodes[drop].parent {
+            self.nodes[p].children.retain(|&c| c != drop);
+        }
+        let mut mask = vec![true; self.nodes.len()];
+        mask[drop] = false;
+        let handle = self.handle_at(keep);
+        self.compact(&mask);
+        self.update_path_lengths();
+        Ok(handle)
+    }
+
+    /// Splits the edge into `child` with a new Steiner node at `point`,
+    /// which must lie in the bounding box of the edge so the wirelength is
+    /// unchanged. Returns the new node's handle.
+    pub fn split_edge(&mut self, child: NodeId, point: Point<i32, i32>) -> RoutingResult<NodeId> {
+        let idx = self.index(child)?;
+        let p = self.nodes[idx].parent.ok_or(RoutingError::SourceNode)?;
+        if !self.nodes[p]
+            .pt
+            .hull_with(&self.nodes[idx].pt)
+            .contains(&point)
+        {
+            return Err(RoutingError::OffEdge(child));
+        }
+        let (pid, cid) = (self.nodes[p].id.clone(), self.nodes[idx].id.clone());
+        let id = self.insert_node_on_branch(NodeType::Steiner, point, &pid, &cid);
+        self.update_path_lengths();
+        Ok(self.handle_at(self.node_map[&id]))
+    }
+
+    /// Fills `capacitance` and `delay` of every node with an Elmore analysis.
+    ///
+    /// The downstream capacitance of a node is its pin capacitance plus, for
+    /// each child $k$ at wire length $L_k$,
+    ///
+    /// $$C_v = C^{pin}_v + \sum_k \left(c L_k + C_k\right)$$
+    ///
+    /// The source delay is $R_d C_{src}$ and each wire adds
+    /// $r L_k \left(c L_k / 2 + C_k\right)$.
+    pub fn compute_elmore_delays(&mut self, params: &ElmoreParams) {
+        fn downstream(tree: &mut GlobalRoutingTree, idx: usize, c: f64) -> f64 {
+            let mut cap = tree.nodes[idx].pin_capacitance;
+            for k in 0..tree.nodes[idx].children.len() {
+                let child = tree.nodes[idx].children[k];
+                let len = tree.nodes[idx].manhattan_distance(&tree.nodes[child]) as f64;
+                cap += c * len + downstream(tree, child, c);
+            }
+            tree.nodes[idx].capacitance = cap;
+            cap
+        }
+        fn propagate(tree: &mut GlobalRoutingTree, idx: usize, params: &ElmoreParams) {
+            for k in 0..tree.nodes[idx].children.len() {
+                let child = tree.nodes[idx].children[k];
+                let len = tree.nodes[idx].manhattan_distance(&tree.nodes[child]) as f64;
+                let load = params.unit_capacitance * len / 2.0 + tree.nodes[child].capacitance;
+                tree.nodes[child].delay =
+                    tree.nodes[idx].delay + params.unit_resistance * len * load;
+                propagate(tree, child, params);
+            }
+        }
+
+        let src = self.source_idx;
+        let total = downstream(self, src, params.unit_capacitance);
+        self.nodes[src].delay = params.driver_resistance * total;
+        propagate(self, src, params);
+    }
+
+    /// Weighted sum of terminal delays, $\sum_i w_i d_i$, using the delays
+    /// from the last `compute_elmore_delays`. `weights` maps terminal ids to
+    /// weights; terminals not listed have weight 1.
+    pub fn weighted_terminal_delay(&self, weights: &HashMap<String, f64>) -> f64 {
+        self.get_all_terminals()
+            .iter()
+            .map(|t| weights.get(&t.id).copied().unwrap_or(1.0) * t.delay)
+            .sum()
     }
 
     pub fn insert_terminal_with_steiner(
@@ -380,7 +941,8 @@ impl GlobalRoutingTree {
         point: Point<i32, i32>,
         keepouts: Option<Vec<Point<Interval<i32>, Interval<i32>>>>,
     ) {
-        self._insert_terminal_impl(point, i32::MAX, keepouts);
+        let keepouts = KeepoutSet::from_rects(keepouts.as_deref().unwrap_or(&[]));
+        self.insert_terminal_with_keepouts(point, i32::MAX, &keepouts);
     }
 
     pub fn insert_terminal_with_constraints(
@@ -389,7 +951,80 @@ impl GlobalRoutingTree {
         allowed_wirelength: i32,
         keepouts: Option<Vec<Point<Interval<i32>, Interval<i32>>>>,
     ) {
-        self._insert_terminal_impl(point, allowed_wirelength, keepouts);
+        let keepouts = KeepoutSet::from_rects(keepouts.as_deref().unwrap_or(&[]));
+        self.insert_terminal_with_keepouts(point, allowed_wirelength, &keepouts);
+    }
+
+    /// Number of vias: every edge between coincident nodes on different
+    /// layers counts one via per layer crossed.
+    pub fn via_count(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter_map(|n| n.parent.map(|p| (&self.nodes[p], n)))
+            .filter(|(p, n)| p.pt == n.pt)
+            .map(|(p, n)| p.layer.abs_diff(n.layer))
+            .sum()
+    }
+
+    /// Wirelength on each layer, indexed by layer. A planar edge counts
+    /// towards the layer of its child node.
+    pub fn layer_wirelength(&self) -> Vec<i32> {
+        let num_layers = self.nodes.iter().map(|n| n.layer + 1).max().unwrap_or(1);
+        let mut lengths = vec![0; num_layers];
+        for node in &self.nodes {
+            if let Some(p) = node.parent {
+                lengths[node.layer] += self.nodes[p].manhattan_distance(node);
+            }
+        }
+        lengths
+    }
+
+    /// All nodes, indexed as in `RoutingNode::children` and `parent`.
+    pub(crate) fn nodes(&self) -> &[RoutingNode] {
+        &self.nodes
+    }
+
+    pub(crate) fn node_mut(&mut self, idx: usize) -> &mut RoutingNode {
+        &mut self.nodes[idx]
+    }
+
+    pub(crate) fn node_index(&self, node_id: &str) -> Option<usize> {
+        self.node_map.get(node_id).copied()
+    }
+
+    pub(crate) fn source_index(&self) -> usize {
+        self.source_idx
+    }
+
+    /// Moves node `idx` (with its subtree) under `new_parent`.
+    pub(crate) fn reparent_index(&mut self, idx: usize, new_parent: usize) {
+        if let Some(old) = self.nodes[idx].parent {
+            self.nodes[old].children.retain(|&c| c != idx);
+        }
+        self.nodes[idx].parent = Some(new_parent);
+        self.nodes[new_parent].children.push(idx);
+    }
+
+    /// Adds `node` under `parent` keeping its id, and moves the id counters
+    /// past it so later insertions do not reuse `steiner_N`/`terminal_N`.
+    pub(crate) fn attach_with_id(&mut self, node: RoutingNode, parent: usize) -> usize {
+        let counter = match node.node_type {
+            NodeType::Steiner => node.id.strip_prefix("steiner_").map(|n| (n, true)),
+            NodeType::Terminal => node.id.strip_prefix("terminal_").map(|n| (n, false)),
+            NodeType::Source => None,
+        };
+        if let Some((Ok(n), steiner)) = counter.map(|(n, s)| (n.parse::<i32>(), s)) {
+            let next = if steiner {
+                &mut self.next_steiner_id
+            } else {
+                &mut self.next_terminal_id
+            };
+            *next = (*next).max(n + 1);
+        }
+        let idx = self.add_node(node);
+        self.nodes[idx].parent = Some(parent);
+        self.nodes[parent].children.push(idx);
+        idx
     }
 
     /// Calculates the total wirelength of the entire routing tree.
@@ -405,6 +1040,20 @@ impl GlobalRoutingTree {
         total
     }
 
+    /// Recomputes `path_length` of every node from the source.
+    pub fn update_path_lengths(&mut self) {
+        let mut stack = vec![self.source_idx];
+        self.nodes[self.source_idx].path_length = 0;
+        while let Some(idx) = stack.pop() {
+            for k in 0..self.nodes[idx].children.len() {
+                let child = self.nodes[idx].children[k];
+                self.nodes[child].path_length = self.nodes[idx].path_length
+                    + self.nodes[idx].manhattan_distance(&self.nodes[child]);
+                stack.push(child);
+            }
+        }
+    }
+
     /// Calculates the worst-case (maximum) source-to-terminal wirelength.
     ///
     /// $$W = \max_{\text{leaf}} \sum_{\text{path(source, leaf)}} \text{edge\_length}$$
@@ -500,6 +1149,7 @@ impl GlobalRoutingTree {
             .map(|(i, _)| i)
             .collect();
 
+        let mut keep = vec![true; self.nodes.len()];
         for &idx in to_remove.iter().rev() {
             let parent = self.nodes[idx].parent;
             let child = self.nodes[idx].children[0];
@@ -508,37 +1158,47 @@ impl GlobalRoutingTree {
                 self.nodes[p].children.push(child);
             }
             self.nodes[child].parent = parent;
-            self.node_map.remove(&self.nodes[idx].id);
+            keep[idx] = false;
         }
-        self.nodes.retain(|n| self.node_map.contains_key(&n.id));
-        self.remap_indices();
+        self.compact(&keep);
     }
 
-    fn remap_indices(&mut self) {
-        let old_indices: Vec<usize> = (0..self.nodes.len()).collect();
-        let mut new_map = HashMap::new();
-        let mut new_nodes = Vec::new();
-        for node in self.nodes.drain(..) {
-            let new_idx = new_nodes.len();
-            new_map.insert(node.id.clone(), new_idx);
-            new_nodes.push(node);
-        }
-        self.nodes = new_nodes;
-        for node in &mut self.nodes {
-            if let Some(p) = node.parent {
-                if let Some(&old_p) = old_indices.get(p) {
-                    node.parent = Some(old_p);
-                } else {
-                    node.parent = None;
-                }
+    /// Drops the nodes not marked in `keep` and renumbers the indices in
+    /// `parent`, `children`, and the id and handle maps. Dropped nodes must
+    /// already be unlinked from the kept ones.
+    fn compact(&mut self, keep: &[bool]) {
+        let mut new_index = vec![None; self.nodes.len()];
+        let mut next = 0;
+        for (old, &k) in keep.iter().enumerate() {
+            if k {
+                new_index[old] = Some(next);
+                next += 1;
             }
-            node.children = node
-                .children
-                .iter()
-                .filter_map(|c| old_indices.get(*c).copied())
-                .collect();
         }
-        self.node_map = new_map;
+        let old_nodes = std::mem::take(&mut self.nodes);
+        self.nodes = old_nodes
+            .into_iter()
+            .zip(keep)
+            .filter(|(_, &k)| k)
+            .map(|(mut node, _)| {
+                node.parent = node.parent.and_then(|p| new_index[p]);
+                node.children = node.children.iter().filter_map(|&c| new_index[c]).collect();
+                node
+            })
+            .collect();
+        self.source_idx = new_index[self.source_idx].expect("Source is kept");
+        self.node_map = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.id.clone(), i))
+            .collect();
+        self.handles = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.handle.expect("Node in tree"), i))
+            .collect();
     }
 
     /// Generate an SVG visualization of the routing tree.
@@ -733,9 +1393,11 @@ impl GlobalRoutingTree {
 /// and a set of terminal points, with optional keepout avoidance.
 pub struct GlobalRouter {
     terminal_positions: Vec<Point<i32, i32>>,
+    /// Index into the caller's terminal list for each sorted terminal
+    terminal_order: Vec<usize>,
     tree: GlobalRoutingTree,
     worst_wirelength: i32,
-    keepouts: Option<Vec<Point<Interval<i32>, Interval<i32>>>>,
+    keepouts: KeepoutSet,
 }
 
 impl GlobalRouter {
@@ -744,12 +1406,22 @@ impl GlobalRouter {
         terminal_positions: Vec<Point<i32, i32>>,
         keepout_regions: Option<Vec<Point<Interval<i32>, Interval<i32>>>>,
     ) -> Self {
-        let mut sorted = terminal_positions.clone();
-        sorted.sort_by(|a, b| {
-            let da = source_pos.min_dist_with(a) as i32;
-            let db = source_pos.min_dist_with(b) as i32;
-            da.cmp(&db)
-        });
+        let keepouts = KeepoutSet::from_rects(keepout_regions.as_deref().unwrap_or(&[]));
+        GlobalRouter::with_keepouts(source_pos, terminal_positions, keepouts)
+    }
+
+    /// Creates a router with polygon, halo and soft keepouts.
+    pub fn with_keepouts(
+        source_pos: Point<i32, i32>,
+        terminal_positions: Vec<Point<i32, i32>>,
+        keepouts: KeepoutSet,
+    ) -> Self {
+        let mut terminal_order: Vec<usize> = (0..terminal_positions.len()).collect();
+        terminal_order.sort_by_key(|&i| source_pos.min_dist_with(&terminal_positions[i]) as i32);
+        let sorted: Vec<Point<i32, i32>> = terminal_order
+            .iter()
+            .map(|&i| terminal_positions[i])
+            .collect();
 
         let worst = if sorted.is_empty() {
             0
@@ -759,9 +1431,10 @@ impl GlobalRouter {
 
         GlobalRouter {
             terminal_positions: sorted,
+            terminal_order,
             tree: GlobalRoutingTree::new(source_pos),
             worst_wirelength: worst,
-            keepouts: keepout_regions,
+            keepouts,
         }
     }
 
@@ -774,12 +1447,13 @@ impl GlobalRouter {
     }
 
     /// Routes terminals with Steiner point insertion to reduce total
-    /// wirelength while avoiding keepout regions.
+    /// wirelength while avoiding hard keepouts; soft keepouts add their
+    /// penalty to a connection's cost.
     pub fn route_with_steiners(&mut self) {
         self.tree.worst_wirelength = self.worst_wirelength;
         for &terminal in &self.terminal_positions {
             self.tree
-                .insert_terminal_with_steiner(terminal, self.keepouts.clone());
+                .insert_terminal_with_keepouts(terminal, i32::MAX, &self.keepouts);
         }
     }
 
@@ -791,10 +1465,108 @@ impl GlobalRouter {
         self.tree.worst_wirelength = self.worst_wirelength;
         for &terminal in &self.terminal_positions {
             self.tree
-                .insert_terminal_with_constraints(terminal, allowed, self.keepouts.clone());
+                .insert_terminal_with_keepouts(terminal, allowed, &self.keepouts);
         }
     }
 
+    /// Routes the net as a rectilinear Steiner minimal tree, replacing the
+    /// current tree. Exact for small nets, heuristic for large ones (see
+    /// `rsmt::build_rsmt`). Keepouts are ignored.
+    pub fn route_rsmt(&mut self) {
+        let source = self.tree.get_source().pt;
+        self.tree = crate::rsmt::build_rsmt(source, &self.terminal_positions);
+    }
+
+    /// Routes the net as a PD-II Prim-Dijkstra tree with parameter `alpha`
+    /// (0 favours wirelength, 1 favours radius), replacing the current tree.
+    /// Keepouts are ignored.
+    pub fn route_prim_dijkstra(&mut self, alpha: f64) {
+        let source = self.tree.get_source().pt;
+        self.tree = crate::prim_dijkstra::prim_dijkstra_ii(source, &self.terminal_positions, alpha);
+    }
+
+    /// Routes the net as an obstacle-avoiding rectilinear Steiner tree
+    /// (see `oarsmt::build_oarsmt`), replacing the current tree. Returns
+    /// `false`, leaving the tree unchanged, if a pin is inside a hard keepout
+    /// or cannot be reached. Soft keepouts are ignored.
+    pub fn route_oarsmt(&mut self) -> bool {
+        let source = self.tree.get_source().pt;
+        let keepouts = self.keepouts.hard_rects();
+        match crate::oarsmt::build_oarsmt(source, &self.terminal_positions, &keepouts) {
+            Some(tree) => {
+                self.tree = tree;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Routes terminals to minimize the weighted sum of Elmore sink delays.
+    ///
+    /// `pin_caps` and `weights` are indexed like the terminal positions
+    /// passed to `new`; give critical sinks larger weights. Terminals are
+    /// inserted nearest-first, each at the connection that minimizes the
+    /// weighted delay of the terminals routed so far (see
+    /// `GlobalRoutingTree::insert_terminal_timing_driven`). Keepouts are
+    /// honoured, and node delays are left filled in. Returns the indices
+    /// (into the terminal list passed to `new`) of terminals whose every
+    /// connection is blocked; these are left out of the tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pin_caps` or `weights` does not have one entry per terminal.
+    pub fn route_timing_driven(
+        &mut self,
+        params: &ElmoreParams,
+        pin_caps: &[f64],
+        weights: &[f64],
+    ) -> Vec<usize> {
+        let n = self.terminal_positions.len();
+        assert!(
+            pin_caps.len() == n && weights.len() == n,
+            "Need one pin capacitance and one weight per terminal"
+        );
+        let mut inserted = Vec::with_capacity(n);
+        let mut unrouted = Vec::new();
+        for (k, &terminal) in self.terminal_positions.iter().enumerate() {
+            let i = self.terminal_order[k];
+            let id = self.tree.insert_terminal_timing_driven_avoiding(
+                terminal,
+                pin_caps[i],
+                weights[i],
+                params,
+                &mut inserted,
+                &self.keepouts,
+            );
+            if id.is_none() {
+                unrouted.push(i);
+            }
+        }
+        self.tree.compute_elmore_delays(params);
+        unrouted
+    }
+
+    /// Routes terminals nearest-first, connecting each to the tree by an A*
+    /// maze route around the hard keepouts (soft ones are ignored). Returns
+    /// the indices (into the terminal list passed to `new`) of terminals
+    /// that could not be reached; these are left out of the tree.
+    pub fn route_maze(&mut self) -> Vec<usize> {
+        let router = MazeRouter::from_keepouts(&self.keepouts.hard_rects());
+        let mut unrouted = Vec::new();
+        for (k, &terminal) in self.terminal_positions.iter().enumerate() {
+            if self.tree.insert_terminal_maze(terminal, &router).is_none() {
+                unrouted.push(self.terminal_order[k]);
+            }
+        }
+        self.tree.worst_wirelength = self.tree.calculate_worst_wirelength();
+        unrouted
+    }
+
+    /// Returns the keepouts.
+    pub fn keepouts(&self) -> &KeepoutSet {
+        &self.keepouts
+    }
+
     /// Returns a reference to the constructed routing tree.
     pub fn get_tree(&self) -> &GlobalRoutingTree {
         &self.tree
@@ -804,6 +1576,8 @@ impl GlobalRouter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::keepout::Keepout;
+    use crate::rpolygon::RPolygon;
 
     fn make_keepout(x1: i32, x2: i32, y1: i32, y2: i32) -> Point<Interval<i32>, Interval<i32>> {
         let lo_x = x1.min(x2);
@@ -899,6 +1673,131 @@ mod tests {
         assert_eq!(tree.get_all_steiner_nodes().len(), 0);
     }
 
+    /// Checks parent/child symmetry, the id and handle maps and path lengths.
+    #[allow(clippy::manual_contains)]
+    fn assert_consistent(tree: &GlobalRoutingTree) {
+        for (i, node) in tree.nodes.iter().enumerate() {
+            assert_eq!(tree.node_map[&node.id], i);
+            assert_eq!(tree.handles[&node.handle.unwrap()], i);
+            for &c in &node.children {
+                assert_eq!(tree.nodes[c].parent, Some(i));
+            }
+            match node.parent {
+                Some(p) => {
+                    assert!(tree.nodes[p].children.iter().any(|&c| c == i));
+                    let d = tree.nodes[p].manhattan_distance(node);
+                    assert_eq!(node.path_length, tree.nodes[p].path_length + d);
+                }
+                None => assert_eq!(i, tree.source_idx),
+            }
+        }
+        assert_eq!(tree.node_map.len(), tree.nodes.len());
+        assert_eq!(tree.handles.len(), tree.nodes.len());
+    }
+
+    #[test]
+    fn test_optimize_steiner_points_keeps_links() {
+        let mut tree = GlobalRoutingTree::new(Point::new(0, 0));
+        let s1 = tree.insert_steiner_node(Point::new(5, 0), None);
+        let s2 = tree.insert_steiner_node(Point::new(5, 5), Some(&s1));
+        tree.insert_terminal_node(Point::new(5, 10), Some(&s2));
+        tree.insert_terminal_node(Point::new(10, 5), Some(&s2));
+        let far = tree.insert_terminal_node(Point::new(10, 0), Some(&s1));
+        let far_handle = tree.node_id(&far).unwrap();
+        tree.optimize_steiner_points();
+        tree.update_path_lengths();
+        assert_consistent(&tree);
+        assert_eq!(tree.node(far_handle).unwrap().pt, Point::new(10, 0));
+    }
+
+    #[test]
+    fn test_handles_survive_delete_subtree() {
+        let mut tree = GlobalRoutingTree::new(Point::new(0, 0));
+        let src = tree.source_id();
+        let s = tree.add_steiner(Point::new(5, 0), src).unwrap();
+        let t1 = tree.add_terminal(Point::new(5, 5), s).unwrap();
+        let t2 = tree.add_terminal(Point::new(10, 0), s).unwrap();
+        let t3 = tree.add_terminal(Point::new(0, 8), src).unwrap();
+        assert_eq!(tree.get_source().handle(), Some(src));
+        assert_eq!(tree.node(t3).unwrap().handle(), Some(t3));
+        assert_eq!(
+            RoutingNode::new("loose", NodeType::Steiner, Point::new(0, 0)).handle(),
+            None
+        );
+        let removed = tree.delete_subtree(t1).unwrap();
+        assert_eq!(removed, vec![t1]);
+        assert!(tree.node(t1).is_none());
+        assert_eq!(tree.node(t2).unwrap().path_length, 10);
+        assert_eq!(tree.node(t3).unwrap().pt, Point::new(0, 8));
+        assert_eq!(tree.delete_subtree(t1), Err(RoutingError::NodeNotFound(t1)));
+        assert_eq!(tree.delete_subtree(src), Err(RoutingError::SourceNode));
+        assert_eq!(tree.delete_subtree(s).unwrap().len(), 2);
+        assert_eq!(tree.children_of(src).unwrap(), vec![t3]);
+        assert_consistent(&tree);
+    }
+
+    #[test]
+    fn test_reparent_and_move() {
+        let mut tree = GlobalRoutingTree::new(Point::new(0, 0));
+        let src = tree.source_id();
+        let s = tree.add_steiner(Point::new(5, 0), src).unwrap();
+        let t1 = tree.add_terminal(Point::new(5, 5), s).unwrap();
+        let t2 = tree.add_terminal(Point::new(0, 5), src).unwrap();
+        assert_eq!(
+            tree.reparent(s, t1),
+            Err(RoutingError::WouldCreateCycle {
+                node: s,
+                new_parent: t1
+            })
+        );
+        tree.reparent(t1, t2).unwrap();
+        assert_eq!(tree.parent_of(t1).unwrap(), Some(t2));
+        assert_eq!(tree.node(t1).unwrap().path_length, 10);
+        tree.move_node(t2, Point::new(0, 2)).unwrap();
+        assert_eq!(tree.node(t1).unwrap().path_length, 10);
+        assert_eq!(tree.node(t2).unwrap().path_length, 2);
+        assert_consistent(&tree);
+    }
+
+    #[test]
+    fn test_merge_coincident_and_split_edge() {
+        let mut tree = GlobalRoutingTree::new(Point::new(0, 0));
+        let src = tree.source_id();
+        let s1 = tree.add_steiner(Point::new(5, 0), src).unwrap();
+        let s2 = tree.add_steiner(Point::new(5, 0), s1).unwrap();
+        let t1 = tree.add_terminal(Point::new(5, 5), s2).unwrap();
+        let t2 = tree.add_terminal(Point::new(5, 0), src).unwrap();
+        // Merging a Steiner node into its coincident descendant keeps the
+        // descendant and hands it the ancestor's parent.
+        assert_eq!(tree.merge_coincident(s2, s1).unwrap(), s2);
+        assert_eq!(tree.parent_of(s2).unwrap(), Some(src));
+        // A pin survives a merge with a Steiner node.
+        assert_eq!(tree.merge_coincident(s2, t2).unwrap(), t2);
+        assert_eq!(tree.parent_of(t1).unwrap(), Some(t2));
+        assert!(tree.node(s1).is_none() && tree.node(s2).is_none());
+        assert_eq!(
+            tree.merge_coincident(t1, t2),
+            Err(RoutingError::NotCoincident {
+                first: t1,
+                second: t2
+            })
+        );
+
+        let mid = tree.split_edge(t1, Point::new(5, 3)).unwrap();
+        assert_eq!(tree.parent_of(t1).unwrap(), Some(mid));
+        assert_eq!(tree.node(t1).unwrap().path_length, 10);
+        assert_eq!(
+            tree.split_edge(t1, Point::new(6, 4)),
+            Err(RoutingError::OffEdge(t1))
+        );
+        assert_eq!(
+            tree.split_edge(src, Point::new(0, 0)),
+            Err(RoutingError::SourceNode)
+        );
+        assert_eq!(tree.calculate_total_wirelength(), 10);
+        assert_consistent(&tree);
+    }
+
     #[test]
     fn test_calculate_worst_wirelength() {
         let mut tree = GlobalRoutingTree::new(Point::new(0, 0));
@@ -919,6 +1818,287 @@ mod tests {
         assert!(svg.find("Wirelength").is_some());
     }
 
+    #[test]
+    fn test_compute_elmore_delays() {
+        let mut tree = GlobalRoutingTree::new(Point::new(0, 0));
+        let t1 = tree.insert_terminal_node(Point::new(10, 0), None);
+        tree.set_pin_capacitance(&t1, 1.0);
+        tree.compute_elmore_delays(&ElmoreParams::new(2.0, 0.1, 0.2));
+        // C_src = 1 + 0.2 * 10 = 3; d_src = 2 * 3; d_t1 = 6 + 0.1 * 10 * (1 + 1)
+        assert!((tree.get_source().capacitance - 3.0).abs() < 1e-12);
+        assert!((tree.get_source().delay - 6.0).abs() < 1e-12);
+        let t = tree.get_node(&t1).unwrap();
+        assert!((t.delay - 8.0).abs() < 1e-12);
+
+        let weights: HashMap<String, f64> = [(t1.clone(), 3.0)].into_iter().collect();
+        assert!((tree.weighted_terminal_delay(&weights) - 24.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_route_timing_driven_favors_critical_sink() {
+        let src = Point::new(0, 0);
+        let terminals = vec![
+            Point::new(20, 0),
+            Point::new(40, 5),
+            Point::new(60, -5),
+            Point::new(80, 40),
+        ];
+        let params = ElmoreParams::new(1.0, 0.5, 0.2);
+        let caps = vec![1.0; 4];
+
+        let mut uniform = GlobalRouter::new(src, terminals.clone(), None);
+        uniform.route_timing_driven(&params, &caps, &[1.0; 4]);
+        let mut critical = GlobalRouter::new(src, terminals.clone(), None);
+        critical.route_timing_driven(&params, &caps, &[1.0, 1.0, 1.0, 100.0]);
+
+        let delay_of = |router: &GlobalRouter, pt: Point<i32, i32>| {
+            let tree = router.get_tree();
+            tree.get_all_terminals()
+                .into_iter()
+                .find(|t| t.pt == pt)
+                .unwrap()
+                .delay
+        };
+        assert_eq!(critical.get_tree().get_all_terminals().len(), 4);
+        assert!(delay_of(&critical, terminals[3]) <= delay_of(&uniform, terminals[3]) + 1e-9);
+
+        let mut steiner = GlobalRouter::new(src, terminals.clone(), None);
+        steiner.route_with_steiners();
+        let mut steiner_tree = steiner.get_tree().clone();
+        for t in steiner.get_tree().get_all_terminals() {
+            steiner_tree.set_pin_capacitance(&t.id, 1.0);
+        }
+        steiner_tree.compute_elmore_delays(&params);
+        let steiner_delay = steiner_tree
+            .get_all_terminals()
+            .into_iter()
+            .find(|t| t.pt == terminals[3])
+            .unwrap()
+            .delay;
+        assert!(delay_of(&critical, terminals[3]) <= steiner_delay + 1e-9);
+    }
+
+    #[test]
+    fn test_timing_driven_insert_matches_brute_force() {
+        let params = ElmoreParams::new(1.5, 0.3, 0.2);
+        let mut tree = GlobalRoutingTree::new(Point::new(0, 0));
+        let mut weights = Vec::new();
+        let pins = [(30, 5), (10, 25), (45, 30), (20, -15), (50, 0)];
+        for (k, &(x, y)) in pins.iter().enumerate() {
+            let before = tree.clone();
+            let point = Point::new(x, y);
+            let weight = 1.0 + k as f64;
+            let cost = |t: &GlobalRoutingTree, w: &[(NodeId, f64)]| {
+                w.iter()
+                    .map(|&(h, wt)| wt * t.node(h).unwrap().delay)
+                    .sum::<f64>()
+            };
+            let best = before
+                .connection_candidates(point, &KeepoutSet::default())
+                .into_iter()
+                .map(|(parent, nearest)| {
+                    let mut trial = before.clone();
+                    let idx = trial.attach_terminal(point, parent, nearest);
+                    trial.nodes[idx].pin_capacitance = 0.5;
+                    trial.compute_elmore_delays(&params);
+                    let mut w = weights.clone();
+                    w.push((trial.handle_at(idx), weight));
+                    cost(&trial, &w)
+                })
+                .fold(f64::INFINITY, f64::min);
+            tree.insert_terminal_timing_driven(point, 0.5, weight, &params, &mut weights, None)
+                .unwrap();
+            assert!((cost(&tree, &weights) - best).abs() < 1e-9 * best);
+        }
+    }
+
+    #[test]
+    fn test_route_timing_driven_avoids_keepout() {
+        let src = Point::new(0, 0);
+        let terminals = vec![Point::new(0, 8), Point::new(10, 0)];
+        let keepout = make_keepout(4, 6, -1, 5);
+        let mut router = GlobalRouter::new(src, terminals, Some(vec![keepout]));
+        let params = ElmoreParams::new(1.0, 0.1, 0.1);
+        assert!(router
+            .route_timing_driven(&params, &[1.0, 1.0], &[1.0, 1.0])
+            .is_empty());
+        let tree = router.get_tree();
+        assert_eq!(tree.get_all_terminals().len(), 2);
+        for node in &tree.nodes {
+            if let Some(p) = node.parent {
+                assert!(!keepout.blocks(&tree.nodes[p].pt.hull_with(&node.pt)));
+            }
+        }
+        let t = tree.get_node("terminal_2").unwrap();
+        assert_eq!(t.pt, Point::new(10, 0));
+        assert_eq!(tree.nodes[t.parent.unwrap()].pt, Point::new(0, 8));
+
+        let wall = make_keepout(4, 6, -1, 1);
+        let mut blocked = GlobalRouter::new(src, vec![Point::new(10, 0)], Some(vec![wall]));
+        assert_eq!(
+            blocked.route_timing_driven(&params, &[1.0], &[1.0]),
+            vec![0]
+        );
+        assert!(blocked.get_tree().get_all_terminals().is_empty());
+    }
+
+    #[test]
+    fn test_route_rsmt() {
+        let src = Point::new(0, 5);
+        let terminals = vec![Point::new(10, 5), Point::new(5, 0), Point::new(5, 10)];
+        let mut router = GlobalRouter::new(src, terminals.clone(), None);
+        router.route_rsmt();
+        let rsmt_wl = router.get_tree().calculate_total_wirelength();
+        let mut incremental = GlobalRouter::new(src, terminals, None);
+        incremental.route_with_steiners();
+        assert_eq!(rsmt_wl, 20);
+        assert!(rsmt_wl <= incremental.get_tree().calculate_total_wirelength());
+        let t = router.get_tree().get_all_terminals();
+        assert!(t.iter().all(|n| n.path_length == 10));
+    }
+
+    #[test]
+    fn test_route_prim_dijkstra() {
+        let src = Point::new(0, 0);
+        let terminals = vec![Point::new(10, 1), Point::new(20, 2), Point::new(30, 3)];
+        let mut mst_like = GlobalRouter::new(src, terminals.clone(), None);
+        mst_like.route_prim_dijkstra(0.0);
+        let mut spt_like = GlobalRouter::new(src, terminals, None);
+        spt_like.route_prim_dijkstra(1.0);
+        assert_eq!(mst_like.get_tree().calculate_total_wirelength(), 33);
+        assert_eq!(spt_like.get_tree().calculate_worst_wirelength(), 33);
+    }
+
+    #[test]
+    fn test_route_maze_avoids_keepout() {
+        let src = Point::new(0, 0);
+        let terminals = vec![Point::new(10, 0), Point::new(20, 0), Point::new(5, 20)];
+        let keepout = make_keepout(4, 6, -5, 5);
+        let mut router = GlobalRouter::new(src, terminals, Some(vec![keepout]));
+        let unrouted = router.route_maze();
+        assert!(unrouted.is_empty());
+        let tree = router.get_tree();
+        assert_eq!(tree.get_all_terminals().len(), 3);
+        let maze = MazeRouter::from_keepouts(&[keepout]);
+        for node in &tree.nodes {
+            if let Some(p) = node.parent {
+                let parent = &tree.nodes[p];
+                assert!(parent.pt.xcoord == node.pt.xcoord || parent.pt.ycoord == node.pt.ycoord);
+                assert!(!maze.segment_blocked(&parent.pt, &node.pt));
+            }
+        }
+        let t = tree.get_node("terminal_1").unwrap();
+        assert_eq!(t.pt, Point::new(10, 0));
+        assert_eq!(t.path_length, 22);
+    }
+
+    #[test]
+    fn test_insert_terminal_maze_taps_branch() {
+        let mut tree = GlobalRoutingTree::new(Point::new(0, 0));
+        tree.insert_terminal_node(Point::new(20, 0), None);
+        let id = tree
+            .insert_terminal_maze(Point::new(10, 10), &MazeRouter::new(vec![]))
+            .unwrap();
+        let t = tree.get_node(&id).unwrap();
+        let tap = &tree.nodes[t.parent.unwrap()];
+        assert_eq!(tap.node_type, NodeType::Steiner);
+        assert_eq!(tap.pt, Point::new(10, 0));
+        assert_eq!(
+            tree.get_node("terminal_1").unwrap().parent,
+            tree.node_index(&tap.id)
+        );
+        assert_eq!(tree.calculate_total_wirelength(), 30);
+        assert_consistent(&tree);
+
+        // Around a blockage the branch is tapped at an escape line
+        let mut tree = GlobalRoutingTree::new(Point::new(0, 0));
+        tree.insert_terminal_node(Point::new(20, 0), None);
+        let router = MazeRouter::from_keepouts(&[make_keepout(8, 12, 3, 5)]);
+        let id = tree
+            .insert_terminal_maze(Point::new(10, 10), &router)
+            .unwrap();
+        assert_eq!(tree.calculate_total_wirelength(), 20 + 13);
+        let path = tree.find_path_to_source(&id);
+        assert!(path
+            .iter()
+            .all(|n| n.node_type != NodeType::Terminal || n.id == id));
+        assert_consistent(&tree);
+    }
+
+    #[test]
+    fn test_route_maze_reports_unreachable() {
+        let src = Point::new(0, 0);
+        let terminals = vec![Point::new(10, 0), Point::new(30, 30)];
+        let cage = vec![
+            make_keepout(25, 35, 25, 25),
+            make_keepout(25, 35, 35, 35),
+            make_keepout(25, 25, 25, 35),
+            make_keepout(35, 35, 25, 35),
+        ];
+        let mut router = GlobalRouter::new(src, terminals, Some(cage));
+        assert_eq!(router.route_maze(), vec![1]);
+        assert_eq!(router.get_tree().get_all_terminals().len(), 1);
+    }
+
+    #[test]
+    fn test_route_oarsmt() {
+        let src = Point::new(0, 0);
+        let terminals = vec![Point::new(10, 0), Point::new(10, 8)];
+        let keepout = make_keepout(4, 6, -5, 5);
+        let mut router = GlobalRouter::new(src, terminals.clone(), Some(vec![keepout]));
+        assert!(router.route_oarsmt());
+        // Over the keepout at y = 6, then a branch up to (10, 8) and down to (10, 0)
+        assert_eq!(
+            router.get_tree().calculate_total_wirelength(),
+            6 + 10 + 2 + 6
+        );
+
+        let mut blocked = GlobalRouter::new(src, terminals, Some(vec![make_keepout(9, 11, -1, 1)]));
+        assert!(!blocked.route_oarsmt());
+        assert!(blocked.get_tree().get_all_terminals().is_empty());
+    }
+
+    #[test]
+    fn test_soft_keepout_penalizes_connection() {
+        let src = Point::new(0, 0);
+        let terminals = vec![Point::new(0, 20), Point::new(20, 0), Point::new(18, 20)];
+        let route = |penalty| {
+            let keepouts = KeepoutSet::new(vec![
+                Keepout::from_rect(&make_keepout(5, 10, 18, 22)).soft(penalty)
+            ]);
+            let mut router = GlobalRouter::with_keepouts(src, terminals.clone(), keepouts);
+            router.route_with_steiners();
+            let tree = router.get_tree();
+            let t = tree.get_node("terminal_3").unwrap();
+            (tree.nodes[t.parent.unwrap()].pt, t.path_length)
+        };
+        // A small penalty still takes the short hop across the soft keepout
+        assert_eq!(route(1), (Point::new(0, 20), 38));
+        // A large one prefers the branch from below
+        assert_eq!(route(5), (Point::new(18, 0), 38));
+    }
+
+    #[test]
+    fn test_route_maze_respects_halo() {
+        let src = Point::new(0, 0);
+        let terminals = vec![Point::new(20, 0)];
+        let shape = RPolygon::new(&[
+            Point::new(8, -5),
+            Point::new(12, -5),
+            Point::new(12, 5),
+            Point::new(8, 5),
+        ]);
+        let keepouts = KeepoutSet::new(vec![Keepout::new(shape).with_halo(1)]);
+        let mut router = GlobalRouter::with_keepouts(src, terminals, keepouts);
+        assert_eq!(
+            router.keepouts().hard_rects(),
+            vec![make_keepout(7, 13, -6, 6)]
+        );
+        assert!(router.route_maze().is_empty());
+        let t = router.get_tree().get_node("terminal_1").unwrap();
+        assert_eq!(t.path_length, 20 + 2 * 7);
+    }
+
     #[test]
     fn test_terminal_sorting_by_distance() {
         let src = Point::new(0, 0);
@@ -0,0 +1,423 @@
+//! Routing keepouts: rectilinear polygons with spacing halos.
+//!
+//! A `Keepout` is an `RPolygon<i32>` plus a spacing halo and a hard/soft
+//! flag. The halo grows the shape by `halo` in every direction (the
+//! Minkowski sum with a `2 * halo` square), so nothing may come within
+//! `halo` of the shape. Hard keepouts must be avoided; soft keepouts may be
+//! crossed at a wirelength penalty.
+//!
+//! Internally each shape is cut into horizontal slabs of rectangles
+//! (`rpolygon_cut_slabs`) which are grown by the halo; their union is
+//! exactly the blocked region. A `KeepoutSet` stores the rectangles of all
+//! keepouts once in an `RTree`, so queries only visit rectangles near the
+//! query.
+
+use crate::generic::{Contain, Overlap};
+use crate::interval::Interval;
+use crate::point::Point;
+use crate::rpolygon::RPolygon;
+use crate::rpolygon_cut::rpolygon_cut_slabs;
+use crate::rtree::{Bounded, RTree};
+use crate::vlsi_ops::Rectangle;
+
+type Rect = Point<Interval<i32>, Interval<i32>>;
+
+/// Whether a keepout must be avoided or only discouraged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeepoutKind {
+    /// Routes must avoid the keepout
+    #[default]
+    Hard,
+    /// Routes may cross the keepout; each connection touching it costs
+    /// `penalty` extra wirelength units when choosing where to connect
+    Soft { penalty: i32 },
+}
+
+/// A rectilinear keepout with a spacing halo.
+#[derive(Debug, Clone)]
+pub struct Keepout {
+    /// Shape of the keepout
+    shape: RPolygon<i32>,
+    /// Spacing halo around the shape
+    halo: i32,
+    /// Hard or soft
+    pub kind: KeepoutKind,
+    /// Rectangles covering the shape, before the halo is added
+    pieces: Vec<Rect>,
+}
+
+fn grow(rect: &Rect, halo: i32) -> Rect {
+    Point::new(
+        Interval::new(rect.xcoord.lb - halo, rect.xcoord.ub + halo),
+        Interval::new(rect.ycoord.lb - halo, rect.ycoord.ub + halo),
+    )
+}
+
+impl Keepout {
+    /// Creates a hard keepout without halo from a rectilinear polygon.
+    ///
+    /// The polygon may be given in compressed form, where consecutive
+    /// vertices are joined horizontally first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the polygon has no area.
+    pub fn new(shape: RPolygon<i32>) -> Self {
+        let pieces = rpolygon_cut_slabs(&shape);
+        assert!(!pieces.is_empty(), "Keepout shape has no area");
+        Keepout {
+            shape,
+            halo: 0,
+            kind: KeepoutKind::Hard,
+            pieces,
+        }
+    }
+
+    /// Creates a hard keepout without halo from a rectangle.
+    pub fn from_rect(rect: &Rect) -> Self {
+        let (x, y) = (rect.xcoord, rect.ycoord);
+        Keepout {
+            shape: RPolygon::new(&[
+                Point::new(x.lb, y.lb),
+                Point::new(x.ub, y.lb),
+                Point::new(x.ub, y.ub),
+                Point::new(x.lb, y.ub),
+            ]),
+            halo: 0,
+            kind: KeepoutKind::Hard,
+            pieces: vec![*rect],
+        }
+    }
+
+    /// Sets the spacing halo.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `halo` is negative.
+    pub fn with_halo(mut self, halo: i32) -> Self {
+        assert!(halo >= 0, "Halo must not be negative");
+        self.halo = halo;
+        self
+    }
+
+    /// Makes the keepout soft with the given penalty.
+    pub fn soft(mut self, penalty: i32) -> Self {
+        self.kind = KeepoutKind::Soft { penalty };
+        self
+    }
+
+    /// Shape of the keepout.
+    pub fn shape(&self) -> &RPolygon<i32> {
+        &self.shape
+    }
+
+    /// Spacing halo around the shape.
+    pub fn halo(&self) -> i32 {
+        self.halo
+    }
+
+    /// Returns `true` for a hard keepout.
+    pub fn is_hard(&self) -> bool {
+        self.kind == KeepoutKind::Hard
+    }
+
+    /// Rectangles, grown by the halo, whose union is the blocked region.
+    pub fn rects(&self) -> Vec<Rect> {
+        self.pieces.iter().map(|r| grow(r, self.halo)).collect()
+    }
+
+    /// Bounding box of the blocked region.
+    pub fn bounding_box(&self) -> Rect {
+        let rects = self.rects();
+        let mut bbox = rects[0];
+        for r in &rects[1..] {
+            bbox.xcoord = Interval::new(
+                bbox.xcoord.lb.min(r.xcoord.lb),
+                bbox.xcoord.ub.max(r.xcoord.ub),
+            );
+            bbox.ycoord = Interval::new(
+                bbox.ycoord.lb.min(r.ycoord.lb),
+                bbox.ycoord.ub.max(r.ycoord.ub),
+            );
+        }
+        bbox
+    }
+
+    /// Returns `true` if `pt` lies in the blocked region (shape plus halo,
+    /// boundary included).
+    pub fn contains(&self, pt: &Point<i32, i32>) -> bool {
+        self.rects().iter().any(|r| r.contains(pt))
+    }
+}
+
+/// An indexed collection of keepouts.
+#[derive(Debug, Clone)]
+pub struct KeepoutSet {
+    keepouts: Vec<Keepout>,
+    /// Halo-grown rectangles with the index of their keepout
+    rects: Vec<(Rect, usize)>,
+    /// Spatial index over `rects`
+    index: RTree<i32>,
+}
+
+impl Default for KeepoutSet {
+    fn default() -> Self {
+        KeepoutSet::new(Vec::new())
+    }
+}
+
+impl KeepoutSet {
+    pub fn new(keepouts: Vec<Keepout>) -> Self {
+        let rects: Vec<(Rect, usize)> = keepouts
+            .iter()
+            .enumerate()
+            .flat_map(|(k, keepout)| keepout.rects().into_iter().map(move |r| (r, k)))
+            .collect();
+        let index = RTree::bulk_load(&rects.iter().map(|(r, _)| *r).collect::<Vec<_>>());
+        KeepoutSet {
+            keepouts,
+            rects,
+            index,
+        }
+    }
+
+    /// Creates a set of hard rectangular keepouts without halo.
+    pub fn from_rects(rects: &[Rect]) -> Self {
+        KeepoutSet::new(rects.iter().map(Keepout::from_rect).collect())
+    }
+
+    /// Adds a keepout, rebuilding the index; prefer `new` for many keepouts.
+    pub fn push(&mut self, keepout: Keepout) {
+        let mut keepouts = std::mem::take(&mut self.keepouts);
+        keepouts.push(keepout);
+        *self = KeepoutSet::new(keepouts);
+    }
+
+    /// Number of keepouts.
+    pub fn len(&self) -> usize {
+        self.keepouts.len()
+    }
+
+    /// Returns `true` if there are no keepouts.
+    pub fn is_empty(&self) -> bool {
+        self.keepouts.is_empty()
+    }
+
+    /// The keepouts in insertion order.
+    pub fn keepouts(&self) -> &[Keepout] {
+        &self.keepouts
+    }
+
+    /// Halo-grown rectangles of the hard keepouts, e.g. as blockages for
+    /// `MazeRouter` or `oarsmt::build_oarsmt`.
+    pub fn hard_rects(&self) -> Vec<Rect> {
+        self.rects
+            .iter()
+            .filter(|(_, k)| self.keepouts[*k].is_hard())
+            .map(|(r, _)| *r)
+            .collect()
+    }
+
+    /// Rectangles meeting `window`.
+    fn near(&self, window: Rectangle<i32>) -> impl Iterator<Item = &(Rect, usize)> {
+        self.index
+            .query(&window)
+            .into_iter()
+            .map(move |i| &self.rects[i])
+    }
+
+    /// Returns `true` if `pt` lies in a hard keepout.
+    pub fn is_blocked(&self, pt: &Point<i32, i32>) -> bool {
+        self.near(Rectangle::new(*pt, *pt))
+            .any(|(r, k)| self.keepouts[*k].is_hard() && r.contains(pt))
+    }
+
+    /// Cost of a connection through `pt` along the bounding boxes `paths`
+    /// of its wires, each running between opposite corners (the one nearest
+    /// `pt` and the far one).
+    ///
+    /// A keepout is hit if it contains `pt` or blocks a path, i.e. both
+    /// L-shaped routes of the path meet the keepout (its rectangles taken
+    /// together, so a polygon blocks a path no single slab spans). Returns
+    /// `None` if a hard keepout is hit, otherwise the sum of the penalties
+    /// of the soft keepouts hit.
+    pub fn connection_cost(&self, pt: &Point<i32, i32>, paths: &[Rect]) -> Option<i32> {
+        let window = paths
+            .iter()
+            .map(|p| p.bounds())
+            .fold(Rectangle::new(*pt, *pt), |w, b| w.bounding_rect(&b));
+        let mut near: Vec<(usize, Rect)> = self.near(window).map(|&(r, k)| (k, r)).collect();
+        near.sort_unstable_by_key(|&(k, _)| k);
+        let routes: Vec<[[Rect; 2]; 2]> = paths.iter().map(|p| l_routes(pt, p)).collect();
+        let mut hit = Vec::new();
+        let mut start = 0;
+        while start < near.len() {
+            let k = near[start].0;
+            let len = near[start..].iter().take_while(|e| e.0 == k).count();
+            let group = &near[start..start + len];
+            start += len;
+            let meets = |seg: &Rect| group.iter().any(|(_, r)| r.overlaps(seg));
+            let blocked = group.iter().any(|(_, r)| r.contains(pt))
+                || routes
+                    .iter()
+                    .any(|ls| ls.iter().all(|l| l.iter().any(&meets)));
+            if blocked {
+                hit.push(k);
+            }
+        }
+        let mut cost = 0;
+        for k in hit {
+            match self.keepouts[k].kind {
+                KeepoutKind::Hard => return None,
+                KeepoutKind::Soft { penalty } => cost += penalty,
+            }
+        }
+        Some(cost)
+    }
+}
+
+/// The two L-shaped routes across `path` from its point nearest `pt` to the
+/// opposite corner, as pairs of degenerate (segment) rectangles.
+fn l_routes(pt: &Point<i32, i32>, path: &Rect) -> [[Rect; 2]; 2] {
+    let far = |iv: Interval<i32>, v: i32| if iv.lb == v { iv.ub } else { iv.lb };
+    let start = path.nearest_to(pt);
+    let (x0, y0) = (start.xcoord, start.ycoord);
+    let (x1, y1) = (far(path.xcoord, x0), far(path.ycoord, y0));
+    let seg = |xa: i32, xb: i32, ya: i32, yb: i32| {
+        Point::new(
+            Interval::new(xa.min(xb), xa.max(xb)),
+            Interval::new(ya.min(yb), ya.max(yb)),
+        )
+    };
+    [
+        [seg(x0, x1, y0, y0), seg(x1, x1, y0, y1)],
+        [seg(x0, x0, y0, y1), seg(x0, x1, y1, y1)],
+    ]
+}
+
+impl From<Vec<Rect>> for KeepoutSet {
+    fn from(rects: Vec<Rect>) -> Self {
+        KeepoutSet::from_rects(&rects)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x1: i32, x2: i32, y1: i32, y2: i32) -> Rect {
+        Point::new(Interval::new(x1, x2), Interval::new(y1, y2))
+    }
+
+    fn l_shape() -> RPolygon<i32> {
+        RPolygon::new(&[
+            Point::new(0, 0),
+            Point::new(30, 0),
+            Point::new(30, 10),
+            Point::new(10, 10),
+            Point::new(10, 20),
+            Point::new(0, 20),
+        ])
+    }
+
+    #[test]
+    fn test_polygon_keepout_with_halo() {
+        let ko = Keepout::new(l_shape());
+        assert!(ko.contains(&Point::new(5, 15)));
+        assert!(ko.contains(&Point::new(30, 10)));
+        assert!(!ko.contains(&Point::new(20, 15)));
+        let area: i32 = ko
+            .rects()
+            .iter()
+            .map(|r| r.xcoord.length() * r.ycoord.length())
+            .sum();
+        assert_eq!(area, 400);
+
+        let ko = ko.with_halo(2);
+        assert!(ko.contains(&Point::new(12, 15)));
+        assert!(ko.contains(&Point::new(32, 12)));
+        assert!(!ko.contains(&Point::new(13, 13)));
+        assert_eq!(ko.bounding_box(), rect(-2, 32, -2, 22));
+    }
+
+    #[test]
+    fn test_compressed_polygon_keepout() {
+        // The L shape above with its corners implied: (30, 0) and (10, 10)
+        // follow from joining consecutive vertices horizontally first.
+        let ko = Keepout::new(RPolygon::new(&[
+            Point::new(0, 0),
+            Point::new(30, 10),
+            Point::new(10, 20),
+            Point::new(0, 20),
+        ]));
+        assert!(ko.contains(&Point::new(5, 5)));
+        assert!(!ko.contains(&Point::new(20, 15)));
+        let area: i32 = ko
+            .rects()
+            .iter()
+            .map(|r| r.xcoord.length() * r.ycoord.length())
+            .sum();
+        assert_eq!(area, 400);
+        let set = KeepoutSet::new(vec![ko]);
+        assert!(set.is_blocked(&Point::new(5, 5)));
+
+        // Two vertices describe a rectangle.
+        let ko = Keepout::new(RPolygon::new(&[Point::new(0, 0), Point::new(10, 5)]));
+        assert_eq!(ko.rects(), vec![rect(0, 10, 0, 5)]);
+    }
+
+    #[test]
+    fn test_polygon_blocks_path_as_a_whole() {
+        // No single slab spans the path, but both L routes from (-5, 5) to
+        // (35, 15) cross the L shape.
+        let set = KeepoutSet::new(vec![Keepout::new(l_shape())]);
+        let from = Point::new(-5, 5);
+        assert_eq!(set.connection_cost(&from, &[rect(-5, 35, 5, 15)]), None);
+        let bbox = KeepoutSet::from_rects(&[rect(0, 30, 0, 20)]);
+        assert_eq!(bbox.connection_cost(&from, &[rect(-5, 35, 5, 15)]), None);
+
+        // Up the left side and along y = 25 avoids it.
+        assert_eq!(
+            set.connection_cost(&Point::new(-5, 15), &[rect(-5, 35, 15, 25)]),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_keepout_set_queries() {
+        let set = KeepoutSet::new(vec![
+            Keepout::from_rect(&rect(10, 20, 0, 10)),
+            Keepout::new(l_shape()).with_halo(1).soft(7),
+            Keepout::from_rect(&rect(100, 110, 0, 10)).soft(3),
+        ]);
+        assert_eq!(set.len(), 3);
+        assert!(set.is_blocked(&Point::new(15, 5)));
+        // Soft keepouts do not block points.
+        assert!(!set.is_blocked(&Point::new(5, 15)));
+        assert!(!set.is_blocked(&Point::new(50, 50)));
+
+        // A vertical wire at x = 50 touches nothing.
+        assert_eq!(
+            set.connection_cost(&Point::new(50, 0), &[rect(50, 50, -5, 30)]),
+            Some(0)
+        );
+        // Crossing the soft keepouts only.
+        assert_eq!(
+            set.connection_cost(&Point::new(105, -5), &[rect(105, 105, -5, 30)]),
+            Some(3)
+        );
+        assert_eq!(
+            set.connection_cost(
+                &Point::new(5, 30),
+                &[rect(5, 5, -5, 30), rect(105, 105, -5, 30)]
+            ),
+            Some(10)
+        );
+        // The hard rectangle is crossed.
+        assert_eq!(
+            set.connection_cost(&Point::new(15, -5), &[rect(15, 15, -5, 30)]),
+            None
+        );
+        assert_eq!(set.hard_rects(), vec![rect(10, 20, 0, 10)]);
+    }
+}
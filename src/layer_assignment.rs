@@ -0,0 +1,410 @@
+//! Layer assignment of 2D routing trees onto a metal layer stack.
+//!
+//! The stack is the list of `RoutingLayer`s of a `Technology`, each with a
+//! preferred routing direction and a per-GCell-edge track capacity.
+//! `LayerAssigner::assign` maps a 2D `GlobalRoutingTree` onto the stack:
+//! every wire becomes planar on a layer of matching direction, and vias
+//! (edges between coincident nodes on adjacent layers) connect wires on
+//! different layers. Pins sit on layer 0.
+//!
+//! The assignment is a tree dynamic program minimising
+//!
+//! $$\sum_{\text{wires}} c_{ovf} \cdot \text{overflow} + c_{via} \cdot \#\text{vias}$$
+//!
+//! against the usage left by previously assigned nets, so nets assigned in
+//! turn share the layer capacities.
+
+use std::collections::BTreeMap;
+
+use crate::gcell_router::GCellGrid;
+use crate::global_router::{GlobalRoutingTree, NodeType};
+use crate::point::Point;
+use crate::technology::{PreferredDirection, Technology};
+
+/// Cost of one track of overflow, relative to one via of cost 1.
+const OVERFLOW_COST: f64 = 1000.0;
+
+/// Summary of a layer assignment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerReport {
+    /// Number of vias in the assigned tree
+    pub via_count: usize,
+    /// Wirelength per layer of the assigned tree
+    pub layer_wirelength: Vec<i32>,
+    /// Total overflow over all layers after the assignment
+    pub overflow: u32,
+}
+
+/// Direction of the straight wire from `a` to `b`, or `None` for a via or
+/// zero-length edge.
+fn wire_direction(a: &Point<i32, i32>, b: &Point<i32, i32>) -> Option<PreferredDirection> {
+    if a == b {
+        None
+    } else if a.ycoord == b.ycoord {
+        Some(PreferredDirection::Horizontal)
+    } else {
+        Some(PreferredDirection::Vertical)
+    }
+}
+
+/// Returns `true` if every edge of `tree` is either a via between
+/// coincident nodes on the same or adjacent layers, or a straight wire with
+/// both ends on one layer of `tech` whose direction it follows.
+pub fn check_layers(tree: &GlobalRoutingTree, tech: &Technology) -> bool {
+    let nodes = tree.nodes();
+    nodes.iter().all(|node| {
+        let Some(p) = node.parent else { return true };
+        let parent = &nodes[p];
+        if node.layer >= tech.layers.len() || parent.layer >= tech.layers.len() {
+            return false;
+        }
+        match wire_direction(&parent.pt, &node.pt) {
+            None => parent.layer.abs_diff(node.layer) <= 1,
+            Some(dir) => {
+                let straight =
+                    parent.pt.xcoord == node.pt.xcoord || parent.pt.ycoord == node.pt.ycoord;
+                straight && parent.layer == node.layer && tech.layers[node.layer].direction == dir
+            }
+        }
+    })
+}
+
+/// Assigns routing trees to layers while tracking per-layer GCell usage.
+pub struct LayerAssigner {
+    grid: GCellGrid,
+    tech: Technology,
+    via_cost: f64,
+    /// Usage per layer, per GCell edge
+    usage: Vec<Vec<u32>>,
+}
+
+impl LayerAssigner {
+    /// Creates an assigner over the geometry of `grid` and the layers of
+    /// `tech`, lowest (pin layer) first. The capacities come from the layers,
+    /// not from the grid, and `via_cost` is the cost of one via relative to
+    /// the unit overflow cost.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tech` has no horizontal or no vertical layer.
+    pub fn new(grid: GCellGrid, tech: Technology, via_cost: f64) -> Self {
+        for dir in [PreferredDirection::Horizontal, PreferredDirection::Vertical] {
+            assert!(
+                tech.layers.iter().any(|l| l.direction == dir),
+                "Layer stack needs a {:?} layer",
+                dir
+            );
+        }
+        let usage = vec![vec![0; grid.num_edges()]; tech.layers.len()];
+        LayerAssigner {
+            grid,
+            tech,
+            via_cost,
+            usage,
+        }
+    }
+
+    /// Returns the technology whose layers are assigned.
+    pub fn technology(&self) -> &Technology {
+        &self.tech
+    }
+
+    /// Tracks of `layer` used on GCell edge `edge`.
+    pub fn usage(&self, layer: usize, edge: usize) -> u32 {
+        self.usage[layer][edge]
+    }
+
+    /// Tracks of `layer` available on GCell edge `edge`: the layer capacity
+    /// if the edge runs in the layer's direction, otherwise zero.
+    pub fn capacity(&self, layer: usize, edge: usize) -> u32 {
+        let dir = if self.grid.is_horizontal(edge) {
+            PreferredDirection::Horizontal
+        } else {
+            PreferredDirection::Vertical
+        };
+        let l = &self.tech.layers[layer];
+        if l.direction == dir {
+            l.capacity
+        } else {
+            0
+        }
+    }
+
+    /// Total and maximum overflow over all layers and edges.
+    pub fn overflow(&self) -> (u32, u32) {
+        let mut total = 0;
+        let mut max = 0;
+        for (layer, usage) in self.usage.iter().enumerate() {
+            for (edge, &u) in usage.iter().enumerate() {
+                let o = u.saturating_sub(self.capacity(layer, edge));
+                total += o;
+                max = max.max(o);
+            }
+        }
+        (total, max)
+    }
+
+    /// GCell edges crossed by the straight wire from `a` to `b`.
+    fn crossed_edges(&self, a: &Point<i32, i32>, b: &Point<i32, i32>) -> Vec<usize> {
+        let (ca, ra) = self.grid.gcell_of(a);
+        let (cb, rb) = self.grid.gcell_of(b);
+        match wire_direction(a, b) {
+            None => Vec::new(),
+            Some(PreferredDirection::Horizontal) => (ca.min(cb)..ca.max(cb))
+                .map(|c| self.grid.horizontal_edge(c, ra))
+                .collect(),
+            Some(PreferredDirection::Vertical) => (ra.min(rb)..ra.max(rb))
+                .map(|r| self.grid.vertical_edge(ca, r))
+                .collect(),
+        }
+    }
+
+    /// Cost of routing the wire from `a` to `b` on `layer`.
+    fn wire_cost(&self, a: &Point<i32, i32>, b: &Point<i32, i32>, layer: usize) -> f64 {
+        match wire_direction(a, b) {
+            Some(dir) if dir != self.tech.layers[layer].direction => f64::INFINITY,
+            _ => self
+                .crossed_edges(a, b)
+                .iter()
+                .map(|&e| {
+                    let excess = (self.usage[layer][e] + 1).saturating_sub(self.capacity(layer, e));
+                    OVERFLOW_COST * excess as f64
+                })
+                .sum(),
+        }
+    }
+
+    /// Splits every non-straight edge at an L-bend (horizontal first).
+    fn planarize(tree: &mut GlobalRoutingTree) {
+        let n = tree.nodes().len();
+        for v in 0..n {
+            let Some(p) = tree.nodes()[v].parent else {
+                continue;
+            };
+            let (pp, vp) = (tree.nodes()[p].pt, tree.nodes()[v].pt);
+            if pp.xcoord != vp.xcoord && pp.ycoord != vp.ycoord {
+                let (pid, vid) = (tree.nodes()[p].id.clone(), tree.nodes()[v].id.clone());
+                let bend = Point::new(vp.xcoord, pp.ycoord);
+                tree.insert_node_on_branch(NodeType::Steiner, bend, &pid, &vid);
+            }
+        }
+    }
+
+    /// Maps `tree` onto the layer stack in place and records its usage.
+    ///
+    /// Non-straight edges are first split at an L-bend. Steiner nodes take
+    /// the layer of their incoming wire, pins stay on layer 0, and via
+    /// Steiner nodes are inserted wherever wires on different layers meet.
+    pub fn assign(&mut self, tree: &mut GlobalRoutingTree) -> LayerReport {
+        Self::planarize(tree);
+        let num_layers = self.tech.layers.len();
+        let via_cost = self.via_cost;
+        let nodes = tree.nodes();
+        let root = tree.source_index();
+
+        let mut order = vec![root];
+        let mut i = 0;
+        while i < order.len() {
+            order.extend(nodes[order[i]].children.iter().copied());
+            i += 1;
+        }
+
+        // Layers a node occupies when its incoming wire is on layer `l`.
+        let anchor = |v: usize, l: usize| {
+            if nodes[v].node_type == NodeType::Steiner {
+                (l, l)
+            } else {
+                (0, l)
+            }
+        };
+        let span_cost = |l: usize, (lo, hi): (usize, usize)| {
+            via_cost * (lo.saturating_sub(l) + l.saturating_sub(hi)) as f64
+        };
+
+        // cost[v][l]: cheapest subtree of v with its incoming wire on l.
+        let mut cost = vec![vec![f64::INFINITY; num_layers]; nodes.len()];
+        let best_child = |cost: &[Vec<f64>], c: usize, at: (usize, usize)| {
+            (0..num_layers)
+                .map(|lc| (cost[c][lc] + span_cost(lc, at), lc))
+                .min_by(|a, b| a.0.total_cmp(&b.0))
+                .expect("Non-empty layer stack")
+        };
+        for &v in order.iter().rev().filter(|&&v| v != root) {
+            let p = nodes[v].parent.expect("Non-root node has a parent");
+            for l in 0..num_layers {
+                let wire = self.wire_cost(&nodes[p].pt, &nodes[v].pt, l);
+                if wire.is_infinite() {
+                    continue;
+                }
+                let at = anchor(v, l);
+                let below: f64 = nodes[v]
+                    .children
+                    .iter()
+                    .map(|&c| best_child(&cost, c, at).0)
+                    .sum();
+                let pin_vias = if nodes[v].node_type == NodeType::Steiner {
+                    0.0
+                } else {
+                    via_cost * l as f64
+                };
+                cost[v][l] = wire + pin_vias + below;
+            }
+        }
+
+        // Top-down choice of each wire's layer.
+        let mut edge_layer = vec![0usize; nodes.len()];
+        for &v in &order {
+            let at = if v == root {
+                (0, 0)
+            } else {
+                anchor(v, edge_layer[v])
+            };
+            for &c in &nodes[v].children {
+                edge_layer[c] = best_child(&cost, c, at).1;
+            }
+        }
+        for &v in order.iter().filter(|&&v| v != root) {
+            let p = nodes[v].parent.expect("Non-root node has a parent");
+            for e in self.crossed_edges(&nodes[p].pt, &nodes[v].pt) {
+                self.usage[edge_layer[v]][e] += 1;
+            }
+        }
+
+        Self::insert_vias(tree, &order, &edge_layer);
+        tree.update_path_lengths();
+        LayerReport {
+            via_count: tree.via_count(),
+            layer_wirelength: tree.layer_wirelength(),
+            overflow: self.overflow().0,
+        }
+    }
+
+    /// Sets node layers and inserts via stacks so that every wire runs
+    /// between nodes on its assigned layer.
+    fn insert_vias(tree: &mut GlobalRoutingTree, order: &[usize], edge_layer: &[usize]) {
+        fn add_via(
+            tree: &mut GlobalRoutingTree,
+            parent: usize,
+            pt: Point<i32, i32>,
+            layer: usize,
+        ) -> usize {
+            let parent_id = tree.nodes()[parent].id.clone();
+            let id = tree.insert_steiner_node(pt, Some(&parent_id));
+            let idx = tree.node_index(&id).expect("Node just inserted");
+            tree.node_mut(idx).layer = layer;
+            idx
+        }
+
+        let root = tree.source_index();
+        for &v in order {
+            let kids = tree.nodes()[v].children.clone();
+            let pt = tree.nodes()[v].pt;
+            // Nodes at v's position, by layer.
+            let mut vias = BTreeMap::new();
+            if v != root && tree.nodes()[v].node_type == NodeType::Steiner {
+                tree.node_mut(v).layer = edge_layer[v];
+                vias.insert(edge_layer[v], v);
+            } else {
+                tree.node_mut(v).layer = 0;
+                if v != root && edge_layer[v] > 0 {
+                    // The wire lands on a via stack down to the pin.
+                    let mut above = tree.nodes()[v].parent.expect("Non-root node");
+                    for k in (1..=edge_layer[v]).rev() {
+                        above = add_via(tree, above, pt, k);
+                        vias.insert(k, above);
+                    }
+                    tree.reparent_index(v, above);
+                }
+                vias.insert(0, v);
+            }
+            for c in kids {
+                let lc = edge_layer[c];
+                while let Some((&top, &idx)) = vias.last_key_value().filter(|(&k, _)| k < lc) {
+                    vias.insert(top + 1, add_via(tree, idx, pt, top + 1));
+                }
+                while let Some((&bottom, &idx)) = vias.first_key_value().filter(|(&k, _)| k > lc) {
+                    vias.insert(bottom - 1, add_via(tree, idx, pt, bottom - 1));
+                }
+                if vias[&lc] != v {
+                    tree.reparent_index(c, vias[&lc]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::technology::{RoutingLayer, WireRC};
+
+    fn stack(capacity: u32) -> Technology {
+        let rc = WireRC::new(0.1, 0.2);
+        let mut tech = Technology::new();
+        for (name, dir) in [
+            ("M1", PreferredDirection::Horizontal),
+            ("M2", PreferredDirection::Vertical),
+            ("M3", PreferredDirection::Horizontal),
+        ] {
+            tech.add_layer(RoutingLayer::new(name, dir, rc, rc).with_capacity(capacity));
+        }
+        tech
+    }
+
+    fn grid() -> GCellGrid {
+        GCellGrid::new(Point::new(0, 0), 10, 10, 6, 6, 0, 0)
+    }
+
+    #[test]
+    fn test_assign_l_shaped_net() {
+        let mut tree = GlobalRoutingTree::new(Point::new(5, 5));
+        tree.insert_terminal_node(Point::new(45, 35), Some("source"));
+        let mut assigner = LayerAssigner::new(grid(), stack(2), 1.0);
+        let report = assigner.assign(&mut tree);
+        assert!(check_layers(&tree, assigner.technology()));
+        // M1 to the bend, via up to M2, via back down to the pin.
+        assert_eq!(report.layer_wirelength, vec![40, 30]);
+        assert_eq!(report.via_count, 2);
+        assert_eq!(report.overflow, 0);
+        let t = tree.get_node("terminal_1").unwrap();
+        assert_eq!(t.layer, 0);
+        assert_eq!(t.path_length, 70);
+    }
+
+    #[test]
+    fn test_capacity_pushes_nets_up() {
+        let mut assigner = LayerAssigner::new(grid(), stack(1), 1.0);
+        let mut reports = Vec::new();
+        for _ in 0..3 {
+            let mut tree = GlobalRoutingTree::new(Point::new(5, 25));
+            tree.insert_terminal_node(Point::new(45, 25), Some("source"));
+            reports.push(assigner.assign(&mut tree));
+            assert!(check_layers(&tree, assigner.technology()));
+        }
+        assert_eq!(reports[0].layer_wirelength, vec![40]);
+        assert_eq!(reports[0].via_count, 0);
+        // The second net climbs to M3 (two vias at each end).
+        assert_eq!(reports[1].layer_wirelength, vec![0, 0, 40]);
+        assert_eq!(reports[1].via_count, 4);
+        assert_eq!(reports[1].overflow, 0);
+        // No horizontal tracks are left for the third.
+        assert_eq!(reports[2].overflow, 4);
+    }
+
+    #[test]
+    fn test_assign_branching_tree() {
+        let mut tree = GlobalRoutingTree::new(Point::new(5, 5));
+        let s = tree.insert_steiner_node(Point::new(25, 5), Some("source"));
+        tree.insert_terminal_node(Point::new(25, 45), Some(&s));
+        tree.insert_terminal_node(Point::new(55, 5), Some(&s));
+        tree.insert_terminal_node(Point::new(45, 25), Some(&s));
+        let wirelength = tree.calculate_total_wirelength();
+        let mut assigner = LayerAssigner::new(grid(), stack(4), 1.0);
+        let report = assigner.assign(&mut tree);
+        assert!(check_layers(&tree, assigner.technology()));
+        assert_eq!(tree.calculate_total_wirelength(), wirelength);
+        assert_eq!(report.layer_wirelength.iter().sum::<i32>(), wirelength);
+        assert_eq!(tree.get_all_terminals().len(), 3);
+        assert!(tree.get_all_terminals().iter().all(|t| t.layer == 0));
+    }
+}
@@ -62,36 +62,72 @@
 //!
 /// Geometric algorithms module
 pub mod algorithms;
+/// Clock mesh / spine structures driven by a DME tree
+pub mod clock_mesh;
+/// Monte Carlo variation analysis for clock skew
+pub mod clock_variation;
+/// Placement-stage congestion estimation (RUDY, probabilistic L/Z) and heatmaps
+pub mod congestion;
 /// Doubly-linked list node for polygon decomposition
 pub mod dllink;
 /// DME algorithm for clock tree synthesis
 pub mod dme_algorithm;
 /// SVG visualizer for DME clock trees
 pub mod dme_visualizer;
+/// Design rule checking on layered rectangles and rectilinear polygons
+pub mod drc;
 /// Error types for geometric operations
 pub mod error;
+/// Multi-net global routing on a GCell grid with capacities
+pub mod gcell_router;
 /// Generic traits for geometric operations
 pub mod generic;
 /// Global router for Steiner tree routing
 pub mod global_router;
 /// Interval operations and types
 pub mod interval;
+/// Routing keepouts: rectilinear polygons with spacing halos, hard or soft
+pub mod keepout;
+/// Layer assignment of routing trees onto a metal stack with vias
+pub mod layer_assignment;
 /// Manhattan arc geometry for the DME algorithm
 pub mod manhattan_arc;
+/// A* maze router around rectangular and rectilinear polygon blockages
+pub mod maze_router;
 /// Merge object for combining geometric objects
 pub mod merge_obj;
+/// Netlists and multi-net routing with shared congestion accounting
+pub mod netlist;
+/// Obstacle-avoiding rectilinear Steiner trees on the escape graph
+pub mod oarsmt;
 /// Point types and operations
 pub mod point;
 /// Polygon types and operations
 pub mod polygon;
+/// Prim-Dijkstra (PD / PD-II) cost-radius tradeoff trees
+pub mod prim_dijkstra;
 /// Circular doubly-linked list for polygon decomposition
 pub mod rdllist;
+/// Export of routed nets as DEF-style segments and JSON, and JSON import
+pub mod route_export;
+/// Post-optimization of routing trees (edge merging, detour removal, swaps)
+pub mod route_optimizer;
 /// Rectilinear polygon types and operations
 pub mod rpolygon;
 /// Rectilinear polygon cut (decomposition) operations
 pub mod rpolygon_cut;
 /// Rectilinear polygon hull operations
 pub mod rpolygon_hull;
+/// Rectilinear Steiner minimal tree construction
+pub mod rsmt;
+/// Static R-tree spatial index with STR bulk loading
+pub mod rtree;
+/// Sink clustering for hierarchical clock tree synthesis
+pub mod sink_clustering;
+/// Technology description: layer RC and process corners
+pub mod technology;
+/// Consistency checker for DME clock trees
+pub mod tree_verifier;
 /// Vector2 types and operations
 pub mod vector2;
 /// VLSI-specific geometric operations
@@ -0,0 +1,383 @@
+//! A* maze routing on a track graph around rectilinear blockages.
+//!
+//! The track graph is the grid formed by the coordinates of the start
+//! point, the targets and the escape lines of every blockage, one unit
+//! outside each blockage edge. Blockages are closed regions, so a wire may
+//! not touch them. This grid contains a shortest obstacle-avoiding
+//! rectilinear path whenever one exists, so A* on it finds the true
+//! shortest path. Ties are broken by the number of bends.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::generic::{Contain, MinDist};
+use crate::interval::{Hull, Interval};
+use crate::point::Point;
+use crate::rpolygon::RPolygon;
+
+/// A routing blockage: an axis-parallel rectangle or a rectilinear polygon.
+#[derive(Debug, Clone)]
+pub enum Blockage {
+    /// Rectangular keepout, as used by `GlobalRoutingTree`
+    Rect(Point<Interval<i32>, Interval<i32>>),
+    /// Rectilinear polygon blockage
+    Polygon(RPolygon<i32>),
+}
+
+impl From<Point<Interval<i32>, Interval<i32>>> for Blockage {
+    fn from(rect: Point<Interval<i32>, Interval<i32>>) -> Self {
+        Blockage::Rect(rect)
+    }
+}
+
+impl From<RPolygon<i32>> for Blockage {
+    fn from(poly: RPolygon<i32>) -> Self {
+        Blockage::Polygon(poly)
+    }
+}
+
+impl Blockage {
+    /// Returns `true` if `pt` lies inside or on the boundary of the blockage.
+    pub fn contains_point(&self, pt: &Point<i32, i32>) -> bool {
+        match self {
+            Blockage::Rect(rect) => rect.contains(pt),
+            Blockage::Polygon(poly) => {
+                let vertices = poly.vertices();
+                RPolygon::<i32>::point_in_rpolygon(&vertices, pt)
+                    || polygon_edges(&vertices).any(|e| e.contains(pt))
+            }
+        }
+    }
+
+    /// Returns `true` if the axis-parallel segment from `a` to `b` touches
+    /// the blockage.
+    pub fn blocks_segment(&self, a: &Point<i32, i32>, b: &Point<i32, i32>) -> bool {
+        if self.contains_point(a) || self.contains_point(b) {
+            return true;
+        }
+        let seg = a.hull_with(b);
+        match self {
+            Blockage::Rect(rect) => rect.blocks(&seg),
+            // With both ends outside, the segment meets the polygon only if
+            // it meets the boundary.
+            Blockage::Polygon(poly) => {
+                polygon_edges(&poly.vertices()).any(|e| e.min_dist_with(&seg) == 0)
+            }
+        }
+    }
+
+    /// Escape coordinates: one unit outside each blockage edge.
+    pub(crate) fn escape_lines(&self) -> (Vec<i32>, Vec<i32>) {
+        match self {
+            Blockage::Rect(rect) => (
+                vec![rect.xcoord.lb - 1, rect.xcoord.ub + 1],
+                vec![rect.ycoord.lb - 1, rect.ycoord.ub + 1],
+            ),
+            Blockage::Polygon(poly) => {
+                let vertices = poly.vertices();
+                let xs = vertices
+                    .iter()
+                    .flat_map(|v| [v.xcoord - 1, v.xcoord + 1])
+                    .collect();
+                let ys = vertices
+                    .iter()
+                    .flat_map(|v| [v.ycoord - 1, v.ycoord + 1])
+                    .collect();
+                (xs, ys)
+            }
+        }
+    }
+}
+
+/// Boundary edges of a polygon as degenerate rectangles.
+fn polygon_edges(
+    vertices: &[Point<i32, i32>],
+) -> impl Iterator<Item = Point<Interval<i32>, Interval<i32>>> + '_ {
+    let n = vertices.len();
+    (0..n).map(move |i| vertices[i].hull_with(&vertices[(i + 1) % n]))
+}
+
+/// A routed path from the start point to one of the targets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MazePath {
+    /// Start point, bend points and target, in order
+    pub points: Vec<Point<i32, i32>>,
+    /// Rectilinear length of the path
+    pub length: i32,
+    /// Index of the reached target in the slice passed to `route`
+    pub target: usize,
+}
+
+impl MazePath {
+    /// The bend points, excluding the start point and the target.
+    pub fn bends(&self) -> &[Point<i32, i32>] {
+        &self.points[1..self.points.len() - 1]
+    }
+}
+
+/// A* search state ordered by increasing `(f, bends)`.
+#[derive(PartialEq, Eq)]
+struct SearchState {
+    estimate: i64,
+    bends: u32,
+    cost: i64,
+    node: usize,
+}
+
+impl Ord for SearchState {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.estimate, other.bends, other.node).cmp(&(self.estimate, self.bends, self.node))
+    }
+}
+
+impl PartialOrd for SearchState {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Shortest-path router that avoids a set of blockages.
+#[derive(Debug, Clone, Default)]
+pub struct MazeRouter {
+    blockages: Vec<Blockage>,
+}
+
+impl MazeRouter {
+    pub fn new(blockages: Vec<Blockage>) -> Self {
+        MazeRouter { blockages }
+    }
+
+    /// Creates a router from rectangular keepouts.
+    pub fn from_keepouts(keepouts: &[Point<Interval<i32>, Interval<i32>>]) -> Self {
+        MazeRouter::new(keepouts.iter().cloned().map(Blockage::from).collect())
+    }
+
+    /// Returns the blockages.
+    pub fn blockages(&self) -> &[Blockage] {
+        &self.blockages
+    }
+
+    /// Returns `true` if `pt` is inside any blockage.
+    pub fn is_blocked(&self, pt: &Point<i32, i32>) -> bool {
+        self.blockages.iter().any(|b| b.contains_point(pt))
+    }
+
+    /// Returns `true` if the axis-parallel segment from `a` to `b` touches
+    /// any blockage.
+    pub fn segment_blocked(&self, a: &Point<i32, i32>, b: &Point<i32, i32>) -> bool {
+        self.blockages.iter().any(|bl| bl.blocks_segment(a, b))
+    }
+
+    /// Finds the shortest blockage-free rectilinear path from `from` to the
+    /// nearest reachable target, preferring fewer bends among equally short
+    /// paths. Returns `None` if `from` is blocked or no target is reachable.
+    pub fn route(&self, from: Point<i32, i32>, targets: &[Point<i32, i32>]) -> Option<MazePath> {
+        if self.is_blocked(&from) {
+            return None;
+        }
+        let goals: Vec<usize> = (0..targets.len())
+            .filter(|&i| !self.is_blocked(&targets[i]))
+            .collect();
+        if goals.is_empty() {
+            return None;
+        }
+
+        let mut xs = vec![from.xcoord];
+        let mut ys = vec![from.ycoord];
+        for &i in &goals {
+            xs.push(targets[i].xcoord);
+            ys.push(targets[i].ycoord);
+        }
+        for b in &self.blockages {
+            let (bx, by) = b.escape_lines();
+            xs.extend(bx);
+            ys.extend(by);
+        }
+        xs.sort_unstable();
+        xs.dedup();
+        ys.sort_unstable();
+        ys.dedup();
+
+        let nx = xs.len();
+        let point_of = |v: usize| Point::new(xs[v % nx], ys[v / nx]);
+        let index_of = |p: &Point<i32, i32>| {
+            let xi = xs.binary_search(&p.xcoord).expect("Coordinate on grid");
+            let yi = ys.binary_search(&p.ycoord).expect("Coordinate on grid");
+            yi * nx + xi
+        };
+        let heuristic = |p: &Point<i32, i32>| {
+            goals
+                .iter()
+                .map(|&i| p.min_dist_with(&targets[i]) as i64)
+                .min()
+                .unwrap_or(0)
+        };
+
+        // Search states are (grid vertex, direction of arrival); direction 4
+        // is the start, where no bend is counted.
+        let num_vertices = nx * ys.len();
+        let state = |v: usize, dir: usize| v * 5 + dir;
+        let mut best: Vec<(i64, u32)> = vec![(i64::MAX, u32::MAX); num_vertices * 5];
+        let mut prev: Vec<Option<usize>> = vec![None; num_vertices * 5];
+        let mut goal_at = vec![None; num_vertices];
+        for &i in &goals {
+            let v = index_of(&targets[i]);
+            goal_at[v].get_or_insert(i);
+        }
+
+        let start = state(index_of(&from), 4);
+        best[start] = (0, 0);
+        let mut heap = BinaryHeap::new();
+        heap.push(SearchState {
+            estimate: heuristic(&from),
+            bends: 0,
+            cost: 0,
+            node: start,
+        });
+
+        while let Some(SearchState {
+            bends, cost, node, ..
+        }) = heap.pop()
+        {
+            if (cost, bends) > best[node] {
+                continue;
+            }
+            let (v, dir) = (node / 5, node % 5);
+            let pv = point_of(v);
+            if let Some(target) = goal_at[v] {
+                return Some(self.trace_path(node, &prev, &point_of, cost, target));
+            }
+            let (xi, yi) = (v % nx, v / nx);
+            let moves = [
+                (xi + 1 < nx).then(|| v + 1),
+                (xi > 0).then(|| v - 1),
+                (yi + 1 < ys.len()).then(|| v + nx),
+                (yi > 0).then(|| v - nx),
+            ];
+            for (d, next) in moves.iter().enumerate() {
+                let Some(u) = *next else { continue };
+                let pu = point_of(u);
+                if self.segment_blocked(&pv, &pu) {
+                    continue;
+                }
+                let next_cost = cost + pv.min_dist_with(&pu) as i64;
+                let next_bends = bends + u32::from(dir != 4 && dir != d);
+                let s = state(u, d);
+                if (next_cost, next_bends) < best[s] {
+                    best[s] = (next_cost, next_bends);
+                    prev[s] = Some(node);
+                    heap.push(SearchState {
+                        estimate: next_cost + heuristic(&pu),
+                        bends: next_bends,
+                        cost: next_cost,
+                        node: s,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn trace_path(
+        &self,
+        goal: usize,
+        prev: &[Option<usize>],
+        point_of: &dyn Fn(usize) -> Point<i32, i32>,
+        length: i64,
+        target: usize,
+    ) -> MazePath {
+        let mut states = vec![goal];
+        while let Some(p) = prev[*states.last().expect("Non-empty")] {
+            states.push(p);
+        }
+        states.reverse();
+        // Keep the start, the target and every state whose arrival direction
+        // differs from the next one.
+        let mut points = vec![point_of(states[0] / 5)];
+        for w in states.windows(2).skip(1) {
+            if w[0] % 5 != w[1] % 5 {
+                points.push(point_of(w[0] / 5));
+            }
+        }
+        let end = point_of(goal / 5);
+        if points.last() != Some(&end) {
+            points.push(end);
+        }
+        MazePath {
+            points,
+            length: length as i32,
+            target,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x1: i32, x2: i32, y1: i32, y2: i32) -> Blockage {
+        Blockage::Rect(Point::new(Interval::new(x1, x2), Interval::new(y1, y2)))
+    }
+
+    #[test]
+    fn test_unobstructed_route() {
+        let router = MazeRouter::default();
+        let path = router
+            .route(Point::new(0, 0), &[Point::new(10, 5), Point::new(3, 3)])
+            .unwrap();
+        assert_eq!(path.target, 1);
+        assert_eq!(path.length, 6);
+        assert_eq!(path.bends().len(), 1);
+    }
+
+    #[test]
+    fn test_detour_around_wall() {
+        let router = MazeRouter::new(vec![rect(4, 6, -5, 5)]);
+        let path = router
+            .route(Point::new(0, 0), &[Point::new(10, 0)])
+            .unwrap();
+        assert_eq!(path.length, 10 + 2 * 6);
+        assert_eq!(path.bends().len(), 2);
+        for w in path.points.windows(2) {
+            assert!(!router.segment_blocked(&w[0], &w[1]));
+        }
+    }
+
+    #[test]
+    fn test_polygon_blockage() {
+        // L-shaped blockage whose notch holds the target
+        let poly = RPolygon::new(&[
+            Point::new(2, -4),
+            Point::new(8, -4),
+            Point::new(8, 4),
+            Point::new(6, 4),
+            Point::new(6, -2),
+            Point::new(2, -2),
+        ]);
+        let router = MazeRouter::new(vec![Blockage::from(poly)]);
+        assert!(router.is_blocked(&Point::new(7, 0)));
+        assert!(!router.is_blocked(&Point::new(4, 0)));
+        let path = router
+            .route(Point::new(10, 0), &[Point::new(4, 0)])
+            .unwrap();
+        assert_eq!(path.length, 6 + 2 * 5);
+        for w in path.points.windows(2) {
+            assert!(!router.segment_blocked(&w[0], &w[1]));
+        }
+    }
+
+    #[test]
+    fn test_unreachable_target() {
+        let router = MazeRouter::new(vec![rect(-1, 1, -1, 1)]);
+        assert!(router
+            .route(Point::new(0, 0), &[Point::new(5, 5)])
+            .is_none());
+        let boxed = MazeRouter::new(vec![
+            rect(-3, 3, 2, 2),
+            rect(-3, 3, -2, -2),
+            rect(-3, -3, -2, 2),
+            rect(3, 3, -2, 2),
+        ]);
+        assert!(boxed.route(Point::new(0, 0), &[Point::new(9, 9)]).is_none());
+    }
+}
@@ -0,0 +1,389 @@
+//! Netlists and multi-net routing with shared resource accounting.
+//!
+//! A `Netlist` holds named nets, each with a driver, its sinks, a weight
+//! and a criticality. `Netlist::route_all` orders the nets, routes them
+//! together on a `GCellGrid` so that they compete for the same edge
+//! capacities, and returns one `GlobalRoutingTree` per net together with
+//! the shared edge usage and an aggregate report.
+
+use std::collections::HashMap;
+
+use crate::error::{NetlistError, NetlistResult};
+use crate::gcell_router::{GCellGrid, GridGlobalRouter, GridNet, NegotiationConfig};
+use crate::global_router::GlobalRoutingTree;
+use crate::interval::Interval;
+use crate::point::Point;
+
+/// A named net: one driver and its sinks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Net {
+    /// Net name, unique within a netlist
+    pub name: String,
+    /// Driver pin position
+    pub driver: Point<i32, i32>,
+    /// Sink pin positions
+    pub sinks: Vec<Point<i32, i32>>,
+    /// Weight of the net in the weighted wirelength (default 1.0)
+    pub weight: f64,
+    /// Timing criticality; more critical nets are routed first (default 0.0)
+    pub criticality: f64,
+}
+
+impl Net {
+    pub fn new(name: &str, driver: Point<i32, i32>, sinks: Vec<Point<i32, i32>>) -> Self {
+        Net {
+            name: name.to_string(),
+            driver,
+            sinks,
+            weight: 1.0,
+            criticality: 0.0,
+        }
+    }
+
+    /// Sets the weight of the net.
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Sets the criticality of the net.
+    pub fn with_criticality(mut self, criticality: f64) -> Self {
+        self.criticality = criticality;
+        self
+    }
+
+    /// Driver followed by the sinks.
+    pub fn pins(&self) -> impl Iterator<Item = &Point<i32, i32>> {
+        std::iter::once(&self.driver).chain(&self.sinks)
+    }
+
+    /// Bounding box of all pins.
+    pub fn bounding_box(&self) -> Point<Interval<i32>, Interval<i32>> {
+        let (mut xlo, mut ylo) = (self.driver.xcoord, self.driver.ycoord);
+        let (mut xhi, mut yhi) = (xlo, ylo);
+        for p in &self.sinks {
+            xlo = xlo.min(p.xcoord);
+            xhi = xhi.max(p.xcoord);
+            ylo = ylo.min(p.ycoord);
+            yhi = yhi.max(p.ycoord);
+        }
+        Point::new(Interval::new(xlo, xhi), Interval::new(ylo, yhi))
+    }
+
+    /// Half-perimeter wirelength of the bounding box.
+    pub fn hpwl(&self) -> i32 {
+        let bbox = self.bounding_box();
+        bbox.xcoord.length() + bbox.ycoord.length()
+    }
+}
+
+/// Order in which `Netlist::route_all` routes the nets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NetOrder {
+    /// Most critical first; ties broken by smaller bounding box
+    #[default]
+    Criticality,
+    /// Smallest bounding box (half perimeter) first
+    BoundingBox,
+}
+
+/// A collection of uniquely named nets.
+#[derive(Debug, Clone, Default)]
+pub struct Netlist {
+    nets: Vec<Net>,
+    index: HashMap<String, usize>,
+}
+
+/// The route of one net.
+#[derive(Clone)]
+pub struct NetRoute {
+    /// Net name
+    pub name: String,
+    /// Routing tree from the driver to the sinks
+    pub tree: GlobalRoutingTree,
+    /// Total wirelength of the tree
+    pub wirelength: i32,
+    /// Whether the route uses an edge whose capacity is exceeded
+    pub overflowed: bool,
+}
+
+/// Aggregate statistics of `Netlist::route_all`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetlistReport {
+    /// Sum of the tree wirelengths
+    pub wirelength: i64,
+    /// Sum of the tree wirelengths scaled by the net weights
+    pub weighted_wirelength: f64,
+    /// Sum over all edges of the usage above capacity
+    pub total_overflow: u32,
+    /// Largest usage above capacity of any edge
+    pub max_overflow: u32,
+    /// Rip-up and reroute iterations performed
+    pub iterations: usize,
+    /// Nets that overflow or have a pin outside the grid, in routing order
+    pub failed: Vec<String>,
+}
+
+/// Result of `Netlist::route_all`.
+#[derive(Clone)]
+pub struct NetlistRouting {
+    /// Routed nets in routing order (nets with a pin outside the grid are
+    /// not routed)
+    pub routes: Vec<NetRoute>,
+    /// Shared number of nets using each grid edge
+    pub usage: Vec<u32>,
+    /// Aggregate statistics
+    pub report: NetlistReport,
+}
+
+impl NetlistRouting {
+    /// The route of the named net, if it was routed.
+    pub fn route(&self, name: &str) -> Option<&NetRoute> {
+        self.routes.iter().find(|r| r.name == name)
+    }
+}
+
+impl Netlist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a net.
+    ///
+    /// Fails if the name is taken, the net has no sinks, or its weight or
+    /// criticality is NaN, infinite or negative.
+    pub fn add_net(&mut self, net: Net) -> NetlistResult<()> {
+        if self.index.contains_key(&net.name) {
+            return Err(NetlistError::DuplicateNet(net.name));
+        }
+        if net.sinks.is_empty() {
+            return Err(NetlistError::EmptyNet(net.name));
+        }
+        for value in [net.weight, net.criticality] {
+            if !value.is_finite() || value < 0.0 {
+                return Err(NetlistError::InvalidWeight {
+                    net: net.name,
+                    value,
+                });
+            }
+        }
+        self.index.insert(net.name.clone(), self.nets.len());
+        self.nets.push(net);
+        Ok(())
+    }
+
+    /// Number of nets.
+    pub fn len(&self) -> usize {
+        self.nets.len()
+    }
+
+    /// Returns `true` if the netlist has no nets.
+    pub fn is_empty(&self) -> bool {
+        self.nets.is_empty()
+    }
+
+    /// All nets in insertion order.
+    pub fn nets(&self) -> &[Net] {
+        &self.nets
+    }
+
+    /// The net with the given name.
+    pub fn get(&self, name: &str) -> Option<&Net> {
+        self.index.get(name).map(|&i| &self.nets[i])
+    }
+
+    /// Nets in routing order. Remaining ties keep insertion order.
+    pub fn ordered(&self, order: NetOrder) -> Vec<&Net> {
+        let mut nets: Vec<&Net> = self.nets.iter().collect();
+        match order {
+            NetOrder::Criticality => nets.sort_by(|a, b| {
+                b.criticality
+                    .total_cmp(&a.criticality)
+                    .then(a.hpwl().cmp(&b.hpwl()))
+            }),
+            NetOrder::BoundingBox => nets.sort_by_key(|n| n.hpwl()),
+        }
+        nets
+    }
+
+    /// Routes every net on `grid` with the default negotiation settings.
+    pub fn route_all(&self, grid: &GCellGrid, order: NetOrder) -> NetlistRouting {
+        self.route_all_with(grid, order, NegotiationConfig::default())
+    }
+
+    /// Routes every net on `grid`.
+    ///
+    /// Nets are pattern routed in the given order against a shared edge
+    /// usage map, so earlier nets get the uncongested resources; nets on
+    /// overflowing edges are then ripped up and rerouted by negotiation.
+    /// Nets with a pin outside the grid are reported as failed and not
+    /// routed.
+    pub fn route_all_with(
+        &self,
+        grid: &GCellGrid,
+        order: NetOrder,
+        config: NegotiationConfig,
+    ) -> NetlistRouting {
+        use crate::generic::Contain;
+
+        let extent = grid.extent();
+        let mut failed = Vec::new();
+        let mut routable = Vec::new();
+        for net in self.ordered(order) {
+            if net.pins().all(|p| extent.contains(p)) {
+                routable.push(net);
+            } else {
+                failed.push(net.name.clone());
+            }
+        }
+
+        let grid_nets = routable
+            .iter()
+            .map(|n| GridNet::new(&n.name, n.driver, n.sinks.clone()))
+            .collect();
+        let mut router = GridGlobalRouter::new(grid.clone(), grid_nets);
+        router.config = config;
+        let result = router.route();
+
+        let mut routes = Vec::with_capacity(routable.len());
+        let mut wirelength = 0;
+        let mut weighted_wirelength = 0.0;
+        for ((net, tree), edges) in routable.iter().zip(result.trees).zip(&result.net_edges) {
+            let overflowed = edges.iter().any(|&e| result.usage[e] > grid.capacity(e));
+            if overflowed {
+                failed.push(net.name.clone());
+            }
+            let length = tree.calculate_total_wirelength();
+            wirelength += length as i64;
+            weighted_wirelength += net.weight * length as f64;
+            routes.push(NetRoute {
+                name: net.name.clone(),
+                tree,
+                wirelength: length,
+                overflowed,
+            });
+        }
+
+        NetlistRouting {
+            routes,
+            usage: result.usage,
+            report: NetlistReport {
+                wirelength,
+                weighted_wirelength,
+                total_overflow: result.total_overflow,
+                max_overflow: result.max_overflow,
+                iterations: result.iterations,
+                failed,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Netlist {
+        let mut netlist = Netlist::new();
+        netlist
+            .add_net(Net::new(
+                "wide",
+                Point::new(5, 5),
+                vec![Point::new(75, 35), Point::new(45, 5)],
+            ))
+            .unwrap();
+        netlist
+            .add_net(
+                Net::new("short", Point::new(15, 15), vec![Point::new(35, 15)]).with_weight(2.0),
+            )
+            .unwrap();
+        netlist
+            .add_net(
+                Net::new("clk", Point::new(5, 35), vec![Point::new(65, 25)]).with_criticality(0.9),
+            )
+            .unwrap();
+        netlist
+    }
+
+    #[test]
+    fn test_add_net_validation() {
+        let mut netlist = sample();
+        assert_eq!(netlist.len(), 3);
+        assert_eq!(
+            netlist.add_net(Net::new("short", Point::new(0, 0), vec![Point::new(1, 1)])),
+            Err(NetlistError::DuplicateNet("short".to_string()))
+        );
+        assert_eq!(
+            netlist.add_net(Net::new("lonely", Point::new(0, 0), vec![])),
+            Err(NetlistError::EmptyNet("lonely".to_string()))
+        );
+        assert!(matches!(
+            netlist.add_net(
+                Net::new("bad", Point::new(0, 0), vec![Point::new(1, 1)]).with_weight(f64::NAN)
+            ),
+            Err(NetlistError::InvalidWeight { .. })
+        ));
+        assert_eq!(netlist.len(), 3);
+        assert_eq!(netlist.get("clk").unwrap().hpwl(), 70);
+    }
+
+    #[test]
+    fn test_net_ordering() {
+        let netlist = sample();
+        let names = |order| -> Vec<String> {
+            netlist
+                .ordered(order)
+                .iter()
+                .map(|n| n.name.clone())
+                .collect()
+        };
+        assert_eq!(names(NetOrder::Criticality), ["clk", "short", "wide"]);
+        assert_eq!(names(NetOrder::BoundingBox), ["short", "clk", "wide"]);
+    }
+
+    #[test]
+    fn test_route_all() {
+        let netlist = sample();
+        let grid = GCellGrid::new(Point::new(0, 0), 10, 10, 8, 4, 4, 4);
+        let routing = netlist.route_all(&grid, NetOrder::Criticality);
+        let report = &routing.report;
+        assert!(report.failed.is_empty());
+        assert_eq!(report.total_overflow, 0);
+        assert_eq!(routing.routes.len(), 3);
+        assert_eq!(routing.routes[0].name, "clk");
+        let wide = routing.route("wide").unwrap();
+        assert_eq!(wide.tree.get_all_terminals().len(), 2);
+        assert!(wide.wirelength >= netlist.get("wide").unwrap().hpwl());
+        let total: i64 = routing.routes.iter().map(|r| r.wirelength as i64).sum();
+        assert_eq!(report.wirelength, total);
+        let short = routing.route("short").unwrap().wirelength as f64;
+        assert!((report.weighted_wirelength - (total as f64 + short)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_route_all_reports_failures() {
+        let mut netlist = Netlist::new();
+        for (name, y) in [("a", 5), ("b", 6)] {
+            netlist
+                .add_net(Net::new(name, Point::new(5, y), vec![Point::new(35, y)]))
+                .unwrap();
+        }
+        netlist
+            .add_net(Net::new(
+                "outside",
+                Point::new(5, 5),
+                vec![Point::new(500, 5)],
+            ))
+            .unwrap();
+        // A single row of GCells with one track: both nets need the same edges.
+        let grid = GCellGrid::new(Point::new(0, 0), 10, 10, 4, 1, 1, 1);
+        let routing = netlist.route_all(&grid, NetOrder::BoundingBox);
+        let report = &routing.report;
+        assert_eq!(report.failed, ["outside", "a", "b"]);
+        assert!(routing.route("outside").is_none());
+        assert_eq!(report.total_overflow, 3);
+        assert_eq!(report.max_overflow, 1);
+        assert!(routing.usage.iter().all(|&u| u == 2));
+        assert!(routing.routes.iter().all(|r| r.overflowed));
+    }
+}
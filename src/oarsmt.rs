@@ -0,0 +1,394 @@
+//! Obstacle-avoiding rectilinear Steiner minimal trees (OARSMT).
+//!
+//! The tree is built on the escape graph: the Hanan grid of the pins
+//! extended by the escape lines of every keepout, one unit outside each of
+//! its edges (keepouts are closed, so their boundary lines are unusable).
+//! Grid vertices inside a keepout and grid edges that cross or touch one are
+//! removed, so any tree in the graph avoids the keepouts by construction.
+//!
+//! The Steiner tree in the graph is found with the KMB heuristic
+//! (Kou-Markowsky-Berman: MST of the shortest-path metric closure, expanded
+//! to paths, re-spanned and pruned), then refined in the manner of iterated
+//! 1-Steiner: each round adds the escape-graph vertex that, promoted to a
+//! terminal, shortens the KMB tree the most, and drops added vertices that
+//! no longer branch.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::generic::MinDist;
+use crate::global_router::GlobalRoutingTree;
+use crate::interval::{Hull, Interval};
+use crate::point::Point;
+use crate::rsmt::{build_routing_tree_with_bends, distinct_pins};
+
+/// Upper bound on refinement rounds.
+const MAX_REFINE_ROUNDS: usize = 8;
+
+/// Escape graphs larger than this skip the refinement, whose rounds cost
+/// one KMB run per vertex.
+const MAX_REFINE_VERTICES: usize = 1024;
+
+/// Tree edges as pairs of escape-graph vertices.
+type GraphEdges = Vec<(usize, usize)>;
+
+/// The escape graph: grid vertices with blocked flags and, per vertex,
+/// whether the edge to the right and the edge upwards are usable.
+struct EscapeGraph {
+    xs: Vec<i32>,
+    ys: Vec<i32>,
+    blocked: Vec<bool>,
+    right_ok: Vec<bool>,
+    up_ok: Vec<bool>,
+}
+
+impl EscapeGraph {
+    fn new(pins: &[Point<i32, i32>], keepouts: &[Point<Interval<i32>, Interval<i32>>]) -> Self {
+        use crate::generic::Contain;
+
+        let mut xs: Vec<i32> = pins.iter().map(|p| p.xcoord).collect();
+        let mut ys: Vec<i32> = pins.iter().map(|p| p.ycoord).collect();
+        for ko in keepouts {
+            xs.extend([ko.xcoord.lb - 1, ko.xcoord.ub + 1]);
+            ys.extend([ko.ycoord.lb - 1, ko.ycoord.ub + 1]);
+        }
+        xs.sort_unstable();
+        xs.dedup();
+        ys.sort_unstable();
+        ys.dedup();
+
+        let (nx, ny) = (xs.len(), ys.len());
+        let point = |v: usize| Point::new(xs[v % nx], ys[v / nx]);
+        let blocked: Vec<bool> = (0..nx * ny)
+            .map(|v| keepouts.iter().any(|ko| ko.contains(&point(v))))
+            .collect();
+        let usable = |a: usize, b: usize| {
+            if blocked[a] || blocked[b] {
+                return false;
+            }
+            let seg = point(a).hull_with(&point(b));
+            !keepouts.iter().any(|ko| ko.blocks(&seg))
+        };
+        let right_ok = (0..nx * ny)
+            .map(|v| v % nx + 1 < nx && usable(v, v + 1))
+            .collect();
+        let up_ok = (0..nx * ny)
+            .map(|v| v / nx + 1 < ny && usable(v, v + nx))
+            .collect();
+        EscapeGraph {
+            xs,
+            ys,
+            blocked,
+            right_ok,
+            up_ok,
+        }
+    }
+
+    fn point(&self, v: usize) -> Point<i32, i32> {
+        let nx = self.xs.len();
+        Point::new(self.xs[v % nx], self.ys[v / nx])
+    }
+
+    fn vertex(&self, p: &Point<i32, i32>) -> usize {
+        let xi = self.xs.binary_search(&p.xcoord).expect("Pin on grid");
+        let yi = self.ys.binary_search(&p.ycoord).expect("Pin on grid");
+        yi * self.xs.len() + xi
+    }
+
+    fn num_vertices(&self) -> usize {
+        self.blocked.len()
+    }
+
+    /// Usable neighbours of `v` with the edge length.
+    fn neighbors(&self, v: usize) -> impl Iterator<Item = (usize, i64)> + '_ {
+        let nx = self.xs.len();
+        let candidates = [
+            self.right_ok[v].then(|| v + 1),
+            (v % nx > 0 && self.right_ok[v - 1]).then(|| v - 1),
+            self.up_ok[v].then(|| v + nx),
+            (v >= nx && self.up_ok[v - nx]).then(|| v - nx),
+        ];
+        candidates.into_iter().flatten().map(move |u| {
+            let d = self.point(v).min_dist_with(&self.point(u)) as i64;
+            (u, d)
+        })
+    }
+
+    /// Single-source shortest paths: distances and predecessors.
+    fn dijkstra(&self, src: usize) -> (Vec<i64>, Vec<Option<usize>>) {
+        let n = self.num_vertices();
+        let mut dist = vec![i64::MAX; n];
+        let mut prev = vec![None; n];
+        let mut heap = BinaryHeap::new();
+        dist[src] = 0;
+        heap.push(Reverse((0i64, src)));
+        while let Some(Reverse((d, v))) = heap.pop() {
+            if d > dist[v] {
+                continue;
+            }
+            for (u, w) in self.neighbors(v) {
+                if d + w < dist[u] {
+                    dist[u] = d + w;
+                    prev[u] = Some(v);
+                    heap.push(Reverse((d + w, u)));
+                }
+            }
+        }
+        (dist, prev)
+    }
+
+    /// KMB Steiner tree over `terms` in the graph, with leaves pruned unless
+    /// they are among the first `required` terms. Returns
+    /// `(wirelength, edges)`, or `None` if some terminal is unreachable.
+    fn kmb(&self, terms: &[usize], required: usize) -> Option<(i64, GraphEdges)> {
+        let n = self.num_vertices();
+        let sp: Vec<_> = terms.iter().map(|&t| self.dijkstra(t)).collect();
+
+        // Prim's MST on the metric closure of the terminals.
+        let k = terms.len();
+        let mut in_tree = vec![false; k];
+        let mut best = vec![(i64::MAX, 0usize); k];
+        best[0] = (0, 0);
+        let mut in_union = vec![false; n];
+        in_union[terms[0]] = true;
+        for _ in 0..k {
+            let i = (0..k)
+                .filter(|&i| !in_tree[i])
+                .min_by_key(|&i| best[i].0)
+                .expect("Terminal left");
+            if best[i].0 == i64::MAX {
+                return None;
+            }
+            in_tree[i] = true;
+            // Expand the closure edge into its shortest path.
+            let (_, prev) = &sp[best[i].1];
+            let mut v = terms[i];
+            while !in_union[v] {
+                in_union[v] = true;
+                match prev[v] {
+                    Some(p) => v = p,
+                    None => break,
+                }
+            }
+            for j in 0..k {
+                if !in_tree[j] && sp[i].0[terms[j]] < best[j].0 {
+                    best[j] = (sp[i].0[terms[j]], i);
+                }
+            }
+        }
+
+        // Prim's MST of the graph induced by the union of the paths.
+        let mut parent: Vec<Option<usize>> = vec![None; n];
+        let mut key = vec![i64::MAX; n];
+        let mut done = vec![false; n];
+        key[terms[0]] = 0;
+        let mut heap = BinaryHeap::from([Reverse((0i64, terms[0]))]);
+        while let Some(Reverse((d, v))) = heap.pop() {
+            if done[v] || d > key[v] {
+                continue;
+            }
+            done[v] = true;
+            for (u, w) in self.neighbors(v) {
+                if in_union[u] && !done[u] && w < key[u] {
+                    key[u] = w;
+                    parent[u] = Some(v);
+                    heap.push(Reverse((w, u)));
+                }
+            }
+        }
+
+        // Prune non-terminal leaves.
+        let mut is_term = vec![false; n];
+        for &t in &terms[..required] {
+            is_term[t] = true;
+        }
+        let mut degree = vec![0usize; n];
+        for (u, p) in parent.iter().enumerate() {
+            if let Some(p) = *p {
+                degree[u] += 1;
+                degree[p] += 1;
+            }
+        }
+        let mut alive = done;
+        let mut leaves: Vec<usize> = (0..n)
+            .filter(|&v| alive[v] && degree[v] == 1 && !is_term[v])
+            .collect();
+        // The root is a terminal, so every pruned vertex has a parent.
+        while let Some(v) = leaves.pop() {
+            alive[v] = false;
+            let p = parent[v].expect("Pruned vertex is not the root");
+            degree[p] -= 1;
+            if degree[p] == 1 && !is_term[p] {
+                leaves.push(p);
+            }
+        }
+
+        let edges: Vec<(usize, usize)> = (0..n)
+            .filter(|&u| alive[u])
+            .filter_map(|u| parent[u].filter(|&p| alive[p]).map(|p| (p, u)))
+            .collect();
+        let cost = edges
+            .iter()
+            .map(|&(a, b)| self.point(a).min_dist_with(&self.point(b)) as i64)
+            .sum();
+        Some((cost, edges))
+    }
+}
+
+/// Builds an obstacle-avoiding rectilinear Steiner tree connecting `source`
+/// to every terminal. Every edge of the result is horizontal or vertical and
+/// neither touches nor crosses a keepout (see `Point::blocks`).
+///
+/// Returns `None` if a pin lies inside a keepout or cannot be reached.
+pub fn build_oarsmt(
+    source: Point<i32, i32>,
+    terminals: &[Point<i32, i32>],
+    keepouts: &[Point<Interval<i32>, Interval<i32>>],
+) -> Option<GlobalRoutingTree> {
+    let pins = distinct_pins(source, terminals);
+    let graph = EscapeGraph::new(&pins, keepouts);
+    let terms: Vec<usize> = pins.iter().map(|p| graph.vertex(p)).collect();
+    if terms.iter().any(|&t| graph.blocked[t]) {
+        return None;
+    }
+
+    let (mut cost, mut edges) = graph.kmb(&terms, terms.len())?;
+    let mut steiner: Vec<usize> = Vec::new();
+    let refine = graph.num_vertices() <= MAX_REFINE_VERTICES;
+    for _ in 0..MAX_REFINE_ROUNDS {
+        if !refine {
+            break;
+        }
+        let mut base = terms.clone();
+        base.extend(&steiner);
+        let mut best: Option<(i64, usize, GraphEdges)> = None;
+        for c in 0..graph.num_vertices() {
+            if graph.blocked[c] || base.contains(&c) {
+                continue;
+            }
+            base.push(c);
+            if let Some((cc, e)) = graph.kmb(&base, terms.len()) {
+                if cc < best.as_ref().map_or(cost, |b| b.0) {
+                    best = Some((cc, c, e));
+                }
+            }
+            base.pop();
+        }
+        let Some((c, v, e)) = best else { break };
+        cost = c;
+        edges = e;
+        steiner.push(v);
+        // Keep only the added points that still branch.
+        let mut degree = vec![0usize; graph.num_vertices()];
+        for &(a, b) in &edges {
+            degree[a] += 1;
+            degree[b] += 1;
+        }
+        steiner.retain(|&s| degree[s] >= 3);
+    }
+
+    // Pins first, then the other tree vertices.
+    let mut points = pins.clone();
+    let mut index = vec![usize::MAX; graph.num_vertices()];
+    for (i, &t) in terms.iter().enumerate() {
+        index[t] = i;
+    }
+    let mut tree_edges = Vec::with_capacity(edges.len());
+    for &(a, b) in &edges {
+        for v in [a, b] {
+            if index[v] == usize::MAX {
+                index[v] = points.len();
+                points.push(graph.point(v));
+            }
+        }
+        tree_edges.push((index[a], index[b]));
+    }
+    Some(build_routing_tree_with_bends(
+        &points,
+        pins.len(),
+        &tree_edges,
+        terminals,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generic::Contain;
+
+    fn keepout(x1: i32, x2: i32, y1: i32, y2: i32) -> Point<Interval<i32>, Interval<i32>> {
+        Point::new(Interval::new(x1, x2), Interval::new(y1, y2))
+    }
+
+    fn assert_clear(tree: &GlobalRoutingTree, keepouts: &[Point<Interval<i32>, Interval<i32>>]) {
+        let nodes = tree
+            .get_all_terminals()
+            .into_iter()
+            .chain(tree.get_all_steiner_nodes());
+        for node in nodes {
+            let path = tree.find_path_to_source(&node.id);
+            let parent = path[path.len() - 2];
+            assert!(node.pt.xcoord == parent.pt.xcoord || node.pt.ycoord == parent.pt.ycoord);
+            let seg = parent.pt.hull_with(&node.pt);
+            for ko in keepouts {
+                assert!(!ko.contains(&node.pt));
+                assert!(!ko.blocks(&seg));
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_keepouts_matches_rsmt() {
+        let source = Point::new(0, 0);
+        let terminals = vec![Point::new(10, 5), Point::new(5, 10), Point::new(10, 10)];
+        let tree = build_oarsmt(source, &terminals, &[]).unwrap();
+        let rsmt = crate::rsmt::build_rsmt(source, &terminals);
+        assert_eq!(
+            tree.calculate_total_wirelength(),
+            rsmt.calculate_total_wirelength()
+        );
+        assert_eq!(tree.get_all_terminals().len(), 3);
+    }
+
+    #[test]
+    fn test_routes_around_keepout() {
+        let source = Point::new(0, 0);
+        let terminals = vec![Point::new(10, 0), Point::new(20, 0)];
+        let keepouts = vec![keepout(4, 6, -5, 5)];
+        let tree = build_oarsmt(source, &terminals, &keepouts).unwrap();
+        assert_eq!(tree.calculate_total_wirelength(), 20 + 2 * 6);
+        assert_clear(&tree, &keepouts);
+    }
+
+    #[test]
+    fn test_macro_dense_region() {
+        let source = Point::new(0, 0);
+        let terminals = vec![
+            Point::new(30, 0),
+            Point::new(15, 20),
+            Point::new(30, 25),
+            Point::new(0, 25),
+        ];
+        let keepouts = vec![
+            keepout(5, 10, -10, 12),
+            keepout(18, 25, -3, 15),
+            keepout(8, 22, 22, 30),
+        ];
+        let tree = build_oarsmt(source, &terminals, &keepouts).unwrap();
+        assert_eq!(tree.get_all_terminals().len(), 4);
+        assert_clear(&tree, &keepouts);
+    }
+
+    #[test]
+    fn test_blocked_or_unreachable_pins() {
+        let source = Point::new(0, 0);
+        assert!(build_oarsmt(source, &[Point::new(5, 5)], &[keepout(4, 6, 4, 6)]).is_none());
+        let cage = vec![
+            keepout(3, 7, 3, 3),
+            keepout(3, 7, 7, 7),
+            keepout(3, 3, 3, 7),
+            keepout(7, 7, 3, 7),
+        ];
+        assert!(build_oarsmt(source, &[Point::new(5, 5)], &cage).is_none());
+    }
+}
@@ -0,0 +1,310 @@
+//! Prim-Dijkstra tradeoff trees (PD and PD-II).
+//!
+//! The Prim-Dijkstra construction grows a spanning tree from the source,
+//! always adding the pin $u$ and tree node $v$ that minimize
+//!
+//! $$\alpha \cdot \ell(v) + d(v, u)$$
+//!
+//! where $\ell(v)$ is the source-to-$v$ path length in the tree. $\alpha = 0$
+//! gives Prim's minimum spanning tree and $\alpha = 1$ Dijkstra's shortest
+//! path tree, trading wirelength (cost) against radius.
+//!
+//! PD-II post-processes the tree by alternately Steinerizing overlapping
+//! sibling edges and removing detours (reattaching nodes to a closer parent
+//! without increasing wirelength) until neither improves the tree.
+
+use crate::generic::MinDist;
+use crate::global_router::GlobalRoutingTree;
+use crate::point::Point;
+use crate::rsmt::{build_routing_tree, distinct_pins};
+
+/// Upper bound on PD-II improvement rounds.
+const MAX_PD2_ROUNDS: usize = 64;
+
+/// One point of a cost/radius tradeoff sweep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradeoffPoint {
+    /// The Prim-Dijkstra parameter
+    pub alpha: f64,
+    /// Total wirelength (`calculate_total_wirelength`)
+    pub wirelength: i32,
+    /// Longest source-to-terminal path (`calculate_worst_wirelength`)
+    pub radius: i32,
+}
+
+/// Working representation: points with parent links. The first `num_pins`
+/// points are pins, the rest Steiner points.
+struct PdTree {
+    points: Vec<Point<i32, i32>>,
+    parent: Vec<Option<usize>>,
+}
+
+impl PdTree {
+    fn dist(&self, a: usize, b: usize) -> i64 {
+        self.points[a].min_dist_with(&self.points[b]) as i64
+    }
+
+    fn children(&self, v: usize) -> Vec<usize> {
+        (0..self.points.len())
+            .filter(|&u| self.parent[u] == Some(v))
+            .collect()
+    }
+
+    fn path_lengths(&self) -> Vec<i64> {
+        let mut lengths = vec![0i64; self.points.len()];
+        let mut stack = vec![0usize];
+        while let Some(v) = stack.pop() {
+            for c in self.children(v) {
+                lengths[c] = lengths[v] + self.dist(v, c);
+                stack.push(c);
+            }
+        }
+        lengths
+    }
+
+    fn in_subtree(&self, mut node: usize, root: usize) -> bool {
+        loop {
+            if node == root {
+                return true;
+            }
+            match self.parent[node] {
+                Some(p) => node = p,
+                None => return false,
+            }
+        }
+    }
+
+    fn edges(&self) -> Vec<(usize, usize)> {
+        self.parent
+            .iter()
+            .enumerate()
+            .filter_map(|(u, p)| p.map(|p| (p, u)))
+            .collect()
+    }
+
+    /// Replaces a pair of sibling edges $(v, a), (v, b)$ by a shared trunk to
+    /// their median point, which lies on a shortest path to both, so path
+    /// lengths are unchanged. Returns `true` if anything changed.
+    fn steinerize(&mut self) -> bool {
+        let mut changed = false;
+        let mut v = 0;
+        while v < self.points.len() {
+            let children = self.children(v);
+            let mut best: Option<(i64, usize, usize, Point<i32, i32>)> = None;
+            for (i, &a) in children.iter().enumerate() {
+                for &b in &children[i + 1..] {
+                    let (pv, pa, pb) = (self.points[v], self.points[a], self.points[b]);
+                    let s = Point::new(
+                        median(pv.xcoord, pa.xcoord, pb.xcoord),
+                        median(pv.ycoord, pa.ycoord, pb.ycoord),
+                    );
+                    let saving = pv.min_dist_with(&s) as i64;
+                    if saving > 0 && best.map_or(true, |(g, ..)| saving > g) {
+                        best = Some((saving, a, b, s));
+                    }
+                }
+            }
+            match best {
+                Some((_, a, b, s)) => {
+                    if s == self.points[a] {
+                        self.parent[b] = Some(a);
+                    } else if s == self.points[b] {
+                        self.parent[a] = Some(b);
+                    } else {
+                        let idx = self.points.len();
+                        self.points.push(s);
+                        self.parent.push(Some(v));
+                        self.parent[a] = Some(idx);
+                        self.parent[b] = Some(idx);
+                    }
+                    changed = true;
+                }
+                None => v += 1,
+            }
+        }
+        changed
+    }
+
+    /// Reattaches nodes to a parent outside their subtree when that shortens
+    /// their source path without increasing the wirelength. Returns `true` if
+    /// anything changed.
+    fn remove_detours(&mut self) -> bool {
+        let mut changed = false;
+        for u in 1..self.points.len() {
+            let lengths = self.path_lengths();
+            let p = self.parent[u].expect("Non-source node has a parent");
+            let current_edge = self.dist(p, u);
+            let mut best: Option<(i64, i64, usize)> = None;
+            for w in 0..self.points.len() {
+                if w == p || self.in_subtree(w, u) {
+                    continue;
+                }
+                let edge = self.dist(w, u);
+                let path = lengths[w] + edge;
+                if edge <= current_edge
+                    && path < lengths[u]
+                    && best.map_or(true, |(bp, be, _)| (path, edge) < (bp, be))
+                {
+                    best = Some((path, edge, w));
+                }
+            }
+            if let Some((_, _, w)) = best {
+                self.parent[u] = Some(w);
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn median(a: i32, b: i32, c: i32) -> i32 {
+    a.max(b).min(a.min(b).max(c))
+}
+
+fn pd_spanning_tree(pins: &[Point<i32, i32>], alpha: f64) -> PdTree {
+    let n = pins.len();
+    let mut in_tree = vec![false; n];
+    let mut parent: Vec<Option<usize>> = vec![None; n];
+    let mut path = vec![0.0f64; n];
+    let mut key = vec![f64::INFINITY; n];
+    key[0] = 0.0;
+    for _ in 0..n {
+        let v = (0..n)
+            .filter(|&i| !in_tree[i])
+            .min_by(|&a, &b| key[a].total_cmp(&key[b]))
+            .expect("Pin left to add");
+        in_tree[v] = true;
+        if let Some(p) = parent[v] {
+            path[v] = path[p] + pins[p].min_dist_with(&pins[v]) as f64;
+        }
+        for u in 0..n {
+            let k = alpha * path[v] + pins[v].min_dist_with(&pins[u]) as f64;
+            if !in_tree[u] && k < key[u] {
+                key[u] = k;
+                parent[u] = Some(v);
+            }
+        }
+    }
+    PdTree {
+        points: pins.to_vec(),
+        parent,
+    }
+}
+
+/// Builds the Prim-Dijkstra spanning tree for $\alpha \in [0, 1]$.
+pub fn prim_dijkstra(
+    source: Point<i32, i32>,
+    terminals: &[Point<i32, i32>],
+    alpha: f64,
+) -> GlobalRoutingTree {
+    let pins = distinct_pins(source, terminals);
+    let tree = pd_spanning_tree(&pins, alpha);
+    build_routing_tree(&tree.points, pins.len(), &tree.edges(), terminals)
+}
+
+/// Builds the Prim-Dijkstra tree and applies the PD-II post-processing
+/// (Steinerization and detour removal). Neither step increases the
+/// wirelength or the path length of any pin.
+pub fn prim_dijkstra_ii(
+    source: Point<i32, i32>,
+    terminals: &[Point<i32, i32>],
+    alpha: f64,
+) -> GlobalRoutingTree {
+    let pins = distinct_pins(source, terminals);
+    let mut tree = pd_spanning_tree(&pins, alpha);
+    for _ in 0..MAX_PD2_ROUNDS {
+        let steinerized = tree.steinerize();
+        let rerouted = tree.remove_detours();
+        if !steinerized && !rerouted {
+            break;
+        }
+    }
+    build_routing_tree(&tree.points, pins.len(), &tree.edges(), terminals)
+}
+
+/// Builds a PD-II tree for every `alpha` and reports its wirelength and
+/// radius, for plotting the cost/radius Pareto curve.
+pub fn sweep_alpha(
+    source: Point<i32, i32>,
+    terminals: &[Point<i32, i32>],
+    alphas: &[f64],
+) -> Vec<TradeoffPoint> {
+    alphas
+        .iter()
+        .map(|&alpha| {
+            let tree = prim_dijkstra_ii(source, terminals, alpha);
+            TradeoffPoint {
+                alpha,
+                wirelength: tree.calculate_total_wirelength(),
+                radius: tree.calculate_worst_wirelength(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rsmt::mst;
+
+    fn net() -> (Point<i32, i32>, Vec<Point<i32, i32>>) {
+        let source = Point::new(0, 0);
+        let terminals = vec![
+            Point::new(10, 2),
+            Point::new(20, 4),
+            Point::new(30, 6),
+            Point::new(40, 8),
+            Point::new(5, 30),
+            Point::new(35, 25),
+            Point::new(50, 0),
+        ];
+        (source, terminals)
+    }
+
+    #[test]
+    fn test_alpha_extremes() {
+        let (source, terminals) = net();
+        let mst_tree = prim_dijkstra(source, &terminals, 0.0);
+        let mst_len = mst(&distinct_pins(source, &terminals)).0;
+        assert_eq!(mst_tree.calculate_total_wirelength() as i64, mst_len);
+
+        let spt = prim_dijkstra(source, &terminals, 1.0);
+        let max_dist = terminals
+            .iter()
+            .map(|t| source.min_dist_with(t) as i32)
+            .max()
+            .unwrap();
+        assert_eq!(spt.calculate_worst_wirelength(), max_dist);
+        for t in spt.get_all_terminals() {
+            assert_eq!(t.path_length, source.min_dist_with(&t.pt) as i32);
+        }
+    }
+
+    #[test]
+    fn test_pd2_improves_pd() {
+        let (source, terminals) = net();
+        for alpha in [0.0, 0.3, 0.6, 1.0] {
+            let pd = prim_dijkstra(source, &terminals, alpha);
+            let pd2 = prim_dijkstra_ii(source, &terminals, alpha);
+            assert!(pd2.calculate_total_wirelength() <= pd.calculate_total_wirelength());
+            assert!(pd2.calculate_worst_wirelength() <= pd.calculate_worst_wirelength());
+            assert_eq!(pd2.get_all_terminals().len(), terminals.len());
+        }
+    }
+
+    #[test]
+    fn test_sweep_alpha_endpoints() {
+        let (source, terminals) = net();
+        let curve = sweep_alpha(source, &terminals, &[0.0, 0.5, 1.0]);
+        assert_eq!(curve.len(), 3);
+        assert!(curve[0].wirelength <= curve[2].wirelength);
+        assert!(curve[2].radius <= curve[0].radius);
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(1, 5, 3), 3);
+        assert_eq!(median(5, 1, 9), 5);
+        assert_eq!(median(2, 2, 0), 2);
+    }
+}
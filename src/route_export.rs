@@ -0,0 +1,558 @@
+//! Machine-readable export and import of routed nets.
+//!
+//! - `GlobalRoutingTree::segments` lists the wires and vias of a tree:
+//!   every edge becomes straight wires (an L-shaped edge is split
+//!   horizontal first) on the child's layer, and an edge between
+//!   coincident nodes on different layers becomes a via.
+//! - `GlobalRoutingTree::to_def` writes the segments of a net in the style
+//!   of a DEF `ROUTED` statement.
+//! - `GlobalRoutingTree::to_graph` converts the tree to a `NetGraph` of
+//!   nodes (id, type, position, layer, pin capacitance) and parent-child
+//!   edges, in breadth-first order from the source, and
+//!   `GlobalRoutingTree::from_graph` rebuilds it with the same node ids.
+//! - With the `serialize` feature, `NetGraph` implements `Serialize` and
+//!   `Deserialize`, and `to_json`/`from_json` write and read it as JSON.
+//!
+//! A JSON export looks like (written indented, one field per line)
+//!
+//! ```text
+//! {
+//!   "net": "n1",
+//!   "nodes": [
+//!     {"id": "source", "type": "source", "x": 0, "y": 0, "layer": 0, "pin_capacitance": 0.0},
+//!     {"id": "terminal_1", "type": "terminal", "x": 10, "y": 5, "layer": 0, "pin_capacitance": 0.002}
+//!   ],
+//!   "edges": [
+//!     {"from": "source", "to": "terminal_1"}
+//!   ]
+//! }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+
+use crate::error::{ImportError, ImportResult};
+use crate::global_router::{GlobalRoutingTree, NodeType, RoutingNode};
+use crate::point::Point;
+use crate::technology::Technology;
+
+/// A wire or via of a routed net.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteSegment {
+    /// A horizontal or vertical wire on one layer
+    Wire {
+        from: Point<i32, i32>,
+        to: Point<i32, i32>,
+        layer: usize,
+    },
+    /// A via between two adjacent (or stacked) layers
+    Via {
+        at: Point<i32, i32>,
+        lower: usize,
+        upper: usize,
+    },
+}
+
+fn layer_name(tech: Option<&Technology>, layer: usize) -> String {
+    tech.and_then(|t| t.layers.get(layer))
+        .map_or_else(|| format!("M{}", layer + 1), |l| l.name.clone())
+}
+
+/// A node of a `NetGraph`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphNode {
+    /// Node id, unique within the net
+    pub id: String,
+    /// Source, Steiner point or terminal
+    #[cfg_attr(feature = "serialize", serde(rename = "type"))]
+    pub node_type: NodeType,
+    /// x coordinate
+    pub x: i32,
+    /// y coordinate
+    pub y: i32,
+    /// Metal layer index
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub layer: usize,
+    /// Pin capacitance (written as `null` in JSON if not finite)
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub pin_capacitance: f64,
+}
+
+/// A parent-child edge of a `NetGraph`, by node id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphEdge {
+    /// Id of the parent node
+    pub from: String,
+    /// Id of the child node
+    pub to: String,
+}
+
+/// A routed net as a graph of nodes and edges, the export format of
+/// `GlobalRoutingTree::to_graph` and `to_json`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct NetGraph {
+    /// Net name
+    pub net: String,
+    /// Nodes, the source first
+    pub nodes: Vec<GraphNode>,
+    /// Edges, one per non-source node
+    pub edges: Vec<GraphEdge>,
+}
+
+impl GlobalRoutingTree {
+    /// Node indices in breadth-first order from the source.
+    fn bfs_order(&self) -> Vec<usize> {
+        let nodes = self.nodes();
+        let mut order = vec![self.source_index()];
+        let mut i = 0;
+        while i < order.len() {
+            order.extend(nodes[order[i]].children.iter().copied());
+            i += 1;
+        }
+        order
+    }
+
+    /// The wires and vias of the tree, edge by edge in breadth-first order.
+    pub fn segments(&self) -> Vec<RouteSegment> {
+        let nodes = self.nodes();
+        let mut segments = Vec::new();
+        for &idx in &self.bfs_order() {
+            let Some(p) = nodes[idx].parent else { continue };
+            let (parent, child) = (&nodes[p], &nodes[idx]);
+            let (a, b) = (parent.pt, child.pt);
+            if a == b {
+                if parent.layer != child.layer {
+                    segments.push(RouteSegment::Via {
+                        at: a,
+                        lower: parent.layer.min(child.layer),
+                        upper: parent.layer.max(child.layer),
+                    });
+                }
+                continue;
+            }
+            let layer = child.layer;
+            let corner = Point::new(b.xcoord, a.ycoord);
+            for (from, to) in [(a, corner), (corner, b)] {
+                if from != to {
+                    segments.push(RouteSegment::Wire { from, to, layer });
+                }
+            }
+        }
+        segments
+    }
+
+    /// Writes the segments of the tree as a DEF-style `ROUTED` statement for
+    /// net `net`. Layers are named after the layers of `tech`, or `M1`,
+    /// `M2`, ... without one; a via between layers `A` and `B` is named `A_B`.
+    ///
+    /// ```text
+    /// - n1
+    ///   + ROUTED M1 ( 0 0 ) ( 10 * )
+    ///     NEW M1 ( 10 0 ) ( * 5 )
+    ///     NEW M1 ( 10 5 ) M1_M2
+    /// ;
+    /// ```
+    pub fn to_def(&self, net: &str, tech: Option<&Technology>) -> String {
+        let mut out = format!("- {}", net);
+        for (i, seg) in self.segments().iter().enumerate() {
+            out.push_str(if i == 0 {
+                "\n  + ROUTED "
+            } else {
+                "\n    NEW "
+            });
+            match *seg {
+                RouteSegment::Wire { from, to, layer } => {
+                    let x = if to.xcoord == from.xcoord {
+                        "*".to_string()
+                    } else {
+                        to.xcoord.to_string()
+                    };
+                    let y = if to.ycoord == from.ycoord {
+                        "*".to_string()
+                    } else {
+                        to.ycoord.to_string()
+                    };
+                    let _ = write!(
+                        out,
+                        "{} ( {} {} ) ( {} {} )",
+                        layer_name(tech, layer),
+                        from.xcoord,
+                        from.ycoord,
+                        x,
+                        y
+                    );
+                }
+                RouteSegment::Via { at, lower, upper } => {
+                    let _ = write!(
+                        out,
+                        "{} ( {} {} ) {}_{}",
+                        layer_name(tech, lower),
+                        at.xcoord,
+                        at.ycoord,
+                        layer_name(tech, lower),
+                        layer_name(tech, upper)
+                    );
+                }
+            }
+        }
+        out.push_str("\n;\n");
+        out
+    }
+
+    /// Converts the tree of net `net` to a `NetGraph`, nodes in
+    /// breadth-first order from the source.
+    pub fn to_graph(&self, net: &str) -> NetGraph {
+        let nodes = self.nodes();
+        let order = self.bfs_order();
+        NetGraph {
+            net: net.to_string(),
+            nodes: order
+                .iter()
+                .map(|&idx| {
+                    let node = &nodes[idx];
+                    GraphNode {
+                        id: node.id.clone(),
+                        node_type: node.node_type,
+                        x: node.pt.xcoord,
+                        y: node.pt.ycoord,
+                        layer: node.layer,
+                        pin_capacitance: node.pin_capacitance,
+                    }
+                })
+                .collect(),
+            edges: order
+                .iter()
+                .skip(1)
+                .map(|&idx| GraphEdge {
+                    from: nodes[nodes[idx].parent.expect("Non-source node has a parent")]
+                        .id
+                        .clone(),
+                    to: nodes[idx].id.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Rebuilds a tree from a `NetGraph`. Returns the net name and the tree
+    /// with the original node ids; path lengths and the worst wirelength are
+    /// recomputed.
+    ///
+    /// The source must have id `"source"`, every other node must have
+    /// exactly one incoming edge and be reachable from the source, and pin
+    /// capacitances must be finite and non-negative.
+    pub fn from_graph(graph: &NetGraph) -> ImportResult<(String, GlobalRoutingTree)> {
+        let mut specs: Vec<RoutingNode> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        for node in &graph.nodes {
+            if !node.pin_capacitance.is_finite() || node.pin_capacitance < 0.0 {
+                return Err(ImportError::Schema(format!(
+                    "node {} has invalid pin capacitance {}",
+                    node.id, node.pin_capacitance
+                )));
+            }
+            let mut spec = RoutingNode::new(&node.id, node.node_type, Point::new(node.x, node.y));
+            spec.layer = node.layer;
+            spec.pin_capacitance = node.pin_capacitance;
+            if index.insert(node.id.clone(), specs.len()).is_some() {
+                return Err(ImportError::DuplicateNode(node.id.clone()));
+            }
+            specs.push(spec);
+        }
+
+        let mut parent: Vec<Option<usize>> = vec![None; specs.len()];
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); specs.len()];
+        for edge in &graph.edges {
+            let lookup = |id: &str| -> ImportResult<usize> {
+                index
+                    .get(id)
+                    .copied()
+                    .ok_or_else(|| ImportError::UnknownNode(id.to_string()))
+            };
+            let (from, to) = (lookup(&edge.from)?, lookup(&edge.to)?);
+            if parent[to].replace(from).is_some() {
+                return Err(ImportError::InvalidTree(format!(
+                    "node {} has more than one parent",
+                    specs[to].id
+                )));
+            }
+            children[from].push(to);
+        }
+
+        let sources: Vec<usize> = (0..specs.len())
+            .filter(|&i| specs[i].node_type == NodeType::Source)
+            .collect();
+        let &[root] = sources.as_slice() else {
+            return Err(ImportError::InvalidTree(format!(
+                "expected one source, found {}",
+                sources.len()
+            )));
+        };
+        if specs[root].id != "source" {
+            return Err(ImportError::InvalidTree(
+                "the source must have id \"source\"".to_string(),
+            ));
+        }
+        if parent[root].is_some() {
+            return Err(ImportError::InvalidTree(
+                "the source has a parent".to_string(),
+            ));
+        }
+
+        let mut tree = GlobalRoutingTree::new(specs[root].pt);
+        let source = tree.source_index();
+        tree.node_mut(source).layer = specs[root].layer;
+        tree.node_mut(source).pin_capacitance = specs[root].pin_capacitance;
+        let mut placed = 1;
+        let mut queue = VecDeque::from([(root, source)]);
+        while let Some((spec, idx)) = queue.pop_front() {
+            for &c in &children[spec] {
+                let child = tree.attach_with_id(specs[c].clone(), idx);
+                queue.push_back((c, child));
+                placed += 1;
+            }
+        }
+        if placed != specs.len() {
+            return Err(ImportError::InvalidTree(
+                "some nodes are not reachable from the source".to_string(),
+            ));
+        }
+        tree.update_path_lengths();
+        tree.worst_wirelength = tree.calculate_worst_wirelength();
+        Ok((graph.net.clone(), tree))
+    }
+
+    /// Writes the tree of net `net` as JSON; see the module documentation
+    /// for the format. A non-finite pin capacitance is written as `null`,
+    /// which `from_json` rejects.
+    #[cfg(feature = "serialize")]
+    pub fn to_json(&self, net: &str) -> String {
+        let mut out =
+            serde_json::to_string_pretty(&self.to_graph(net)).expect("NetGraph serializes");
+        out.push('\n');
+        out
+    }
+
+    /// Reads a tree written by `to_json`; see `from_graph`.
+    #[cfg(feature = "serialize")]
+    pub fn from_json(json: &str) -> ImportResult<(String, GlobalRoutingTree)> {
+        let graph: NetGraph = serde_json::from_str(json).map_err(|e| {
+            use serde_json::error::Category;
+            match e.classify() {
+                Category::Syntax | Category::Eof | Category::Io => ImportError::Syntax {
+                    line: e.line(),
+                    column: e.column(),
+                    message: e.to_string(),
+                },
+                Category::Data => ImportError::Schema(e.to_string()),
+            }
+        })?;
+        GlobalRoutingTree::from_graph(&graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::global_router::GlobalRouter;
+    use crate::technology::{PreferredDirection, RoutingLayer, WireRC};
+
+    #[test]
+    fn test_segments_and_def() {
+        let mut tree = GlobalRoutingTree::new(Point::new(0, 0));
+        let src = tree.source_id();
+        let s = tree.add_steiner(Point::new(10, 0), src).unwrap();
+        tree.add_terminal(Point::new(20, 5), s).unwrap();
+        let via = tree.add_steiner(Point::new(10, 0), s).unwrap();
+        let idx = tree
+            .nodes()
+            .iter()
+            .position(|n| n.handle() == Some(via))
+            .unwrap();
+        tree.node_mut(idx).layer = 1;
+        assert_eq!(
+            tree.segments(),
+            [
+                RouteSegment::Wire {
+                    from: Point::new(0, 0),
+                    to: Point::new(10, 0),
+                    layer: 0
+                },
+                RouteSegment::Wire {
+                    from: Point::new(10, 0),
+                    to: Point::new(20, 0),
+                    layer: 0
+                },
+                RouteSegment::Wire {
+                    from: Point::new(20, 0),
+                    to: Point::new(20, 5),
+                    layer: 0
+                },
+                RouteSegment::Via {
+                    at: Point::new(10, 0),
+                    lower: 0,
+                    upper: 1
+                },
+            ]
+        );
+        let rc = WireRC::new(0.1, 0.2);
+        let mut tech = Technology::new();
+        tech.add_layer(RoutingLayer::new(
+            "metal1",
+            PreferredDirection::Horizontal,
+            rc,
+            rc,
+        ));
+        tech.add_layer(RoutingLayer::new(
+            "metal2",
+            PreferredDirection::Vertical,
+            rc,
+            rc,
+        ));
+        assert_eq!(
+            tree.to_def("n1", Some(&tech)),
+            "- n1\n  + ROUTED metal1 ( 0 0 ) ( 10 * )\n    NEW metal1 ( 10 0 ) ( 20 * )\n    NEW metal1 ( 20 0 ) ( * 5 )\n    NEW metal1 ( 10 0 ) metal1_metal2\n;\n"
+        );
+        assert!(tree.to_def("n1", None).contains("NEW M1 ( 10 0 ) M1_M2"));
+        assert_eq!(
+            GlobalRoutingTree::new(Point::new(0, 0)).to_def("n0", None),
+            "- n0\n;\n"
+        );
+    }
+
+    fn routed_tree() -> GlobalRoutingTree {
+        let terminals = vec![
+            Point::new(10, 2),
+            Point::new(20, 14),
+            Point::new(3, 30),
+            Point::new(25, 25),
+        ];
+        let mut router = GlobalRouter::new(Point::new(0, 0), terminals, None);
+        router.route_with_steiners();
+        let mut tree = router.get_tree().clone();
+        tree.set_pin_capacitance("terminal_2", 0.25);
+        tree
+    }
+
+    #[test]
+    fn test_graph_round_trip() {
+        let tree = routed_tree();
+        let graph = tree.to_graph("net \"a\"");
+        assert_eq!(graph.nodes[0].id, "source");
+        assert_eq!(graph.edges.len(), graph.nodes.len() - 1);
+        let (net, loaded) = GlobalRoutingTree::from_graph(&graph).unwrap();
+        assert_eq!(net, "net \"a\"");
+        assert_eq!(loaded.to_graph(&net), graph);
+        assert_eq!(
+            loaded.calculate_total_wirelength(),
+            tree.calculate_total_wirelength()
+        );
+        assert_eq!(loaded.worst_wirelength, tree.calculate_worst_wirelength());
+        for node in tree.nodes() {
+            let other = loaded.get_node(&node.id).unwrap();
+            assert_eq!(other.pt, node.pt);
+            assert_eq!(other.node_type, node.node_type);
+            assert_eq!(other.pin_capacitance, node.pin_capacitance);
+            let parent_id =
+                |t: &GlobalRoutingTree, n: &RoutingNode| n.parent.map(|p| t.nodes()[p].id.clone());
+            assert_eq!(parent_id(&loaded, other), parent_id(&tree, node));
+        }
+
+        // New nodes do not reuse imported ids.
+        let mut loaded = loaded;
+        let id = loaded.insert_terminal_node(Point::new(40, 40), None);
+        assert!(tree.get_node(&id).is_none());
+    }
+
+    #[test]
+    fn test_from_graph_errors() {
+        let node = |id: &str, node_type: NodeType| GraphNode {
+            id: id.to_string(),
+            node_type,
+            x: 0,
+            y: 0,
+            layer: 0,
+            pin_capacitance: 0.0,
+        };
+        let edge = |from: &str, to: &str| GraphEdge {
+            from: from.to_string(),
+            to: to.to_string(),
+        };
+        let parse = |nodes: &[GraphNode], edges: &[GraphEdge]| {
+            let graph = NetGraph {
+                net: "n".to_string(),
+                nodes: nodes.to_vec(),
+                edges: edges.to_vec(),
+            };
+            GlobalRoutingTree::from_graph(&graph).map(|_| ())
+        };
+        let src = node("source", NodeType::Source);
+        let t1 = node("terminal_1", NodeType::Terminal);
+        assert_eq!(
+            parse(&[src.clone(), t1.clone(), t1.clone()], &[]),
+            Err(ImportError::DuplicateNode("terminal_1".to_string()))
+        );
+        assert_eq!(
+            parse(&[src.clone(), t1.clone()], &[edge("source", "steiner_3")]),
+            Err(ImportError::UnknownNode("steiner_3".to_string()))
+        );
+        assert!(matches!(
+            parse(&[src.clone(), t1.clone()], &[]),
+            Err(ImportError::InvalidTree(_))
+        ));
+        assert!(matches!(
+            parse(
+                std::slice::from_ref(&t1),
+                &[edge("terminal_1", "terminal_1")]
+            ),
+            Err(ImportError::InvalidTree(_))
+        ));
+        let mut bad = t1.clone();
+        bad.pin_capacitance = f64::NAN;
+        assert!(matches!(
+            parse(&[src.clone(), bad], &[edge("source", "terminal_1")]),
+            Err(ImportError::Schema(_))
+        ));
+        assert_eq!(parse(&[src, t1], &[edge("source", "terminal_1")]), Ok(()));
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn test_json_round_trip() {
+        let tree = routed_tree();
+        let json = tree.to_json("net \"a\"");
+        assert!(json.contains("\"type\": \"terminal\""));
+        let (net, loaded) = GlobalRoutingTree::from_json(&json).unwrap();
+        assert_eq!(net, "net \"a\"");
+        assert_eq!(loaded.to_json(&net), json);
+        assert_eq!(loaded.to_graph(&net), tree.to_graph(&net));
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn test_from_json_errors() {
+        let parse = |s: &str| GlobalRoutingTree::from_json(s).map(|_| ());
+        assert!(matches!(
+            parse("{\"net\":"),
+            Err(ImportError::Syntax { line: 1, .. })
+        ));
+        assert!(matches!(
+            parse("{\"net\":\"n\"}"),
+            Err(ImportError::Schema(_))
+        ));
+        assert!(matches!(
+            parse("{\"net\":\"n\",\"nodes\":[{\"id\":\"source\",\"type\":\"via\",\"x\":0,\"y\":0}],\"edges\":[]}"),
+            Err(ImportError::Schema(_))
+        ));
+
+        // Non-finite pin capacitances are written as null and rejected
+        let mut tree = GlobalRoutingTree::new(Point::new(0, 0));
+        let t = tree.insert_terminal_node(Point::new(5, 0), None);
+        tree.set_pin_capacitance(&t, f64::INFINITY);
+        let json = tree.to_json("n");
+        assert!(json.contains("\"pin_capacitance\": null"));
+        assert!(matches!(parse(&json), Err(ImportError::Schema(_))));
+    }
+}
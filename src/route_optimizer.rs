@@ -0,0 +1,436 @@
+//! Post-optimization of routing trees.
+//!
+//! Four local moves on a `GlobalRoutingTree`, each of which never increases
+//! the total wirelength:
+//!
+//! - **Edge merging**: two child edges of a node that overlap share a trunk
+//!   to the median of the node and both children (path lengths unchanged).
+//! - **Detour removal**: a Steiner node with one child that lies off every
+//!   shortest path between its parent and child (a U-shape) is bypassed.
+//! - **Steiner relocation**: each Steiner node moves to the median of its
+//!   neighbours, the optimal rectilinear location.
+//! - **Edge swapping**: a node is reconnected, with its subtree, to a closer
+//!   node or edge of the rest of the tree.
+//!
+//! Moves that can lengthen source paths (relocation and swapping) are only
+//! applied if the longest source path stays within a given bound.
+
+use crate::generic::MinDist;
+use crate::global_router::{GlobalRoutingTree, NodeId, NodeType};
+use crate::interval::Hull;
+use crate::point::Point;
+
+/// Upper bound on `post_optimize` rounds.
+const MAX_ROUNDS: usize = 32;
+
+/// Outcome of `GlobalRoutingTree::post_optimize`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PostOptReport {
+    /// Total wirelength before optimization
+    pub wirelength_before: i32,
+    /// Total wirelength after optimization
+    pub wirelength_after: i32,
+    /// Longest source path before optimization
+    pub worst_before: i32,
+    /// Longest source path after optimization
+    pub worst_after: i32,
+    /// Pairs of overlapping edges merged
+    pub merged: usize,
+    /// U-shaped detours removed
+    pub detours_removed: usize,
+    /// Steiner nodes relocated
+    pub relocated: usize,
+    /// Edges swapped
+    pub swaps: usize,
+}
+
+fn median(a: i32, b: i32, c: i32) -> i32 {
+    a.max(b).min(a.min(b).max(c))
+}
+
+fn dist(a: &Point<i32, i32>, b: &Point<i32, i32>) -> i32 {
+    a.min_dist_with(b) as i32
+}
+
+impl GlobalRoutingTree {
+    fn handles(&self) -> Vec<NodeId> {
+        (0..self.nodes().len()).map(|i| self.handle_at(i)).collect()
+    }
+
+    fn pt(&self, id: NodeId) -> Point<i32, i32> {
+        self.node(id).expect("Live node").pt
+    }
+
+    /// For every node, the longest path from it down into its subtree.
+    fn depth_below(&self) -> Vec<i32> {
+        let nodes = self.nodes();
+        let mut order = vec![self.source_index()];
+        let mut i = 0;
+        while i < order.len() {
+            order.extend(nodes[order[i]].children.iter().copied());
+            i += 1;
+        }
+        let mut depth = vec![0; nodes.len()];
+        for &v in order.iter().rev() {
+            depth[v] = nodes[v]
+                .children
+                .iter()
+                .map(|&c| nodes[v].manhattan_distance(&nodes[c]) + depth[c])
+                .max()
+                .unwrap_or(0);
+        }
+        depth
+    }
+
+    /// Updates `depth` (from `depth_below`) after the subtree of node `idx`
+    /// changed, walking up to the source. Nodes added since are appended.
+    fn refresh_depth(&self, depth: &mut Vec<i32>, mut idx: usize) {
+        let nodes = self.nodes();
+        depth.resize(nodes.len(), 0);
+        loop {
+            depth[idx] = nodes[idx]
+                .children
+                .iter()
+                .map(|&c| nodes[idx].manhattan_distance(&nodes[c]) + depth[c])
+                .max()
+                .unwrap_or(0);
+            match nodes[idx].parent {
+                Some(p) => idx = p,
+                None => break,
+            }
+        }
+    }
+
+    /// Merges pairs of overlapping child edges: children `a` and `b` of `v`
+    /// get a common parent at the median of `v`, `a` and `b`. Returns the
+    /// number of merges.
+    pub fn merge_overlapping_edges(&mut self) -> usize {
+        let mut merged = 0;
+        for v in self.handles() {
+            loop {
+                let pv = self.pt(v);
+                let children = self.children_of(v).expect("Live node");
+                let mut best: Option<(i32, NodeId, NodeId, Point<i32, i32>)> = None;
+                for (i, &a) in children.iter().enumerate() {
+                    for &b in &children[i + 1..] {
+                        let (pa, pb) = (self.pt(a), self.pt(b));
+                        let s = Point::new(
+                            median(pv.xcoord, pa.xcoord, pb.xcoord),
+                            median(pv.ycoord, pa.ycoord, pb.ycoord),
+                        );
+                        let saving = dist(&pv, &s);
+                        if saving > 0 && best.map_or(true, |(g, ..)| saving > g) {
+                            best = Some((saving, a, b, s));
+                        }
+                    }
+                }
+                let Some((_, a, b, s)) = best else { break };
+                if s == self.pt(a) {
+                    self.reparent(b, a).expect("Sibling is outside the subtree");
+                } else if s == self.pt(b) {
+                    self.reparent(a, b).expect("Sibling is outside the subtree");
+                } else {
+                    let trunk = self.split_edge(a, s).expect("Median lies on the edge");
+                    self.reparent(b, trunk)
+                        .expect("Trunk is outside the subtree");
+                }
+                merged += 1;
+            }
+        }
+        merged
+    }
+
+    /// Bypasses Steiner nodes with a single child that are off every
+    /// shortest path from their parent to that child, and drops childless
+    /// Steiner nodes. Returns the number of nodes removed.
+    pub fn remove_u_detours(&mut self) -> usize {
+        let mut removed = 0;
+        for v in self.handles() {
+            let Some(node) = self.node(v) else { continue };
+            if node.node_type != NodeType::Steiner {
+                continue;
+            }
+            let Some(p) = self.parent_of(v).expect("Live node") else {
+                continue;
+            };
+            let children = self.children_of(v).expect("Live node");
+            match children.as_slice() {
+                [] => {}
+                &[c] => {
+                    let (pp, pv, pc) = (self.pt(p), self.pt(v), self.pt(c));
+                    if dist(&pp, &pc) >= dist(&pp, &pv) + dist(&pv, &pc) {
+                        continue;
+                    }
+                    self.reparent(c, p).expect("Parent is outside the subtree");
+                }
+                _ => continue,
+            }
+            self.delete_subtree(v)
+                .expect("Steiner node is not the source");
+            removed += 1;
+        }
+        removed
+    }
+
+    /// Moves each Steiner node to the median of its neighbours when that
+    /// shortens the tree and keeps every source path within `max_worst`.
+    /// Returns the number of nodes moved.
+    pub fn relocate_steiner_points(&mut self, max_worst: i32) -> usize {
+        let mut moved = 0;
+        let mut depth_below = self.depth_below();
+        for v in self.handles() {
+            let node = self.node(v).expect("Live node");
+            if node.node_type != NodeType::Steiner {
+                continue;
+            }
+            let Some(p) = self.parent_of(v).expect("Live node") else {
+                continue;
+            };
+            let old = node.pt;
+            let mut neighbours: Vec<Point<i32, i32>> = vec![self.pt(p)];
+            for c in self.children_of(v).expect("Live node") {
+                neighbours.push(self.pt(c));
+            }
+            let pick = |mut vals: Vec<i32>, current: i32| {
+                vals.sort_unstable();
+                let n = vals.len();
+                current.clamp(vals[(n - 1) / 2], vals[n / 2])
+            };
+            let new = Point::new(
+                pick(neighbours.iter().map(|q| q.xcoord).collect(), old.xcoord),
+                pick(neighbours.iter().map(|q| q.ycoord).collect(), old.ycoord),
+            );
+            let gain: i32 = neighbours
+                .iter()
+                .map(|q| dist(q, &old) - dist(q, &new))
+                .sum();
+            if gain <= 0 {
+                continue;
+            }
+            let idx = self.index(v).expect("Live node");
+            let nodes = self.nodes();
+            let depth = nodes[idx]
+                .children
+                .iter()
+                .map(|&c| dist(&new, &nodes[c].pt) + depth_below[c])
+                .max()
+                .unwrap_or(0);
+            let path = self.node(p).expect("Live node").path_length + dist(&self.pt(p), &new);
+            if path + depth > max_worst {
+                continue;
+            }
+            self.move_node(v, new).expect("Live node");
+            self.refresh_depth(&mut depth_below, idx);
+            moved += 1;
+        }
+        moved
+    }
+
+    /// Reconnects nodes, with their subtrees, to the nearest point of the
+    /// rest of the tree (a node, or a new Steiner node on an edge) when that
+    /// shortens the tree and keeps every source path within `max_worst`.
+    /// Returns the number of swaps.
+    pub fn swap_edges(&mut self, max_worst: i32) -> usize {
+        let mut swaps = 0;
+        let mut depth_below = self.depth_below();
+        for u in self.handles() {
+            let Some(pu_parent) = self.parent_of(u).expect("Live node") else {
+                continue;
+            };
+            let ui = self.index(u).expect("Live node");
+            let nodes = self.nodes();
+            let pu = nodes[ui].pt;
+            let current = dist(&self.pt(pu_parent), &pu);
+            let depth = depth_below[ui];
+            let in_subtree = |mut w: usize| loop {
+                if w == ui {
+                    return true;
+                }
+                match nodes[w].parent {
+                    Some(p) => w = p,
+                    None => return false,
+                }
+            };
+
+            // (wire length, attach point, node to attach to or whose
+            // incoming edge to split)
+            let mut best: Option<(i32, Point<i32, i32>, NodeId)> = None;
+            for (wi, w) in nodes.iter().enumerate() {
+                if in_subtree(wi) {
+                    continue;
+                }
+                let mut consider = |q: Point<i32, i32>, path: i32| {
+                    let wire = dist(&q, &pu);
+                    if wire < best.map_or(current, |b| b.0) && path + wire + depth <= max_worst {
+                        best = Some((wire, q, w.handle().expect("Node in tree")));
+                    }
+                };
+                consider(w.pt, w.path_length);
+                if let Some(p) = w.parent {
+                    let q = nodes[p].pt.hull_with(&w.pt).nearest_to(&pu);
+                    consider(q, nodes[p].path_length + dist(&nodes[p].pt, &q));
+                }
+            }
+            let Some((_, q, w)) = best else { continue };
+            let target = if q == self.pt(w) {
+                w
+            } else if self.parent_of(w).expect("Live node").map(|p| self.pt(p)) == Some(q) {
+                self.parent_of(w).expect("Live node").expect("Has parent")
+            } else {
+                self.split_edge(w, q).expect("Point lies on the edge")
+            };
+            self.reparent(u, target)
+                .expect("Target is outside the subtree");
+            let old_parent = self.index(pu_parent).expect("Live node");
+            self.refresh_depth(&mut depth_below, old_parent);
+            let new_parent = self.index(target).expect("Live node");
+            self.refresh_depth(&mut depth_below, new_parent);
+            swaps += 1;
+        }
+        swaps
+    }
+
+    /// Runs edge merging, detour removal, Steiner relocation and edge
+    /// swapping until none of them changes the tree. Relocation and swaps
+    /// keep the longest source path within `max_worst` (or within the
+    /// current longest path if that is already larger).
+    pub fn post_optimize(&mut self, max_worst: i32) -> PostOptReport {
+        let wirelength_before = self.calculate_total_wirelength();
+        let worst_before = self.calculate_worst_wirelength();
+        let bound = max_worst.max(worst_before);
+        let mut report = PostOptReport {
+            wirelength_before,
+            wirelength_after: wirelength_before,
+            worst_before,
+            worst_after: worst_before,
+            merged: 0,
+            detours_removed: 0,
+            relocated: 0,
+            swaps: 0,
+        };
+        for _ in 0..MAX_ROUNDS {
+            let merged = self.merge_overlapping_edges();
+            let removed = self.remove_u_detours();
+            let relocated = self.relocate_steiner_points(bound);
+            let swaps = self.swap_edges(bound);
+            report.merged += merged;
+            report.detours_removed += removed;
+            report.relocated += relocated;
+            report.swaps += swaps;
+            if merged + removed + relocated + swaps == 0 {
+                break;
+            }
+        }
+        report.wirelength_after = self.calculate_total_wirelength();
+        report.worst_after = self.calculate_worst_wirelength();
+        self.worst_wirelength = report.worst_after;
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::global_router::GlobalRouter;
+
+    #[test]
+    fn test_merge_overlapping_edges() {
+        let mut tree = GlobalRoutingTree::new(Point::new(0, 0));
+        let src = tree.source_id();
+        let a = tree.add_terminal(Point::new(10, 0), src).unwrap();
+        let b = tree.add_terminal(Point::new(20, 0), src).unwrap();
+        let c = tree.add_terminal(Point::new(10, 5), src).unwrap();
+        let d = tree.add_terminal(Point::new(20, 5), src).unwrap();
+        assert_eq!(tree.calculate_total_wirelength(), 10 + 20 + 15 + 25);
+        let merged = tree.merge_overlapping_edges();
+        assert!(merged >= 2);
+        assert_eq!(tree.calculate_total_wirelength(), 30);
+        for t in [a, b, c, d] {
+            let n = tree.node(t).unwrap();
+            assert_eq!(n.path_length, n.pt.min_dist_with(&Point::new(0, 0)) as i32);
+        }
+    }
+
+    #[test]
+    fn test_remove_u_detours_and_relocate() {
+        let mut tree = GlobalRoutingTree::new(Point::new(0, 0));
+        let src = tree.source_id();
+        let u = tree.add_steiner(Point::new(5, 10), src).unwrap();
+        let t = tree.add_terminal(Point::new(10, 0), u).unwrap();
+        assert_eq!(tree.remove_u_detours(), 1);
+        assert_eq!(tree.parent_of(t).unwrap(), Some(src));
+        assert_eq!(tree.calculate_total_wirelength(), 10);
+
+        let mut tree = GlobalRoutingTree::new(Point::new(0, 0));
+        let src = tree.source_id();
+        let s = tree.add_steiner(Point::new(10, 10), src).unwrap();
+        tree.add_terminal(Point::new(10, 0), s).unwrap();
+        tree.add_terminal(Point::new(20, 0), s).unwrap();
+        assert_eq!(tree.calculate_total_wirelength(), 50);
+        // A tight bound forbids nothing here: relocation shortens paths.
+        assert_eq!(tree.relocate_steiner_points(20), 1);
+        assert_eq!(tree.node(s).unwrap().pt, Point::new(10, 0));
+        assert_eq!(tree.calculate_total_wirelength(), 20);
+    }
+
+    #[test]
+    fn test_swap_edges_respects_bound() {
+        let build = || {
+            let mut tree = GlobalRoutingTree::new(Point::new(0, 0));
+            let src = tree.source_id();
+            let a = tree.add_terminal(Point::new(0, 20), src).unwrap();
+            let b = tree.add_terminal(Point::new(20, 20), a).unwrap();
+            let s = tree.add_steiner(Point::new(22, 0), src).unwrap();
+            let t = tree.add_terminal(Point::new(22, 12), s).unwrap();
+            (tree, b, t)
+        };
+        // Hanging `b` off `t` saves 10 but lengthens its path from 40 to 44.
+        let (mut tight, _, _) = build();
+        assert_eq!(tight.calculate_worst_wirelength(), 40);
+        assert_eq!(tight.swap_edges(40), 0);
+        assert_eq!(tight.calculate_total_wirelength(), 74);
+
+        let (mut loose, b, t) = build();
+        assert_eq!(loose.swap_edges(44), 1);
+        assert_eq!(loose.parent_of(b).unwrap(), Some(t));
+        assert_eq!(loose.calculate_total_wirelength(), 64);
+        assert_eq!(loose.calculate_worst_wirelength(), 44);
+
+        // Incremental depth updates match a full recomputation
+        let (mut tree, b, t) = build();
+        let mut depth = tree.depth_below();
+        let old_parent = tree.index(tree.parent_of(b).unwrap().unwrap()).unwrap();
+        tree.reparent(b, t).unwrap();
+        tree.refresh_depth(&mut depth, old_parent);
+        tree.refresh_depth(&mut depth, tree.index(t).unwrap());
+        assert_eq!(depth, tree.depth_below());
+    }
+
+    #[test]
+    fn test_post_optimize_simple_route() {
+        let src = Point::new(0, 0);
+        let terminals = vec![
+            Point::new(10, 2),
+            Point::new(20, 4),
+            Point::new(30, 6),
+            Point::new(5, 30),
+            Point::new(35, 25),
+            Point::new(50, 0),
+            Point::new(18, 18),
+        ];
+        let mut router = GlobalRouter::new(src, terminals.clone(), None);
+        router.route_simple();
+        let mut tree = router.get_tree().clone();
+        tree.update_path_lengths();
+        let bound = tree.calculate_worst_wirelength();
+        let report = tree.post_optimize(bound);
+        assert!(report.wirelength_after < report.wirelength_before);
+        assert!(report.worst_after <= bound);
+        assert_eq!(report.wirelength_after, tree.calculate_total_wirelength());
+        assert_eq!(tree.get_all_terminals().len(), terminals.len());
+        for t in tree.get_all_terminals() {
+            let path = tree.find_path_to_source(&t.id);
+            assert_eq!(path[0].pt, src);
+        }
+    }
+}
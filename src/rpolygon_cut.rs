@@ -3,9 +3,89 @@
 //! Provides algorithms to decompose a rectilinear polygon into convex,
 //! explicitly-cut, implicitly-cut, or rectangular pieces.
 
+use std::ops::{AddAssign, SubAssign};
+
+use num_traits::Num;
+
 use crate::dllink::Dllink;
+use crate::interval::Interval;
 use crate::point::Point;
 use crate::rdllist::RDllist;
+use crate::rpolygon::RPolygon;
+
+/// Cuts a rectilinear polygon into rectangles, one row per horizontal slab
+/// between consecutive vertex y-coordinates; vertically adjacent rectangles
+/// with equal x extent are merged.
+///
+/// Corners implied by the compressed form of `rpoly` (consecutive vertices
+/// joined horizontally first) are expanded before cutting, so the union of
+/// the rectangles is exactly the polygon.
+///
+/// # Examples
+///
+/// ```
+/// use physdes::interval::Interval;
+/// use physdes::point::Point;
+/// use physdes::rpolygon::RPolygon;
+/// use physdes::rpolygon_cut::rpolygon_cut_slabs;
+///
+/// // An L shape with its corners (30, 0) and (10, 10) implied
+/// let poly = RPolygon::new(&[
+///     Point::new(0, 0),
+///     Point::new(30, 10),
+///     Point::new(10, 20),
+///     Point::new(0, 20),
+/// ]);
+/// assert_eq!(
+///     rpolygon_cut_slabs(&poly),
+///     vec![
+///         Point::new(Interval::new(0, 30), Interval::new(0, 10)),
+///         Point::new(Interval::new(0, 10), Interval::new(10, 20)),
+///     ]
+/// );
+/// ```
+pub fn rpolygon_cut_slabs<T>(rpoly: &RPolygon<T>) -> Vec<Point<Interval<T>, Interval<T>>>
+where
+    T: Clone + Copy + Num + Ord + AddAssign + SubAssign,
+{
+    let vertices = rpoly.to_polygon().vertices();
+    let mut ys: Vec<T> = vertices.iter().map(|p| p.ycoord).collect();
+    ys.sort_unstable();
+    ys.dedup();
+    let n = vertices.len();
+    let mut pieces: Vec<Point<Interval<T>, Interval<T>>> = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    for slab in ys.windows(2) {
+        let (ylo, yhi) = (slab[0], slab[1]);
+        // Vertical edges spanning the slab, by x (even-odd rule).
+        let mut xs: Vec<T> = (0..n)
+            .map(|i| (vertices[i], vertices[(i + 1) % n]))
+            .filter(|(a, b)| {
+                a.xcoord == b.xcoord
+                    && a.ycoord.min(b.ycoord) <= ylo
+                    && a.ycoord.max(b.ycoord) >= yhi
+            })
+            .map(|(a, _)| a.xcoord)
+            .collect();
+        xs.sort_unstable();
+        let mut next_open = Vec::new();
+        for span in xs.chunks_exact(2) {
+            let xint = Interval::new(span[0], span[1]);
+            match open.iter().find(|&&k| pieces[k].xcoord == xint) {
+                Some(&k) => {
+                    pieces[k].ycoord = Interval::new(pieces[k].ycoord.lb, yhi);
+                    next_open.push(k);
+                }
+                None => {
+                    next_open.push(pieces.len());
+                    pieces.push(Point::new(xint, Interval::new(ylo, yhi)));
+                }
+            }
+        }
+        open = next_open;
+    }
+    pieces
+}
 
 /// Decomposes a rectilinear polygon into convex pieces.
 ///
@@ -0,0 +1,462 @@
+//! Rectilinear Steiner minimal tree (RSMT) construction.
+//!
+//! Builds a `GlobalRoutingTree` connecting a source and a set of terminals
+//! with (near-)minimal total Manhattan wirelength. By Hanan's theorem an
+//! optimal tree exists whose Steiner points all lie on the Hanan grid, the
+//! grid formed by the x and y coordinates of the pins.
+//!
+//! - Nets with at most `EXACT_RSMT_MAX_PINS` distinct pins are solved exactly
+//!   with the Dreyfus-Wagner dynamic program over the Hanan grid.
+//! - Larger nets use the batched iterated 1-Steiner heuristic: Hanan points
+//!   are added while they reduce the rectilinear MST, and Steiner points of
+//!   degree two or less are dropped.
+//!
+//! Keepouts are not considered.
+
+use crate::generic::MinDist;
+use crate::global_router::GlobalRoutingTree;
+use crate::point::Point;
+
+/// Largest number of distinct pins (source included) solved exactly.
+pub const EXACT_RSMT_MAX_PINS: usize = 9;
+
+/// Builds a rectilinear Steiner tree, exactly for small nets and with the
+/// iterated 1-Steiner heuristic otherwise.
+pub fn build_rsmt(source: Point<i32, i32>, terminals: &[Point<i32, i32>]) -> GlobalRoutingTree {
+    let pins = distinct_pins(source, terminals);
+    if pins.len() <= EXACT_RSMT_MAX_PINS {
+        exact_rsmt(source, terminals)
+    } else {
+        iterated_one_steiner_rsmt(source, terminals)
+    }
+}
+
+/// Builds an optimal rectilinear Steiner tree with the Dreyfus-Wagner
+/// dynamic program on the Hanan grid.
+///
+/// With $k$ distinct pins and $n$ Hanan points, the cost is
+/// $O(3^k n + 2^k n^2)$, so this is only practical for small nets.
+///
+/// # Panics
+///
+/// Panics if the net has more than 16 distinct pins.
+pub fn exact_rsmt(source: Point<i32, i32>, terminals: &[Point<i32, i32>]) -> GlobalRoutingTree {
+    let pins = distinct_pins(source, terminals);
+    assert!(pins.len() <= 16, "Too many pins for the exact RSMT");
+    if pins.len() == 1 {
+        return build_routing_tree(&pins, 1, &[], terminals);
+    }
+
+    let mut xs: Vec<i32> = pins.iter().map(|p| p.xcoord).collect();
+    let mut ys: Vec<i32> = pins.iter().map(|p| p.ycoord).collect();
+    xs.sort_unstable();
+    xs.dedup();
+    ys.sort_unstable();
+    ys.dedup();
+
+    // Grid points: the pins first (so pin i is grid point i), then the
+    // remaining Hanan points.
+    let mut grid = pins.clone();
+    for &x in &xs {
+        for &y in &ys {
+            let p = Point::new(x, y);
+            if !pins.contains(&p) {
+                grid.push(p);
+            }
+        }
+    }
+    let n = grid.len();
+    let dist = |a: usize, b: usize| grid[a].min_dist_with(&grid[b]) as i64;
+
+    // Pin 0 (the source) is the root; masks range over pins 1..k.
+    let k = pins.len() - 1;
+    let full = (1usize << k) - 1;
+    let mut dp = vec![vec![i64::MAX; n]; full + 1];
+    let mut via = vec![vec![0usize; n]; full + 1];
+    let mut split = vec![vec![i64::MAX; n]; full + 1];
+    let mut split_at = vec![vec![0usize; n]; full + 1];
+
+    for mask in 1..=full {
+        if mask.is_power_of_two() {
+            let pin = mask.trailing_zeros() as usize + 1;
+            for (v, d) in dp[mask].iter_mut().enumerate() {
+                *d = dist(pin, v);
+            }
+            continue;
+        }
+        // Split the subset at each grid point; fixing the lowest bit in the
+        // first part avoids visiting each pair of parts twice.
+        let low = mask & mask.wrapping_neg();
+        for v in 0..n {
+            let rest = mask ^ low;
+            let mut sub = rest;
+            loop {
+                let part = sub | low;
+                if part != mask {
+                    let cost = dp[part][v] + dp[mask ^ part][v];
+                    if cost < split[mask][v] {
+                        split[mask][v] = cost;
+                        split_at[mask][v] = part;
+                    }
+                }
+                if sub == 0 {
+                    break;
+                }
+                sub = (sub - 1) & rest;
+            }
+        }
+        // The split tree may hang off any grid point; the metric obeys the
+        // triangle inequality, so one relaxation pass is enough.
+        for v in 0..n {
+            for (u, &split_cost) in split[mask].iter().enumerate() {
+                let cost = split_cost + dist(u, v);
+                if cost < dp[mask][v] {
+                    dp[mask][v] = cost;
+                    via[mask][v] = u;
+                }
+            }
+        }
+    }
+
+    let mut edges = Vec::new();
+    let mut stack = vec![(full, 0usize)];
+    while let Some((mask, v)) = stack.pop() {
+        if mask.is_power_of_two() {
+            let pin = mask.trailing_zeros() as usize + 1;
+            if pin != v {
+                edges.push((v, pin));
+            }
+            continue;
+        }
+        let u = via[mask][v];
+        if u != v {
+            edges.push((v, u));
+        }
+        let part = split_at[mask][u];
+        stack.push((part, u));
+        stack.push((mask ^ part, u));
+    }
+
+    build_routing_tree(&grid, pins.len(), &edges, terminals)
+}
+
+/// Builds a rectilinear Steiner tree with the batched iterated 1-Steiner
+/// heuristic.
+///
+/// Each round evaluates the MST gain of every Hanan point, then adds
+/// candidates in order of decreasing gain, re-checking each gain against
+/// the points added so far. Steiner points with MST degree of at most two
+/// are removed after each round. The result is never longer than the
+/// rectilinear MST of the pins.
+pub fn iterated_one_steiner_rsmt(
+    source: Point<i32, i32>,
+    terminals: &[Point<i32, i32>],
+) -> GlobalRoutingTree {
+    let pins = distinct_pins(source, terminals);
+    let num_pins = pins.len();
+
+    let mut xs: Vec<i32> = pins.iter().map(|p| p.xcoord).collect();
+    let mut ys: Vec<i32> = pins.iter().map(|p| p.ycoord).collect();
+    xs.sort_unstable();
+    xs.dedup();
+    ys.sort_unstable();
+    ys.dedup();
+    let hanan: Vec<Point<i32, i32>> = xs
+        .iter()
+        .flat_map(|&x| ys.iter().map(move |&y| Point::new(x, y)))
+        .filter(|p| !pins.contains(p))
+        .collect();
+
+    let mut points = pins.clone();
+    let mut cost = mst(&points).0;
+    loop {
+        let mut gains: Vec<(i64, usize)> = Vec::new();
+        for (i, &h) in hanan.iter().enumerate() {
+            if points.contains(&h) {
+                continue;
+            }
+            points.push(h);
+            let gain = cost - mst(&points).0;
+            points.pop();
+            if gain > 0 {
+                gains.push((gain, i));
+            }
+        }
+        if gains.is_empty() {
+            break;
+        }
+        gains.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        let mut added = false;
+        for (_, i) in gains {
+            points.push(hanan[i]);
+            let new_cost = mst(&points).0;
+            if new_cost < cost {
+                cost = new_cost;
+                added = true;
+            } else {
+                points.pop();
+            }
+        }
+
+        // Drop Steiner points that no longer branch; removing one can lower
+        // the degree of another, so repeat until stable.
+        loop {
+            let (_, edges) = mst(&points);
+            let mut degree = vec![0usize; points.len()];
+            for &(a, b) in &edges {
+                degree[a] += 1;
+                degree[b] += 1;
+            }
+            let before = points.len();
+            let mut idx = 0;
+            points.retain(|_| {
+                let keep = idx < num_pins || degree[idx] > 2;
+                idx += 1;
+                keep
+            });
+            if points.len() == before {
+                break;
+            }
+        }
+        cost = mst(&points).0;
+
+        if !added {
+            break;
+        }
+    }
+
+    let (_, edges) = mst(&points);
+    build_routing_tree(&points, num_pins, &edges, terminals)
+}
+
+/// Source followed by the distinct terminal positions other than the source.
+pub(crate) fn distinct_pins(
+    source: Point<i32, i32>,
+    terminals: &[Point<i32, i32>],
+) -> Vec<Point<i32, i32>> {
+    let mut pins = vec![source];
+    for &t in terminals {
+        if !pins.contains(&t) {
+            pins.push(t);
+        }
+    }
+    pins
+}
+
+/// Rectilinear minimum spanning tree with Prim's algorithm in $O(n^2)$.
+/// Returns the total length and the tree edges.
+pub(crate) fn mst(points: &[Point<i32, i32>]) -> (i64, Vec<(usize, usize)>) {
+    let n = points.len();
+    if n < 2 {
+        return (0, Vec::new());
+    }
+    let mut in_tree = vec![false; n];
+    let mut best = vec![i64::MAX; n];
+    let mut from = vec![0usize; n];
+    let mut total = 0;
+    let mut edges = Vec::with_capacity(n - 1);
+    best[0] = 0;
+    for _ in 0..n {
+        let v = (0..n)
+            .filter(|&i| !in_tree[i])
+            .min_by_key(|&i| best[i])
+            .expect("Vertex left to add");
+        in_tree[v] = true;
+        if v != 0 {
+            total += best[v];
+            edges.push((from[v], v));
+        }
+        for u in 0..n {
+            let d = points[v].min_dist_with(&points[u]) as i64;
+            if !in_tree[u] && d < best[u] {
+                best[u] = d;
+                from[u] = v;
+            }
+        }
+    }
+    (total, edges)
+}
+
+/// Turns an edge list over `points` into a `GlobalRoutingTree` rooted at
+/// `points[0]`. The first `num_pins` points are pins; the others become
+/// Steiner nodes, except that Steiner points with a single child are
+/// bypassed and childless ones dropped. Every entry of `terminals` becomes
+/// its own terminal node.
+pub(crate) fn build_routing_tree(
+    points: &[Point<i32, i32>],
+    num_pins: usize,
+    edges: &[(usize, usize)],
+    terminals: &[Point<i32, i32>],
+) -> GlobalRoutingTree {
+    orient_routing_tree(points, num_pins, edges, terminals, false)
+}
+
+/// Like `build_routing_tree`, but a single-child Steiner point is kept when
+/// the route bends there, so axis-parallel input edges stay straight wires.
+pub(crate) fn build_routing_tree_with_bends(
+    points: &[Point<i32, i32>],
+    num_pins: usize,
+    edges: &[(usize, usize)],
+    terminals: &[Point<i32, i32>],
+) -> GlobalRoutingTree {
+    orient_routing_tree(points, num_pins, edges, terminals, true)
+}
+
+/// Returns `true` if `a`, `b` and `c` lie on one horizontal or vertical line.
+fn is_straight(a: Point<i32, i32>, b: Point<i32, i32>, c: Point<i32, i32>) -> bool {
+    (a.xcoord == b.xcoord && b.xcoord == c.xcoord) || (a.ycoord == b.ycoord && b.ycoord == c.ycoord)
+}
+
+fn orient_routing_tree(
+    points: &[Point<i32, i32>],
+    num_pins: usize,
+    edges: &[(usize, usize)],
+    terminals: &[Point<i32, i32>],
+    keep_bends: bool,
+) -> GlobalRoutingTree {
+    let mut adjacency = vec![Vec::new(); points.len()];
+    for &(a, b) in edges {
+        adjacency[a].push(b);
+        adjacency[b].push(a);
+    }
+
+    // Orient the edges away from the source, ignoring any edge that would
+    // close a cycle.
+    let mut children = vec![Vec::new(); points.len()];
+    let mut visited = vec![false; points.len()];
+    visited[0] = true;
+    let mut queue = std::collections::VecDeque::from([0usize]);
+    while let Some(v) = queue.pop_front() {
+        for &u in &adjacency[v] {
+            if !visited[u] {
+                visited[u] = true;
+                children[v].push(u);
+                queue.push_back(u);
+            }
+        }
+    }
+
+    // Terminal indices grouped by pin; a terminal at the source position is
+    // attached to the source with a zero-length wire.
+    let mut pin_terminals = vec![Vec::new(); num_pins];
+    for &t in terminals {
+        let pin = points[..num_pins]
+            .iter()
+            .position(|&p| p == t)
+            .expect("Terminal is a pin");
+        pin_terminals[pin].push(t);
+    }
+
+    let mut tree = GlobalRoutingTree::new(points[0]);
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    for &t in &pin_terminals[0] {
+        tree.insert_terminal_node(t, Some("source"));
+    }
+    for &c in children[0].iter().rev() {
+        stack.push((c, "source".to_string()));
+    }
+    while let Some((v, parent_id)) = stack.pop() {
+        let id = if v < num_pins {
+            // Extra terminals at the same position hang off the same parent.
+            let mut ids: Vec<String> = pin_terminals[v]
+                .iter()
+                .map(|&t| tree.insert_terminal_node(t, Some(&parent_id)))
+                .collect();
+            ids.swap_remove(0)
+        } else {
+            match children[v].len() {
+                0 => continue,
+                1 if !keep_bends
+                    || is_straight(
+                        tree.get_node(&parent_id).expect("Parent inserted").pt,
+                        points[v],
+                        points[children[v][0]],
+                    ) =>
+                {
+                    stack.push((children[v][0], parent_id));
+                    continue;
+                }
+                _ => tree.insert_steiner_node(points[v], Some(&parent_id)),
+            }
+        };
+        for &c in children[v].iter().rev() {
+            stack.push((c, id.clone()));
+        }
+    }
+    tree.update_path_lengths();
+    tree.worst_wirelength = tree.calculate_worst_wirelength();
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::global_router::GlobalRouter;
+
+    fn pseudo_random_points(count: usize, seed: u64, span: i32) -> Vec<Point<i32, i32>> {
+        let mut state = seed;
+        let mut next = || {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            ((state >> 33) % span as u64) as i32
+        };
+        (0..count).map(|_| Point::new(next(), next())).collect()
+    }
+
+    #[test]
+    fn test_exact_rsmt_cross() {
+        let source = Point::new(0, 5);
+        let terminals = vec![Point::new(10, 5), Point::new(5, 0), Point::new(5, 10)];
+        let tree = exact_rsmt(source, &terminals);
+        assert_eq!(tree.calculate_total_wirelength(), 20);
+        assert_eq!(tree.get_all_terminals().len(), 3);
+        assert_eq!(tree.get_all_steiner_nodes().len(), 1);
+        assert_eq!(tree.get_all_steiner_nodes()[0].pt, Point::new(5, 5));
+    }
+
+    #[test]
+    fn test_exact_rsmt_trivial_nets() {
+        let source = Point::new(3, 3);
+        assert_eq!(exact_rsmt(source, &[]).calculate_total_wirelength(), 0);
+        let tree = exact_rsmt(source, &[Point::new(3, 3), Point::new(7, 1)]);
+        assert_eq!(tree.calculate_total_wirelength(), 6);
+        assert_eq!(tree.get_all_terminals().len(), 2);
+    }
+
+    #[test]
+    fn test_rsmt_beats_incremental_routing() {
+        for seed in 0..10 {
+            let pts = pseudo_random_points(8, seed, 100);
+            let (source, terminals) = (pts[0], pts[1..].to_vec());
+            let exact = build_rsmt(source, &terminals);
+            let heuristic = iterated_one_steiner_rsmt(source, &terminals);
+            let mut router = GlobalRouter::new(source, terminals.clone(), None);
+            router.route_with_steiners();
+
+            let exact_wl = exact.calculate_total_wirelength();
+            assert!(exact_wl <= heuristic.calculate_total_wirelength());
+            assert!(exact_wl <= router.get_tree().calculate_total_wirelength());
+            assert!(exact_wl as i64 <= mst(&distinct_pins(source, &terminals)).0);
+            assert_eq!(exact.get_all_terminals().len(), terminals.len());
+        }
+    }
+
+    #[test]
+    fn test_iterated_one_steiner_large_net() {
+        let pts = pseudo_random_points(25, 7, 200);
+        let (source, terminals) = (pts[0], pts[1..].to_vec());
+        let tree = build_rsmt(source, &terminals);
+        let mst_len = mst(&distinct_pins(source, &terminals)).0;
+        assert!((tree.calculate_total_wirelength() as i64) < mst_len);
+        assert_eq!(tree.get_all_terminals().len(), terminals.len());
+        for s in tree.get_all_steiner_nodes() {
+            assert!(s.children.len() >= 2);
+        }
+        for t in tree.get_all_terminals() {
+            let path = tree.find_path_to_source(&t.id);
+            assert_eq!(path[0].id, "source");
+        }
+    }
+}
@@ -0,0 +1,415 @@
+//! Static R-tree spatial index, bulk loaded with Sort-Tile-Recursive (STR).
+//!
+//! Items are indexed by their bounding rectangles (`Bounded`), which covers
+//! `Rectangle<T>`, `Polygon<T>`, `RPolygon<T>`, interval rectangles
+//! `Point<Interval<T>, Interval<T>>` and plain points. Queries return the
+//! indices of the items in the slice the tree was built from:
+//!
+//! - `query`: items whose bounding box meets a window (boundaries included),
+//! - `nearest`: the `k` items closest to a point in Manhattan distance,
+//! - `join` / `self_join`: all pairs of intersecting bounding boxes.
+//!
+//! STR sorts the rectangles by x, cuts them into vertical slices of
+//! `ceil(sqrt(n / M))` leaves each, sorts every slice by y and packs runs of
+//! `M` rectangles into leaves; upper levels are packed the same way. The
+//! result is a balanced tree with nearly full, little-overlapping nodes.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::ops::{Add, Sub};
+
+use num_traits::{Num, Zero};
+
+use crate::interval::Interval;
+use crate::point::Point;
+use crate::polygon::Polygon;
+use crate::rpolygon::RPolygon;
+use crate::vlsi_ops::Rectangle;
+
+/// Default maximum number of children per node.
+pub const DEFAULT_NODE_CAPACITY: usize = 8;
+
+/// Types with an axis-aligned bounding rectangle.
+pub trait Bounded<T> {
+    /// The smallest rectangle containing `self`.
+    fn bounds(&self) -> Rectangle<T>;
+}
+
+impl<T: Copy + Ord + Add<Output = T>> Bounded<T> for Rectangle<T> {
+    fn bounds(&self) -> Rectangle<T> {
+        self.clone()
+    }
+}
+
+impl<T: Copy + Ord + Add<Output = T>> Bounded<T> for Point<T, T> {
+    fn bounds(&self) -> Rectangle<T> {
+        Rectangle::new(*self, *self)
+    }
+}
+
+impl<T: Copy + Ord + Add<Output = T>> Bounded<T> for Point<Interval<T>, Interval<T>> {
+    fn bounds(&self) -> Rectangle<T> {
+        Rectangle::new(
+            Point::new(self.xcoord.lb, self.ycoord.lb),
+            Point::new(self.xcoord.ub, self.ycoord.ub),
+        )
+    }
+}
+
+impl<T: Copy + Num + Ord + std::ops::AddAssign> Bounded<T> for Polygon<T> {
+    fn bounds(&self) -> Rectangle<T> {
+        let (min, max) = self.bounding_box();
+        Rectangle::new(min, max)
+    }
+}
+
+impl<T: Copy + Num + Ord + std::ops::AddAssign> Bounded<T> for RPolygon<T> {
+    fn bounds(&self) -> Rectangle<T> {
+        let (min, max) = self.bounding_box();
+        Rectangle::new(min, max)
+    }
+}
+
+/// A node covering a contiguous range of the level below (of the entries
+/// for level 0).
+#[derive(Debug, Clone)]
+struct Node<T> {
+    bbox: Rectangle<T>,
+    start: usize,
+    end: usize,
+}
+
+/// A static R-tree over item bounding boxes.
+#[derive(Debug, Clone)]
+pub struct RTree<T> {
+    /// Bounding boxes with item indices, in leaf order
+    entries: Vec<(Rectangle<T>, usize)>,
+    /// Node levels from the leaves up; the last level holds only the root
+    levels: Vec<Vec<Node<T>>>,
+}
+
+fn union<T: Copy + Ord + Add<Output = T>>(
+    rects: impl Iterator<Item = Rectangle<T>>,
+) -> Rectangle<T> {
+    rects.reduce(|a, b| a.bounding_rect(&b)).unwrap()
+}
+
+/// Reorders `items` into STR order for nodes of `capacity` children.
+fn str_sort<T, X>(items: &mut [(Rectangle<T>, X)], capacity: usize)
+where
+    T: Copy + Ord + Add<Output = T>,
+{
+    let leaves = (items.len() + capacity - 1) / capacity;
+    let slices = (leaves as f64).sqrt().ceil() as usize;
+    items.sort_by_key(|(r, _)| r.min.xcoord + r.max.xcoord);
+    for slice in items.chunks_mut(slices * capacity) {
+        slice.sort_by_key(|(r, _)| r.min.ycoord + r.max.ycoord);
+    }
+}
+
+/// Manhattan distance from `pt` to the closest point of `rect`.
+fn rect_distance<T>(rect: &Rectangle<T>, pt: &Point<T, T>) -> T
+where
+    T: Copy + Ord + Zero + Sub<Output = T>,
+{
+    let gap = |lo: T, hi: T, v: T| {
+        if v < lo {
+            lo - v
+        } else if v > hi {
+            v - hi
+        } else {
+            T::zero()
+        }
+    };
+    gap(rect.min.xcoord, rect.max.xcoord, pt.xcoord)
+        + gap(rect.min.ycoord, rect.max.ycoord, pt.ycoord)
+}
+
+impl<T> RTree<T>
+where
+    T: Copy + Ord + Zero + Add<Output = T> + Sub<Output = T>,
+{
+    /// Bulk loads a tree over the bounding boxes of `items` with the default
+    /// node capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use physdes::{Point, vlsi_ops::Rectangle, rtree::RTree};
+    ///
+    /// let rects = vec![
+    ///     Rectangle::new(Point::new(0, 0), Point::new(10, 10)),
+    ///     Rectangle::new(Point::new(20, 0), Point::new(30, 10)),
+    ///     Rectangle::new(Point::new(5, 5), Point::new(25, 8)),
+    /// ];
+    /// let tree = RTree::bulk_load(&rects);
+    /// let window = Rectangle::new(Point::new(12, 0), Point::new(18, 20));
+    /// assert_eq!(tree.query(&window), vec![2]);
+    /// assert_eq!(tree.self_join(), vec![(0, 2), (1, 2)]);
+    /// ```
+    pub fn bulk_load<B: Bounded<T>>(items: &[B]) -> Self {
+        Self::bulk_load_with_capacity(items, DEFAULT_NODE_CAPACITY)
+    }
+
+    /// Bulk loads a tree whose nodes hold at most `capacity` children.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is less than 2.
+    pub fn bulk_load_with_capacity<B: Bounded<T>>(items: &[B], capacity: usize) -> Self {
+        assert!(capacity >= 2, "Node capacity must be at least 2");
+        let mut entries: Vec<(Rectangle<T>, usize)> = items
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (b.bounds(), i))
+            .collect();
+        let mut levels: Vec<Vec<Node<T>>> = Vec::new();
+        if entries.is_empty() {
+            return RTree { entries, levels };
+        }
+        str_sort(&mut entries, capacity);
+        let mut bboxes: Vec<Rectangle<T>> = entries.iter().map(|(r, _)| r.clone()).collect();
+        loop {
+            let mut level: Vec<(Rectangle<T>, (usize, usize))> = bboxes
+                .chunks(capacity)
+                .enumerate()
+                .map(|(c, chunk)| {
+                    let start = c * capacity;
+                    (union(chunk.iter().cloned()), (start, start + chunk.len()))
+                })
+                .collect();
+            if level.len() > 1 {
+                str_sort(&mut level, capacity);
+            }
+            bboxes = level.iter().map(|(r, _)| r.clone()).collect();
+            levels.push(
+                level
+                    .into_iter()
+                    .map(|(bbox, (start, end))| Node { bbox, start, end })
+                    .collect(),
+            );
+            if bboxes.len() == 1 {
+                break;
+            }
+        }
+        RTree { entries, levels }
+    }
+
+    /// Number of indexed items.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the tree indexes no items.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Bounding box of all items, or `None` for an empty tree.
+    pub fn bounding_box(&self) -> Option<Rectangle<T>> {
+        self.levels.last().map(|root| root[0].bbox.clone())
+    }
+
+    fn root(&self) -> Option<(usize, usize)> {
+        self.levels.len().checked_sub(1).map(|top| (top, 0))
+    }
+
+    /// Indices of the items whose bounding box meets `window`, ascending.
+    pub fn query(&self, window: &Rectangle<T>) -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut stack: Vec<(usize, usize)> = self.root().into_iter().collect();
+        while let Some((level, idx)) = stack.pop() {
+            let node = &self.levels[level][idx];
+            if !node.bbox.overlaps(window) {
+                continue;
+            }
+            if level == 0 {
+                found.extend(
+                    self.entries[node.start..node.end]
+                        .iter()
+                        .filter(|(r, _)| r.overlaps(window))
+                        .map(|&(_, i)| i),
+                );
+            } else {
+                stack.extend((node.start..node.end).map(|c| (level - 1, c)));
+            }
+        }
+        found.sort_unstable();
+        found
+    }
+
+    /// Indices of the items whose bounding box contains `pt`, ascending.
+    pub fn query_point(&self, pt: &Point<T, T>) -> Vec<usize> {
+        self.query(&Rectangle::new(*pt, *pt))
+    }
+
+    /// The `k` items nearest to `pt` with their Manhattan distances (zero
+    /// for items whose bounding box contains `pt`), closest first.
+    ///
+    /// Nodes are visited best-first, so only the part of the tree within
+    /// the `k`-th distance is explored.
+    pub fn nearest(&self, pt: &Point<T, T>, k: usize) -> Vec<(usize, T)> {
+        // (distance, is_node, level, index); entries win ties with nodes
+        let mut heap = BinaryHeap::new();
+        if let Some((level, idx)) = self.root() {
+            let d = rect_distance(&self.levels[level][idx].bbox, pt);
+            heap.push(Reverse((d, true, level, idx)));
+        }
+        let mut result = Vec::with_capacity(k);
+        while let Some(Reverse((d, is_node, level, idx))) = heap.pop() {
+            if result.len() == k {
+                break;
+            }
+            if !is_node {
+                result.push((self.entries[idx].1, d));
+                continue;
+            }
+            let node = &self.levels[level][idx];
+            for c in node.start..node.end {
+                let (bbox, child_is_node) = if level == 0 {
+                    (&self.entries[c].0, false)
+                } else {
+                    (&self.levels[level - 1][c].bbox, true)
+                };
+                heap.push(Reverse((
+                    rect_distance(bbox, pt),
+                    child_is_node,
+                    level.saturating_sub(1),
+                    c,
+                )));
+            }
+        }
+        result
+    }
+
+    fn children(&self, (level, idx): (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+        let node = &self.levels[level][idx];
+        (node.start..node.end).map(move |c| (level - 1, c))
+    }
+
+    fn join_nodes(
+        &self,
+        a: (usize, usize),
+        other: &RTree<T>,
+        b: (usize, usize),
+        out: &mut Vec<(usize, usize)>,
+    ) {
+        let (na, nb) = (&self.levels[a.0][a.1], &other.levels[b.0][b.1]);
+        if !na.bbox.overlaps(&nb.bbox) {
+            return;
+        }
+        if a.0 == 0 && b.0 == 0 {
+            for (ra, i) in &self.entries[na.start..na.end] {
+                for (rb, j) in &other.entries[nb.start..nb.end] {
+                    if ra.overlaps(rb) {
+                        out.push((*i, *j));
+                    }
+                }
+            }
+        } else if a.0 >= b.0 {
+            for c in self.children(a) {
+                self.join_nodes(c, other, b, out);
+            }
+        } else {
+            for c in other.children(b) {
+                self.join_nodes(a, other, c, out);
+            }
+        }
+    }
+
+    /// All pairs `(i, j)` of an item `i` of `self` and an item `j` of
+    /// `other` whose bounding boxes meet, sorted.
+    pub fn join(&self, other: &RTree<T>) -> Vec<(usize, usize)> {
+        let mut out = Vec::new();
+        if let (Some(a), Some(b)) = (self.root(), other.root()) {
+            self.join_nodes(a, other, b, &mut out);
+        }
+        out.sort_unstable();
+        out
+    }
+
+    /// All pairs `(i, j)`, `i < j`, of items whose bounding boxes meet,
+    /// sorted.
+    pub fn self_join(&self) -> Vec<(usize, usize)> {
+        let mut out = self.join(self);
+        out.retain(|(i, j)| i < j);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x1: i32, y1: i32, x2: i32, y2: i32) -> Rectangle<i32> {
+        Rectangle::new(Point::new(x1, y1), Point::new(x2, y2))
+    }
+
+    /// A deterministic scatter of small rectangles.
+    fn scatter(n: usize) -> Vec<Rectangle<i32>> {
+        crate::vlsi_ops::scatter_rects(12345, n, 1000, 40)
+    }
+
+    #[test]
+    fn test_queries_match_brute_force() {
+        let rects = scatter(500);
+        let tree = RTree::bulk_load_with_capacity(&rects, 4);
+        assert_eq!(tree.len(), 500);
+
+        for window in [
+            rect(100, 100, 200, 300),
+            rect(0, 0, 5, 5),
+            rect(-50, -50, -1, -1),
+        ] {
+            let expected: Vec<usize> = (0..rects.len())
+                .filter(|&i| rects[i].overlaps(&window))
+                .collect();
+            assert_eq!(tree.query(&window), expected);
+        }
+
+        let pt = Point::new(500, 500);
+        let mut dists: Vec<i32> = rects.iter().map(|r| rect_distance(r, &pt)).collect();
+        dists.sort_unstable();
+        let nearest = tree.nearest(&pt, 10);
+        assert_eq!(nearest.len(), 10);
+        assert_eq!(
+            nearest.iter().map(|&(_, d)| d).collect::<Vec<_>>(),
+            dists[..10]
+        );
+        for (i, d) in nearest {
+            assert_eq!(rect_distance(&rects[i], &pt), d);
+        }
+
+        let mut expected = Vec::new();
+        for i in 0..rects.len() {
+            for j in i + 1..rects.len() {
+                if rects[i].overlaps(&rects[j]) {
+                    expected.push((i, j));
+                }
+            }
+        }
+        assert_eq!(tree.self_join(), expected);
+    }
+
+    #[test]
+    fn test_join_and_bounded_shapes() {
+        let points = vec![Point::new(0, 0), Point::new(15, 5), Point::new(40, 40)];
+        let points_tree = RTree::bulk_load(&points);
+        let shapes = vec![
+            Polygon::new(&[Point::new(10, 0), Point::new(20, 0), Point::new(15, 10)]),
+            Polygon::new(&[Point::new(-5, -5), Point::new(1, -5), Point::new(1, 1)]),
+        ];
+        let shapes_tree = RTree::bulk_load(&shapes);
+        assert_eq!(shapes_tree.join(&points_tree), vec![(0, 1), (1, 0)]);
+        assert_eq!(points_tree.join(&shapes_tree), vec![(0, 1), (1, 0)]);
+        assert_eq!(points_tree.nearest(&Point::new(30, 30), 1), vec![(2, 20)]);
+        assert_eq!(shapes_tree.bounding_box(), Some(rect(-5, -5, 20, 10)));
+
+        let empty = RTree::<i32>::bulk_load::<Rectangle<i32>>(&[]);
+        assert!(empty.is_empty());
+        assert!(empty.query(&rect(0, 0, 1, 1)).is_empty());
+        assert!(empty.nearest(&Point::new(0, 0), 3).is_empty());
+        assert!(empty.join(&points_tree).is_empty());
+        assert_eq!(empty.bounding_box(), None);
+    }
+}
@@ -0,0 +1,305 @@
+//! Sink clustering for hierarchical clock tree synthesis.
+//!
+//! Large designs are clustered into leaf groups before DME: each cluster is
+//! merged locally and then acts as one pseudo-sink at the top level (see
+//! `DMEAlgorithm::build_clustered_clock_tree`). Two clustering strategies
+//! are provided:
+//!
+//! - `kmeans_clusters`: k-means with Manhattan assignment and deterministic
+//!   farthest-point seeding.
+//! - `bounded_clusters`: recursive geometric bipartition until every
+//!   cluster satisfies a capacitance bound and a Manhattan diameter bound.
+//!
+//! Both return clusters as lists of indices into the sink slice.
+
+use crate::dme_algorithm::{NodeIdx, Sink, Tree};
+use crate::point::Point;
+
+/// Summary of one cluster of a hierarchical clock tree.
+#[derive(Debug, Clone)]
+pub struct ClusterSummary {
+    /// Index of the cluster's local root in the tree
+    pub root: NodeIdx,
+    /// Number of sinks in the cluster
+    pub num_sinks: usize,
+    /// Aggregate capacitance seen at the local root (sinks plus wires)
+    pub capacitance: f64,
+    /// Internal latency: maximum delay from the local root to a sink
+    pub latency: f64,
+    /// Internal skew among the cluster's sinks
+    pub skew: f64,
+}
+
+/// Partitions sinks into at most `k` clusters with k-means.
+///
+/// Sinks are assigned to the nearest centre by Manhattan distance and
+/// centres are moved to the centroid of their members, for at most
+/// `max_iterations` rounds. Seeding is deterministic: the first centre is
+/// the sink closest to the global centroid and every following centre is
+/// the sink farthest from the centres chosen so far. Empty clusters are
+/// dropped.
+pub fn kmeans_clusters(sinks: &[Sink], k: usize, max_iterations: usize) -> Vec<Vec<usize>> {
+    if sinks.is_empty() || k == 0 {
+        return Vec::new();
+    }
+    let k = k.min(sinks.len());
+    let pts: Vec<(f64, f64)> = sinks
+        .iter()
+        .map(|s| (s.position.xcoord as f64, s.position.ycoord as f64))
+        .collect();
+
+    let dist = |a: (f64, f64), b: (f64, f64)| (a.0 - b.0).abs() + (a.1 - b.1).abs();
+
+    let n = pts.len() as f64;
+    let centroid = pts
+        .iter()
+        .fold((0.0, 0.0), |acc, p| (acc.0 + p.0 / n, acc.1 + p.1 / n));
+    let first = (0..pts.len())
+        .min_by(|&a, &b| dist(pts[a], centroid).total_cmp(&dist(pts[b], centroid)))
+        .unwrap_or(0);
+    let mut centres = vec![pts[first]];
+    let mut nearest: Vec<f64> = pts.iter().map(|&p| dist(p, pts[first])).collect();
+    while centres.len() < k {
+        let far = (0..pts.len())
+            .max_by(|&a, &b| nearest[a].total_cmp(&nearest[b]))
+            .unwrap_or(0);
+        centres.push(pts[far]);
+        for (i, &p) in pts.iter().enumerate() {
+            nearest[i] = nearest[i].min(dist(p, pts[far]));
+        }
+    }
+
+    let mut assign = vec![0usize; pts.len()];
+    for iter in 0..max_iterations.max(1) {
+        let mut changed = false;
+        for (i, &p) in pts.iter().enumerate() {
+            let best = (0..centres.len())
+                .min_by(|&a, &b| dist(p, centres[a]).total_cmp(&dist(p, centres[b])))
+                .unwrap_or(0);
+            if best != assign[i] {
+                changed = true;
+                assign[i] = best;
+            }
+        }
+        if iter > 0 && !changed {
+            break;
+        }
+        let mut sums = vec![(0.0, 0.0, 0usize); centres.len()];
+        for (i, &p) in pts.iter().enumerate() {
+            let s = &mut sums[assign[i]];
+            s.0 += p.0;
+            s.1 += p.1;
+            s.2 += 1;
+        }
+        for (c, s) in centres.iter_mut().zip(&sums) {
+            if s.2 > 0 {
+                *c = (s.0 / s.2 as f64, s.1 / s.2 as f64);
+            }
+        }
+    }
+
+    let mut clusters = vec![Vec::new(); centres.len()];
+    for (i, &a) in assign.iter().enumerate() {
+        clusters[a].push(i);
+    }
+    clusters.retain(|c| !c.is_empty());
+    clusters
+}
+
+/// Partitions sinks into clusters bounded in total capacitance and
+/// Manhattan diameter.
+///
+/// The sink set is recursively split at the median of its longer bounding
+/// box side until each part has total sink capacitance at most
+/// `max_capacitance` and Manhattan diameter at most `max_diameter`. The
+/// Manhattan diameter of a set is computed exactly in rotated coordinates:
+///
+/// $$D = \max\left(\max_i u_i - \min_i u_i,\; \max_i v_i - \min_i v_i\right), \quad u = x + y,\; v = x - y$$
+///
+/// A single sink always forms a valid cluster, even if its own
+/// capacitance exceeds the bound.
+pub fn bounded_clusters(
+    sinks: &[Sink],
+    max_capacitance: f64,
+    max_diameter: i32,
+) -> Vec<Vec<usize>> {
+    let mut clusters = Vec::new();
+    if sinks.is_empty() {
+        return clusters;
+    }
+    let all: Vec<usize> = (0..sinks.len()).collect();
+    split_bounded(sinks, all, max_capacitance, max_diameter, &mut clusters);
+    clusters
+}
+
+fn split_bounded(
+    sinks: &[Sink],
+    mut members: Vec<usize>,
+    max_capacitance: f64,
+    max_diameter: i32,
+    out: &mut Vec<Vec<usize>>,
+) {
+    let cap: f64 = members.iter().map(|&i| sinks[i].capacitance).sum();
+    let positions: Vec<Point<i32, i32>> = members.iter().map(|&i| sinks[i].position).collect();
+    if members.len() == 1
+        || (cap <= max_capacitance && manhattan_diameter(&positions) <= max_diameter)
+    {
+        out.push(members);
+        return;
+    }
+
+    let (min_x, max_x) = min_max(positions.iter().map(|p| p.xcoord));
+    let (min_y, max_y) = min_max(positions.iter().map(|p| p.ycoord));
+    if max_x - min_x >= max_y - min_y {
+        members.sort_by_key(|&i| (sinks[i].position.xcoord, sinks[i].position.ycoord));
+    } else {
+        members.sort_by_key(|&i| (sinks[i].position.ycoord, sinks[i].position.xcoord));
+    }
+    let right = members.split_off(members.len() / 2);
+    split_bounded(sinks, members, max_capacitance, max_diameter, out);
+    split_bounded(sinks, right, max_capacitance, max_diameter, out);
+}
+
+/// Exact Manhattan diameter of a point set.
+pub fn manhattan_diameter(points: &[Point<i32, i32>]) -> i32 {
+    if points.is_empty() {
+        return 0;
+    }
+    let (min_u, max_u) = min_max(points.iter().map(|p| p.xcoord + p.ycoord));
+    let (min_v, max_v) = min_max(points.iter().map(|p| p.xcoord - p.ycoord));
+    (max_u - min_u).max(max_v - min_v)
+}
+
+fn min_max(values: impl Iterator<Item = i32>) -> (i32, i32) {
+    values.fold((i32::MAX, i32::MIN), |(lo, hi), v| (lo.min(v), hi.max(v)))
+}
+
+/// Summarizes every cluster of a hierarchical tree built by
+/// `DMEAlgorithm::build_clustered_clock_tree`.
+///
+/// `roots` are the local cluster roots (`DMEAlgorithm::cluster_roots`).
+/// Latency and skew are measured from each local root, using the delays
+/// stored in the tree.
+pub fn summarize_clusters(tree: &Tree, roots: &[NodeIdx]) -> Vec<ClusterSummary> {
+    roots
+        .iter()
+        .map(|&root| {
+            let base = tree.get(root).delay;
+            let mut delays = Vec::new();
+            let mut stack = vec![root];
+            while let Some(idx) = stack.pop() {
+                let node = tree.get(idx);
+                if node.is_leaf() {
+                    delays.push(node.delay - base);
+                }
+                stack.extend(node.left);
+                stack.extend(node.right);
+            }
+            let latency = delays.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            let earliest = delays.iter().cloned().fold(f64::INFINITY, f64::min);
+            ClusterSummary {
+                root,
+                num_sinks: delays.len(),
+                capacitance: tree.get(root).capacitance,
+                latency,
+                skew: latency - earliest,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dme_algorithm::{get_tree_statistics, DMEAlgorithm, ElmoreDelayCalculator};
+
+    fn make_sinks(count: i32) -> Vec<Sink> {
+        (0..count)
+            .map(|i| {
+                let x = (i * 37) % 200;
+                let y = (i * 53) % 200;
+                Sink::new(
+                    &format!("s{}", i),
+                    Point::new(x, y),
+                    1.0 + (i % 3) as f64 * 0.5,
+                )
+            })
+            .collect()
+    }
+
+    fn assert_partition(clusters: &[Vec<usize>], n: usize) {
+        let mut all: Vec<usize> = clusters.iter().flatten().cloned().collect();
+        all.sort();
+        assert_eq!(all, (0..n).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_kmeans_partition() {
+        let sinks = make_sinks(40);
+        let clusters = kmeans_clusters(&sinks, 5, 20);
+        assert!(!clusters.is_empty() && clusters.len() <= 5);
+        assert_partition(&clusters, 40);
+    }
+
+    #[test]
+    fn test_kmeans_separates_groups() {
+        let mut sinks = Vec::new();
+        for i in 0..5 {
+            sinks.push(Sink::new(&format!("a{}", i), Point::new(i, 0), 1.0));
+            sinks.push(Sink::new(
+                &format!("b{}", i),
+                Point::new(1000 + i, 1000),
+                1.0,
+            ));
+        }
+        let clusters = kmeans_clusters(&sinks, 2, 10);
+        assert_eq!(clusters.len(), 2);
+        for c in &clusters {
+            let first = &sinks[c[0]].name[..1];
+            assert!(c.iter().all(|&i| &sinks[i].name[..1] == first));
+        }
+    }
+
+    #[test]
+    fn test_bounded_clusters_respect_bounds() {
+        let sinks = make_sinks(60);
+        let clusters = bounded_clusters(&sinks, 6.0, 150);
+        assert_partition(&clusters, 60);
+        for c in &clusters {
+            let cap: f64 = c.iter().map(|&i| sinks[i].capacitance).sum();
+            let pts: Vec<_> = c.iter().map(|&i| sinks[i].position).collect();
+            assert!(c.len() == 1 || (cap <= 6.0 && manhattan_diameter(&pts) <= 150));
+        }
+    }
+
+    #[test]
+    fn test_manhattan_diameter() {
+        let pts = vec![Point::new(0, 0), Point::new(3, 4), Point::new(5, -1)];
+        assert_eq!(manhattan_diameter(&pts), 7);
+        assert_eq!(manhattan_diameter(&[]), 0);
+    }
+
+    #[test]
+    fn test_clustered_dme_tree() {
+        let sinks = make_sinks(50);
+        let clusters = bounded_clusters(&sinks, 10.0, 120);
+        let mut dme = DMEAlgorithm::new(sinks, Box::new(ElmoreDelayCalculator::new(0.1, 0.1)));
+        let root = dme.build_clustered_clock_tree(&clusters);
+        let analysis = dme.analyze_skew(root);
+        assert_eq!(analysis.sink_delays.len(), 50);
+        assert!(analysis.skew / analysis.max_delay < 0.02);
+
+        let stats = get_tree_statistics(dme.get_tree(), root);
+        assert_eq!(stats.total_sinks, 50);
+        assert_eq!(stats.total_nodes, 99);
+
+        let summaries = summarize_clusters(dme.get_tree(), dme.cluster_roots());
+        assert_eq!(summaries.len(), clusters.len());
+        let total: usize = summaries.iter().map(|s| s.num_sinks).sum();
+        assert_eq!(total, 50);
+        for s in summaries.iter().filter(|s| s.num_sinks > 1) {
+            assert!(s.latency > 0.0);
+            assert!(s.capacitance > 0.0);
+        }
+    }
+}
@@ -0,0 +1,480 @@
+//! Technology description: per-layer wire RC and process corners.
+//!
+//! A `Technology` holds a stack of `RoutingLayer`s, each with separate
+//! horizontal and vertical per-unit resistance and capacitance, a preferred
+//! routing direction and a track capacity, and a list
+//! of named `Corner`s that scale those values. `analyze_corners` evaluates
+//! one embedded clock `Tree` under every corner so a single embedding can be
+//! checked across PVT.
+
+use crate::dme_algorithm::{ElmoreDelayCalculator, NodeIdx, Tree};
+
+/// Per-unit-length resistance and capacitance of a wire.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WireRC {
+    /// Resistance per unit length
+    pub unit_resistance: f64,
+    /// Capacitance per unit length
+    pub unit_capacitance: f64,
+}
+
+impl WireRC {
+    /// Creates a wire RC pair.
+    pub const fn new(unit_resistance: f64, unit_capacitance: f64) -> Self {
+        WireRC {
+            unit_resistance,
+            unit_capacitance,
+        }
+    }
+}
+
+/// Preferred routing direction of a metal layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreferredDirection {
+    /// Wires run along the x axis
+    Horizontal,
+    /// Wires run along the y axis
+    Vertical,
+}
+
+/// A metal layer with separate horizontal and vertical wire RC, a preferred
+/// direction and a track capacity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoutingLayer {
+    /// Layer name (e.g. "M3")
+    pub name: String,
+    /// RC of horizontal wires on this layer
+    pub horizontal: WireRC,
+    /// RC of vertical wires on this layer
+    pub vertical: WireRC,
+    /// Preferred routing direction
+    pub direction: PreferredDirection,
+    /// Number of tracks crossing each GCell edge in the preferred direction
+    /// (zero unless set with `with_capacity`)
+    pub capacity: u32,
+}
+
+impl RoutingLayer {
+    /// Creates a routing layer with the given preferred direction and
+    /// horizontal and vertical RC, and no track capacity.
+    pub fn new(
+        name: &str,
+        direction: PreferredDirection,
+        horizontal: WireRC,
+        vertical: WireRC,
+    ) -> Self {
+        RoutingLayer {
+            name: name.to_string(),
+            horizontal,
+            vertical,
+            direction,
+            capacity: 0,
+        }
+    }
+
+    /// Sets the number of tracks per GCell edge.
+    pub fn with_capacity(mut self, capacity: u32) -> Self {
+        self.capacity = capacity;
+        self
+    }
+}
+
+/// A named process corner that scales nominal wire RC and sink loads.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Corner {
+    /// Corner name (e.g. "ss_0p72v_125c")
+    pub name: String,
+    /// Multiplier applied to wire resistance
+    pub resistance_scale: f64,
+    /// Multiplier applied to wire capacitance
+    pub capacitance_scale: f64,
+    /// Multiplier applied to sink pin capacitance
+    pub sink_capacitance_scale: f64,
+}
+
+impl Corner {
+    /// Creates a corner with the given wire and sink scale factors.
+    pub fn new(
+        name: &str,
+        resistance_scale: f64,
+        capacitance_scale: f64,
+        sink_capacitance_scale: f64,
+    ) -> Self {
+        Corner {
+            name: name.to_string(),
+            resistance_scale,
+            capacitance_scale,
+            sink_capacitance_scale,
+        }
+    }
+
+    /// The nominal corner: all scale factors are 1.
+    pub fn nominal() -> Self {
+        Corner::new("nominal", 1.0, 1.0, 1.0)
+    }
+}
+
+/// A technology description: routing layer stack and process corners.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Technology {
+    /// Routing layers, bottom to top
+    pub layers: Vec<RoutingLayer>,
+    /// Process corners to analyze
+    pub corners: Vec<Corner>,
+}
+
+impl Technology {
+    /// Creates an empty technology description.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a routing layer, returning its index.
+    pub fn add_layer(&mut self, layer: RoutingLayer) -> usize {
+        self.layers.push(layer);
+        self.layers.len() - 1
+    }
+
+    /// Appends a corner, returning its index.
+    pub fn add_corner(&mut self, corner: Corner) -> usize {
+        self.corners.push(corner);
+        self.corners.len() - 1
+    }
+
+    /// Returns the index of the layer with the given name, if any.
+    pub fn find_layer(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|l| l.name == name)
+    }
+
+    /// Returns the index of the corner with the given name, if any.
+    pub fn find_corner(&self, name: &str) -> Option<usize> {
+        self.corners.iter().position(|c| c.name == name)
+    }
+
+    /// Builds an `ElmoreDelayCalculator` for DME construction on `layer`
+    /// under `corner`, using the mean of the horizontal and vertical RC.
+    pub fn delay_calculator(&self, layer: usize, corner: &Corner) -> ElmoreDelayCalculator {
+        let l = &self.layers[layer];
+        let r = (l.horizontal.unit_resistance + l.vertical.unit_resistance) / 2.0;
+        let c = (l.horizontal.unit_capacitance + l.vertical.unit_capacitance) / 2.0;
+        ElmoreDelayCalculator::new(r * corner.resistance_scale, c * corner.capacitance_scale)
+    }
+}
+
+/// Timing of one clock tree under one process corner.
+#[derive(Debug, Clone)]
+pub struct CornerAnalysis {
+    /// Corner name
+    pub corner: String,
+    /// Latency: maximum source-to-sink delay
+    pub max_delay: f64,
+    /// Minimum source-to-sink delay
+    pub min_delay: f64,
+    /// Clock skew = max_delay - min_delay
+    pub skew: f64,
+    /// Individual delays for each sink, in depth-first order
+    pub sink_delays: Vec<f64>,
+    /// Name of the sink with the largest delay in this corner
+    pub latest_sink: String,
+    /// Name of the sink with the smallest delay in this corner
+    pub earliest_sink: String,
+}
+
+/// Results of evaluating a clock tree under every corner of a technology.
+#[derive(Debug, Clone)]
+pub struct MultiCornerAnalysis {
+    /// Per-corner results, in the technology's corner order
+    pub corners: Vec<CornerAnalysis>,
+    /// Index into `corners` of the corner with the largest skew
+    pub worst_skew_corner: usize,
+    /// Index into `corners` of the corner with the largest latency
+    pub worst_latency_corner: usize,
+}
+
+impl MultiCornerAnalysis {
+    /// Returns the name of the latest sink in the worst-latency corner.
+    pub fn worst_sink(&self) -> &str {
+        &self.corners[self.worst_latency_corner].latest_sink
+    }
+}
+
+/// Evaluates an embedded clock tree on `layer` under a single corner.
+///
+/// Each wire is modelled as an L-shape between the embedded positions of a
+/// node and its parent, using horizontal RC for the x-span and vertical RC
+/// for the y-span. Detour length from elongation (`wire_length` beyond the
+/// Manhattan distance) is charged at horizontal RC. Downstream capacitance
+/// is recomputed bottom-up from the leaf capacitances, then Elmore delay is
+/// propagated top-down with a lumped π model per wire:
+///
+/// $$t_{\text{wire}} = R_w \left(\frac{C_w}{2} + C_{\text{down}}\right)$$
+pub fn analyze_corner(
+    tree: &Tree,
+    root: NodeIdx,
+    layer: &RoutingLayer,
+    corner: &Corner,
+) -> CornerAnalysis {
+    let wires: Vec<(f64, f64)> = wire_spans(tree)
+        .into_iter()
+        .map(|(h_len, v_len)| {
+            let r =
+                layer.horizontal.unit_resistance * h_len + layer.vertical.unit_resistance * v_len;
+            let c =
+                layer.horizontal.unit_capacitance * h_len + layer.vertical.unit_capacitance * v_len;
+            (r * corner.resistance_scale, c * corner.capacitance_scale)
+        })
+        .collect();
+    let pin_caps: Vec<f64> = tree
+        .iter()
+        .map(|n| n.capacitance * corner.sink_capacitance_scale)
+        .collect();
+    let delays = tree_elmore_delays(tree, root, &wires, &pin_caps);
+
+    let mut sinks = Vec::new();
+    collect_sinks(tree, root, &mut sinks);
+
+    let mut sink_delays = Vec::with_capacity(sinks.len());
+    let mut latest = root;
+    let mut earliest = root;
+    let mut max_delay = f64::NEG_INFINITY;
+    let mut min_delay = f64::INFINITY;
+    for &s in &sinks {
+        let d = delays[s];
+        sink_delays.push(d);
+        if d > max_delay {
+            max_delay = d;
+            latest = s;
+        }
+        if d < min_delay {
+            min_delay = d;
+            earliest = s;
+        }
+    }
+
+    CornerAnalysis {
+        corner: corner.name.clone(),
+        max_delay,
+        min_delay,
+        skew: max_delay - min_delay,
+        sink_delays,
+        latest_sink: tree.get(latest).name.clone(),
+        earliest_sink: tree.get(earliest).name.clone(),
+    }
+}
+
+/// Evaluates an embedded clock tree on layer `layer` under every corner of
+/// `tech`, reporting per-corner skew and latency and the worst corners.
+///
+/// # Panics
+///
+/// Panics if `tech` has no corners or `layer` is out of range.
+pub fn analyze_corners(
+    tree: &Tree,
+    root: NodeIdx,
+    tech: &Technology,
+    layer: usize,
+) -> MultiCornerAnalysis {
+    assert!(!tech.corners.is_empty(), "No corners provided");
+    let layer = &tech.layers[layer];
+    let corners: Vec<CornerAnalysis> = tech
+        .corners
+        .iter()
+        .map(|c| analyze_corner(tree, root, layer, c))
+        .collect();
+
+    let mut worst_skew_corner = 0;
+    let mut worst_latency_corner = 0;
+    for (i, c) in corners.iter().enumerate() {
+        if c.skew > corners[worst_skew_corner].skew {
+            worst_skew_corner = i;
+        }
+        if c.max_delay > corners[worst_latency_corner].max_delay {
+            worst_latency_corner = i;
+        }
+    }
+
+    MultiCornerAnalysis {
+        corners,
+        worst_skew_corner,
+        worst_latency_corner,
+    }
+}
+
+/// Horizontal and vertical length of the wire from each node to its
+/// parent (zero for the root). Detour length from elongation is counted as
+/// horizontal.
+pub(crate) fn wire_spans(tree: &Tree) -> Vec<(f64, f64)> {
+    tree.iter()
+        .map(|node| match node.parent {
+            Some(p) => {
+                let pp = tree.get(p).position;
+                let dx = (node.position.xcoord - pp.xcoord).abs();
+                let dy = (node.position.ycoord - pp.ycoord).abs();
+                let detour = (node.wire_length - dx - dy).max(0);
+                ((dx + detour) as f64, dy as f64)
+            }
+            None => (0.0, 0.0),
+        })
+        .collect()
+}
+
+/// Elmore delay of every node from `root`, given the `(R, C)` of the wire
+/// above each node and the pin capacitance of each leaf (both indexed by
+/// node). Wires use a lumped π model.
+pub(crate) fn tree_elmore_delays(
+    tree: &Tree,
+    root: NodeIdx,
+    wires: &[(f64, f64)],
+    pin_caps: &[f64],
+) -> Vec<f64> {
+    let mut down_cap = vec![0.0; tree.len()];
+    downstream_capacitance(tree, root, wires, pin_caps, &mut down_cap);
+    let mut delays = vec![0.0; tree.len()];
+    propagate_delays(tree, root, 0.0, wires, &down_cap, &mut delays);
+    delays
+}
+
+fn downstream_capacitance(
+    tree: &Tree,
+    node: NodeIdx,
+    wires: &[(f64, f64)],
+    pin_caps: &[f64],
+    down_cap: &mut [f64],
+) -> f64 {
+    let n = tree.get(node);
+    let cap = if n.is_leaf() {
+        pin_caps[node]
+    } else {
+        let mut total = 0.0;
+        for child in [n.left, n.right].into_iter().flatten() {
+            total +=
+                downstream_capacitance(tree, child, wires, pin_caps, down_cap) + wires[child].1;
+        }
+        total
+    };
+    down_cap[node] = cap;
+    cap
+}
+
+fn propagate_delays(
+    tree: &Tree,
+    node: NodeIdx,
+    delay: f64,
+    wires: &[(f64, f64)],
+    down_cap: &[f64],
+    delays: &mut [f64],
+) {
+    delays[node] = delay;
+    let n = tree.get(node);
+    for child in [n.left, n.right].into_iter().flatten() {
+        let (r, c) = wires[child];
+        let child_delay = delay + r * (c / 2.0 + down_cap[child]);
+        propagate_delays(tree, child, child_delay, wires, down_cap, delays);
+    }
+}
+
+/// Collects the leaves below `node` in depth-first order.
+pub(crate) fn collect_sinks(tree: &Tree, node: NodeIdx, sinks: &mut Vec<NodeIdx>) {
+    let n = tree.get(node);
+    if n.is_leaf() {
+        sinks.push(node);
+    }
+    for child in [n.left, n.right].into_iter().flatten() {
+        collect_sinks(tree, child, sinks);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dme_algorithm::{DMEAlgorithm, DelayCalculator, Sink};
+    use crate::point::Point;
+
+    fn make_tech() -> Technology {
+        let mut tech = Technology::new();
+        tech.add_layer(RoutingLayer::new(
+            "M1",
+            PreferredDirection::Horizontal,
+            WireRC::new(0.1, 0.2),
+            WireRC::new(0.1, 0.2),
+        ));
+        tech.add_layer(RoutingLayer::new(
+            "M2",
+            PreferredDirection::Vertical,
+            WireRC::new(0.1, 0.2),
+            WireRC::new(0.3, 0.2),
+        ));
+        tech.add_corner(Corner::nominal());
+        tech.add_corner(Corner::new("slow", 1.2, 1.1, 1.05));
+        tech.add_corner(Corner::new("fast", 0.8, 0.9, 0.95));
+        tech
+    }
+
+    fn build(sinks: Vec<Sink>, tech: &Technology) -> (DMEAlgorithm, NodeIdx) {
+        let calc = tech.delay_calculator(0, &Corner::nominal());
+        let mut dme = DMEAlgorithm::new(sinks, Box::new(calc));
+        let root = dme.build_clock_tree();
+        (dme, root)
+    }
+
+    #[test]
+    fn test_nominal_corner_matches_elmore_wire_delay() {
+        let tech = make_tech();
+        let sinks = vec![
+            Sink::new("s1", Point::new(0, 0), 1.0),
+            Sink::new("s2", Point::new(10, 0), 1.0),
+        ];
+        let (dme, root) = build(sinks, &tech);
+        let result = analyze_corner(dme.get_tree(), root, &tech.layers[0], &tech.corners[0]);
+        let expected = ElmoreDelayCalculator::new(0.1, 0.2).calculate_wire_delay(5, 1.0);
+        assert!((result.max_delay - expected).abs() < 1e-9);
+        assert!(result.skew.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_vertical_rc_is_used() {
+        let tech = make_tech();
+        let sinks = vec![
+            Sink::new("h", Point::new(10, 0), 1.0),
+            Sink::new("v", Point::new(0, 10), 1.0),
+            Sink::new("o", Point::new(0, 0), 1.0),
+        ];
+        let (dme, root) = build(sinks, &tech);
+        let iso = analyze_corner(dme.get_tree(), root, &tech.layers[0], &tech.corners[0]);
+        let aniso = analyze_corner(dme.get_tree(), root, &tech.layers[1], &tech.corners[0]);
+        assert!(aniso.max_delay > iso.max_delay);
+    }
+
+    #[test]
+    fn test_analyze_corners_reports_worst() {
+        let tech = make_tech();
+        let sinks = (0..8)
+            .map(|i| {
+                Sink::new(
+                    &format!("s{}", i),
+                    Point::new((i * 37) % 100, (i * 53) % 100),
+                    1.0,
+                )
+            })
+            .collect();
+        let (dme, root) = build(sinks, &tech);
+        let result = analyze_corners(dme.get_tree(), root, &tech, 1);
+        assert_eq!(result.corners.len(), 3);
+        assert_eq!(result.corners[result.worst_latency_corner].corner, "slow");
+        assert!(result.corners[1].max_delay > result.corners[0].max_delay);
+        assert!(result.corners[2].max_delay < result.corners[0].max_delay);
+        assert!(!result.worst_sink().is_empty());
+        for c in &result.corners {
+            assert_eq!(c.sink_delays.len(), 8);
+            assert!((c.skew - (c.max_delay - c.min_delay)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_find_layer_and_corner() {
+        let tech = make_tech();
+        assert_eq!(tech.find_layer("M2"), Some(1));
+        assert_eq!(tech.find_layer("M9"), None);
+        assert_eq!(tech.find_corner("fast"), Some(2));
+    }
+}
@@ -0,0 +1,443 @@
+//! Consistency checker for DME clock trees.
+//!
+//! `verify_tree` re-derives everything that can be derived from a `Tree`
+//! and reports where the stored values disagree. It is intended as a
+//! regression oracle after hand-editing a tree via
+//! `DMEAlgorithm::get_tree_mut`, and in test suites.
+//!
+//! Checks are done in two stages. Structural checks come first: index
+//! bounds, binary internal nodes, parent links, cycles and sink
+//! reachability. Electrical checks (wire lengths, capacitance, delay) are
+//! only run when the structure is sound, since they walk the tree.
+
+use core::fmt;
+
+use crate::dme_algorithm::{DMEAlgorithm, DelayCalculator, NodeIdx, Tree};
+use crate::generic::MinDist;
+
+/// Relative tolerance used when comparing recomputed capacitance and delay.
+const REL_TOLERANCE: f64 = 1e-9;
+
+/// A single inconsistency found by `verify_tree`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TreeViolation {
+    /// The root index is outside the tree
+    InvalidRoot(NodeIdx),
+    /// The root has a parent link
+    RootHasParent(NodeIdx),
+    /// A child index is outside the tree
+    ChildOutOfRange {
+        /// Node holding the child link
+        node: NodeIdx,
+        /// The out-of-range child index
+        child: NodeIdx,
+    },
+    /// An internal node has exactly one child
+    MissingChild(NodeIdx),
+    /// A child's parent link does not point back at the node holding it
+    ParentMismatch {
+        /// The child whose parent link is wrong
+        node: NodeIdx,
+        /// The node holding the child link
+        expected: NodeIdx,
+        /// The stored parent link
+        found: Option<NodeIdx>,
+    },
+    /// A node is reached twice from the root (cycle or shared subtree)
+    Cycle(NodeIdx),
+    /// A sink (leaf) cannot be reached from the root
+    UnreachableSink(NodeIdx),
+    /// A wire is shorter than the Manhattan distance it has to span
+    WireTooShort {
+        /// Node at the lower end of the wire
+        node: NodeIdx,
+        /// Stored wire length
+        wire_length: i32,
+        /// Manhattan distance between the node and its parent
+        distance: i32,
+    },
+    /// A wire is longer than the Manhattan distance without `need_elongation`
+    UnexpectedElongation {
+        /// Node at the lower end of the wire
+        node: NodeIdx,
+        /// Stored wire length
+        wire_length: i32,
+        /// Manhattan distance between the node and its parent
+        distance: i32,
+    },
+    /// Stored capacitance differs from the bottom-up recomputation
+    CapacitanceMismatch {
+        /// The internal node
+        node: NodeIdx,
+        /// Capacitance stored on the node
+        stored: f64,
+        /// Capacitance recomputed from the children
+        expected: f64,
+    },
+    /// Stored delay differs from a fresh top-down delay computation
+    DelayMismatch {
+        /// The node
+        node: NodeIdx,
+        /// Delay stored on the node
+        stored: f64,
+        /// Delay recomputed from the parent
+        expected: f64,
+    },
+}
+
+impl fmt::Display for TreeViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TreeViolation::InvalidRoot(n) => write!(f, "Root index {} out of range", n),
+            TreeViolation::RootHasParent(n) => write!(f, "Root {} has a parent", n),
+            TreeViolation::ChildOutOfRange { node, child } => {
+                write!(f, "Node {} has out-of-range child {}", node, child)
+            }
+            TreeViolation::MissingChild(n) => write!(f, "Internal node {} has one child", n),
+            TreeViolation::ParentMismatch {
+                node,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Node {} has parent {:?}, expected {}",
+                node, found, expected
+            ),
+            TreeViolation::Cycle(n) => write!(f, "Node {} is reachable twice", n),
+            TreeViolation::UnreachableSink(n) => write!(f, "Sink {} is unreachable", n),
+            TreeViolation::WireTooShort {
+                node,
+                wire_length,
+                distance,
+            } => write!(
+                f,
+                "Node {} wire length {} is shorter than distance {}",
+                node, wire_length, distance
+            ),
+            TreeViolation::UnexpectedElongation {
+                node,
+                wire_length,
+                distance,
+            } => write!(
+                f,
+                "Node {} wire length {} exceeds distance {} without elongation",
+                node, wire_length, distance
+            ),
+            TreeViolation::CapacitanceMismatch {
+                node,
+                stored,
+                expected,
+            } => write!(
+                f,
+                "Node {} capacitance {} differs from recomputed {}",
+                node, stored, expected
+            ),
+            TreeViolation::DelayMismatch {
+                node,
+                stored,
+                expected,
+            } => write!(
+                f,
+                "Node {} delay {} differs from recomputed {}",
+                node, stored, expected
+            ),
+        }
+    }
+}
+
+/// Verifies the tree rooted at `root` against `calculator`.
+///
+/// Leaf capacitances are taken as given. For an internal node the
+/// capacitance is recomputed bottom-up as
+///
+/// $$C_v = C_l + C_r + c \cdot (L_l + L_r)$$
+///
+/// where $L$ are the embedded child wire lengths. Delays are recomputed
+/// top-down from the stored capacitances exactly as the DME builder does. If `root`
+/// hangs off the tree's source node, verification starts at the source: the
+/// source may have a single child, and its delay is the driver delay
+/// $R_d \cdot C_{src}$. Returns an empty list if the tree is consistent.
+pub fn verify_tree(
+    tree: &Tree,
+    root: NodeIdx,
+    calculator: &dyn DelayCalculator,
+) -> Vec<TreeViolation> {
+    let mut violations = Vec::new();
+    let root = match tree.source {
+        Some(src) if root < tree.len() && tree.get(root).parent == Some(src) => src,
+        _ => root,
+    };
+    let order = match check_structure(tree, root, &mut violations) {
+        Some(order) if violations.is_empty() => order,
+        _ => return violations,
+    };
+
+    // `order` is a pre-order, so walking it backwards visits children
+    // before their parents.
+    for &idx in order.iter().rev() {
+        let node = tree.get(idx);
+        if let Some(p) = node.parent {
+            let distance = node.position.min_dist_with(&tree.get(p).position) as i32;
+            if node.wire_length < distance {
+                violations.push(TreeViolation::WireTooShort {
+                    node: idx,
+                    wire_length: node.wire_length,
+                    distance,
+                });
+            } else if node.wire_length > distance && !node.need_elongation {
+                violations.push(TreeViolation::UnexpectedElongation {
+                    node: idx,
+                    wire_length: node.wire_length,
+                    distance,
+                });
+            }
+        }
+        if !node.is_leaf() {
+            let children = [node.left, node.right].into_iter().flatten();
+            let (cap, wl) = children.fold((0.0, 0), |(c, l), child| {
+                let cn = tree.get(child);
+                (c + cn.capacitance, l + cn.wire_length)
+            });
+            let expected = cap + calculator.calculate_wire_capacitance(wl);
+            if !approx_eq(node.capacitance, expected) {
+                violations.push(TreeViolation::CapacitanceMismatch {
+                    node: idx,
+                    stored: node.capacitance,
+                    expected,
+                });
+            }
+        }
+    }
+
+    let mut delays = vec![0.0; tree.len()];
+    for &idx in &order {
+        let node = tree.get(idx);
+        let expected = match node.parent {
+            Some(p) if idx != root => {
+                delays[p] + calculator.calculate_wire_delay(node.wire_length, node.capacitance)
+            }
+            _ if tree.source == Some(idx) => tree.driver_resistance * node.capacitance,
+            _ => 0.0,
+        };
+        delays[idx] = expected;
+        if !approx_eq(node.delay, expected) {
+            violations.push(TreeViolation::DelayMismatch {
+                node: idx,
+                stored: node.delay,
+                expected,
+            });
+        }
+    }
+
+    violations
+}
+
+/// Runs the structural checks and returns the pre-order of the nodes
+/// reachable from `root`, or `None` if the root itself is invalid.
+fn check_structure(
+    tree: &Tree,
+    root: NodeIdx,
+    violations: &mut Vec<TreeViolation>,
+) -> Option<Vec<NodeIdx>> {
+    if root >= tree.len() {
+        violations.push(TreeViolation::InvalidRoot(root));
+        return None;
+    }
+    if tree.get(root).parent.is_some() {
+        violations.push(TreeViolation::RootHasParent(root));
+    }
+
+    let mut visited = vec![false; tree.len()];
+    let mut order = Vec::new();
+    let mut stack = vec![root];
+    while let Some(idx) = stack.pop() {
+        if visited[idx] {
+            violations.push(TreeViolation::Cycle(idx));
+            continue;
+        }
+        visited[idx] = true;
+        order.push(idx);
+        let node = tree.get(idx);
+        let single_child_allowed = tree.source == Some(idx) && node.right.is_none();
+        if node.left.is_some() != node.right.is_some() && !single_child_allowed {
+            violations.push(TreeViolation::MissingChild(idx));
+        }
+        // Push right first so that left subtrees come first in pre-order.
+        for child in [node.right, node.left].into_iter().flatten() {
+            if child >= tree.len() {
+                violations.push(TreeViolation::ChildOutOfRange { node: idx, child });
+                continue;
+            }
+            let found = tree.get(child).parent;
+            if found != Some(idx) {
+                violations.push(TreeViolation::ParentMismatch {
+                    node: child,
+                    expected: idx,
+                    found,
+                });
+            }
+            stack.push(child);
+        }
+    }
+
+    for (idx, node) in tree.iter().enumerate() {
+        if node.is_leaf() && !visited[idx] {
+            violations.push(TreeViolation::UnreachableSink(idx));
+        }
+    }
+    Some(order)
+}
+
+fn approx_eq(a: f64, b: f64) -> bool {
+    (a - b).abs() <= REL_TOLERANCE * a.abs().max(b.abs()).max(1.0)
+}
+
+impl DMEAlgorithm {
+    /// Verifies the current tree with this algorithm's delay model.
+    ///
+    /// See `verify_tree`.
+    pub fn verify(&self, root: NodeIdx) -> Vec<TreeViolation> {
+        verify_tree(self.get_tree(), root, self.delay_calculator())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dme_algorithm::{ElmoreDelayCalculator, LinearDelayCalculator, Sink};
+    use crate::point::Point;
+
+    fn make_sinks(count: i32) -> Vec<Sink> {
+        (0..count)
+            .map(|i| {
+                Sink::new(
+                    &format!("s{}", i),
+                    Point::new((i * 37) % 200, (i * 53) % 200),
+                    1.0 + (i % 3) as f64 * 0.5,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_built_trees_are_consistent() {
+        for n in [1, 2, 7, 32] {
+            let mut dme = DMEAlgorithm::new(
+                make_sinks(n),
+                Box::new(ElmoreDelayCalculator::new(0.1, 0.2)),
+            );
+            let root = dme.build_clock_tree();
+            assert_eq!(dme.verify(root), vec![]);
+
+            let mut dme = DMEAlgorithm::new(
+                make_sinks(n),
+                Box::new(LinearDelayCalculator::new(0.5, 0.1)),
+            );
+            let root = dme.build_clock_tree();
+            assert_eq!(dme.verify(root), vec![]);
+        }
+    }
+
+    #[test]
+    fn test_detects_structural_violations() {
+        let mut dme = DMEAlgorithm::new(
+            make_sinks(8),
+            Box::new(LinearDelayCalculator::new(0.5, 0.1)),
+        );
+        let root = dme.build_clock_tree();
+        let len = dme.get_tree().len();
+        assert_eq!(dme.verify(len), vec![TreeViolation::InvalidRoot(len)]);
+
+        let left = dme.get_tree().get(root).left.unwrap();
+        dme.get_tree_mut().get_mut(root).left = None;
+        let violations = dme.verify(root);
+        assert!(violations.contains(&TreeViolation::MissingChild(root)));
+        assert!(violations
+            .iter()
+            .any(|v| matches!(v, TreeViolation::UnreachableSink(_))));
+
+        dme.get_tree_mut().get_mut(root).left = Some(left);
+        dme.get_tree_mut().get_mut(left).parent = None;
+        assert!(dme.verify(root).contains(&TreeViolation::ParentMismatch {
+            node: left,
+            expected: root,
+            found: None,
+        }));
+    }
+
+    #[test]
+    fn test_detects_cycle() {
+        let mut dme = DMEAlgorithm::new(
+            make_sinks(4),
+            Box::new(LinearDelayCalculator::new(0.5, 0.1)),
+        );
+        let root = dme.build_clock_tree();
+        let left = dme.get_tree().get(root).left.unwrap();
+        let right = dme.get_tree().get(root).right.unwrap();
+        dme.get_tree_mut().get_mut(left).left = Some(right);
+        let violations = dme.verify(root);
+        assert!(violations.contains(&TreeViolation::Cycle(right)));
+    }
+
+    #[test]
+    fn test_detects_electrical_violations() {
+        let mut dme = DMEAlgorithm::new(
+            make_sinks(8),
+            Box::new(ElmoreDelayCalculator::new(0.1, 0.2)),
+        );
+        let root = dme.build_clock_tree();
+        let leaf = (0..dme.get_tree().len())
+            .find(|&i| dme.get_tree().get(i).is_leaf() && dme.get_tree().get(i).wire_length > 0)
+            .unwrap();
+        let parent = dme.get_tree().get(leaf).parent.unwrap();
+
+        dme.get_tree_mut().get_mut(leaf).wire_length -= 1;
+        let violations = dme.verify(root);
+        assert!(violations
+            .iter()
+            .any(|v| matches!(v, TreeViolation::WireTooShort { node, .. } if *node == leaf)));
+        assert!(violations.iter().any(
+            |v| matches!(v, TreeViolation::CapacitanceMismatch { node, .. } if *node == parent)
+        ));
+
+        dme.get_tree_mut().get_mut(leaf).wire_length += 2;
+        assert!(dme.verify(root).iter().any(
+            |v| matches!(v, TreeViolation::UnexpectedElongation { node, .. } if *node == leaf)
+        ));
+        dme.get_tree_mut().get_mut(leaf).need_elongation = true;
+        assert!(!dme
+            .verify(root)
+            .iter()
+            .any(|v| matches!(v, TreeViolation::UnexpectedElongation { .. })));
+
+        dme.get_tree_mut().get_mut(leaf).wire_length -= 1;
+        dme.get_tree_mut().get_mut(leaf).delay += 1.0;
+        assert_eq!(
+            dme.verify(root),
+            vec![TreeViolation::DelayMismatch {
+                node: leaf,
+                stored: dme.get_tree().get(leaf).delay,
+                expected: dme.get_tree().get(leaf).delay - 1.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_detects_stale_capacitance() {
+        let mut dme = DMEAlgorithm::new(
+            make_sinks(8),
+            Box::new(ElmoreDelayCalculator::new(0.1, 0.2)),
+        );
+        let root = dme.build_clock_tree();
+        let tree = dme.get_tree();
+        let (l, r) = (tree.get(root).left.unwrap(), tree.get(root).right.unwrap());
+        let lower = tree.get(l).capacitance + tree.get(r).capacitance;
+        // Between the children's load and the full load with both wires
+        let stale = (lower + tree.get(root).capacitance) / 2.0;
+        dme.get_tree_mut().get_mut(root).capacitance = stale;
+        assert!(dme.verify(root).iter().any(|v| matches!(
+            v,
+            TreeViolation::CapacitanceMismatch { node, stored, .. } if *node == root && *stored == stale
+        )));
+    }
+}
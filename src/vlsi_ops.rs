@@ -2,6 +2,8 @@
 //!
 //! This module provides operations commonly used in VLSI physical design and layout.
 
+use num_traits::PrimInt;
+
 use crate::{Point, Polygon};
 
 /// Represents a rectangle in 2D space defined by its minimum (bottom-left)
@@ -230,6 +232,242 @@ where
     None
 }
 
+/// Active y-intervals of a sweep, kept at fixed slots ordered by lower
+/// bound. Each tree node stores the largest upper bound among the active
+/// intervals below it, so a stabbing query descends only into subtrees that
+/// hold a reported interval.
+struct ActiveIntervals<T> {
+    /// `(lb, ub)` per slot, sorted by `lb`
+    slots: Vec<(T, T)>,
+    /// Max active `ub` per node of a complete binary tree over the slots
+    max_ub: Vec<Option<T>>,
+    size: usize,
+}
+
+impl<T: Copy + Ord> ActiveIntervals<T> {
+    fn new(slots: Vec<(T, T)>) -> Self {
+        let size = slots.len().next_power_of_two();
+        ActiveIntervals {
+            slots,
+            max_ub: vec![None; 2 * size],
+            size,
+        }
+    }
+
+    fn set(&mut self, slot: usize, active: bool) {
+        let mut node = slot + self.size;
+        self.max_ub[node] = if active {
+            Some(self.slots[slot].1)
+        } else {
+            None
+        };
+        while node > 1 {
+            node /= 2;
+            self.max_ub[node] = self.max_ub[2 * node].max(self.max_ub[2 * node + 1]);
+        }
+    }
+
+    /// Pushes the slots of the active intervals meeting `[lo, hi]`.
+    fn query(&self, lo: T, hi: T, out: &mut Vec<usize>) {
+        // Only slots with lb <= hi can meet the query.
+        let end = self.slots.partition_point(|&(lb, _)| lb <= hi);
+        self.collect(1, 0, self.size, end, lo, out);
+    }
+
+    fn collect(
+        &self,
+        node: usize,
+        first: usize,
+        last: usize,
+        end: usize,
+        lo: T,
+        out: &mut Vec<usize>,
+    ) {
+        if first >= end || self.max_ub[node].map_or(true, |ub| ub < lo) {
+            return;
+        }
+        if last - first == 1 {
+            out.push(first);
+            return;
+        }
+        let mid = (first + last) / 2;
+        self.collect(2 * node, first, mid, end, lo, out);
+        self.collect(2 * node + 1, mid, last, end, lo, out);
+    }
+}
+
+/// Finds every pair of overlapping rectangles with their intersection.
+///
+/// Rectangles are closed, as in `Rectangle::overlaps`, so rectangles that
+/// only touch are reported with a degenerate (zero-area) intersection.
+///
+/// A sweep over x keeps the rectangles cut by the sweep line in an interval
+/// tree on y; each new rectangle is checked against the active intervals it
+/// meets only. This takes $O((n + k) \log n)$ time for `n` rectangles and
+/// `k` reported pairs.
+///
+/// # Returns
+///
+/// The pairs `(i, j, intersection)` with `i < j`, sorted by `(i, j)`
+///
+/// # Examples
+///
+/// ```
+/// use physdes::{Point, vlsi_ops::{Rectangle, find_all_overlaps}};
+///
+/// let rects = vec![
+///     Rectangle::new(Point::new(0, 0), Point::new(10, 10)),
+///     Rectangle::new(Point::new(5, 5), Point::new(15, 15)),
+///     Rectangle::new(Point::new(8, 0), Point::new(20, 6)),
+/// ];
+/// let overlaps = find_all_overlaps(&rects);
+/// assert_eq!(overlaps.len(), 3);
+/// assert_eq!(
+///     overlaps[0],
+///     (0, 1, Rectangle::new(Point::new(5, 5), Point::new(10, 10)))
+/// );
+/// ```
+pub fn find_all_overlaps<T>(rectangles: &[Rectangle<T>]) -> Vec<(usize, usize, Rectangle<T>)>
+where
+    T: Copy + Ord + std::ops::Add<Output = T>,
+{
+    let mut order: Vec<usize> = (0..rectangles.len()).collect();
+    order.sort_by_key(|&i| (rectangles[i].min.ycoord, i));
+    let mut slot_of = vec![0; rectangles.len()];
+    for (slot, &i) in order.iter().enumerate() {
+        slot_of[i] = slot;
+    }
+    let mut active = ActiveIntervals::new(
+        order
+            .iter()
+            .map(|&i| (rectangles[i].min.ycoord, rectangles[i].max.ycoord))
+            .collect(),
+    );
+
+    // (x, is_end, index): at equal x, starts come before ends so that
+    // touching rectangles are reported
+    let mut events: Vec<(T, bool, usize)> = Vec::with_capacity(rectangles.len() * 2);
+    for (idx, rect) in rectangles.iter().enumerate() {
+        events.push((rect.min.xcoord, false, idx));
+        events.push((rect.max.xcoord, true, idx));
+    }
+    events.sort();
+
+    let mut result = Vec::new();
+    let mut hits = Vec::new();
+    for (_x, is_end, idx) in events {
+        if is_end {
+            active.set(slot_of[idx], false);
+            continue;
+        }
+        let rect = &rectangles[idx];
+        hits.clear();
+        active.query(rect.min.ycoord, rect.max.ycoord, &mut hits);
+        for &slot in &hits {
+            let other = order[slot];
+            let (i, j) = (idx.min(other), idx.max(other));
+            if let Some(region) = rectangles[i].intersect(&rectangles[j]) {
+                result.push((i, j, region));
+            }
+        }
+        active.set(slot_of[idx], true);
+    }
+    result.sort_by_key(|&(i, j, _)| (i, j));
+    result
+}
+
+/// Graph with a vertex per rectangle and an edge per overlapping pair.
+///
+/// # Examples
+///
+/// ```
+/// use physdes::{Point, vlsi_ops::{Rectangle, OverlapGraph}};
+///
+/// let rects = vec![
+///     Rectangle::new(Point::new(0, 0), Point::new(10, 10)),
+///     Rectangle::new(Point::new(30, 30), Point::new(40, 40)),
+///     Rectangle::new(Point::new(5, 5), Point::new(15, 15)),
+/// ];
+/// let graph = OverlapGraph::new(&rects);
+/// assert_eq!(graph.neighbors(0), &[2]);
+/// assert_eq!(graph.components(), vec![vec![0, 2], vec![1]]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct OverlapGraph<T> {
+    overlaps: Vec<(usize, usize, Rectangle<T>)>,
+    adjacency: Vec<Vec<usize>>,
+}
+
+impl<T> OverlapGraph<T>
+where
+    T: Copy + Ord + std::ops::Add<Output = T>,
+{
+    /// Builds the overlap graph of `rectangles` (see `find_all_overlaps`).
+    pub fn new(rectangles: &[Rectangle<T>]) -> Self {
+        let overlaps = find_all_overlaps(rectangles);
+        let mut adjacency = vec![Vec::new(); rectangles.len()];
+        for &(i, j, _) in &overlaps {
+            adjacency[i].push(j);
+            adjacency[j].push(i);
+        }
+        for adj in &mut adjacency {
+            adj.sort_unstable();
+        }
+        OverlapGraph {
+            overlaps,
+            adjacency,
+        }
+    }
+
+    /// Number of rectangles.
+    pub fn len(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    /// Returns `true` if the graph has no rectangles.
+    pub fn is_empty(&self) -> bool {
+        self.adjacency.is_empty()
+    }
+
+    /// The overlapping pairs with their intersections, sorted.
+    pub fn overlaps(&self) -> &[(usize, usize, Rectangle<T>)] {
+        &self.overlaps
+    }
+
+    /// Rectangles overlapping rectangle `i`, ascending.
+    pub fn neighbors(&self, i: usize) -> &[usize] {
+        &self.adjacency[i]
+    }
+
+    /// Groups of transitively overlapping rectangles, each ascending and
+    /// ordered by their smallest index; rectangles overlapping nothing form
+    /// groups of one.
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let mut seen = vec![false; self.len()];
+        let mut groups = Vec::new();
+        for start in 0..self.len() {
+            if seen[start] {
+                continue;
+            }
+            seen[start] = true;
+            let mut group = vec![start];
+            let mut stack = vec![start];
+            while let Some(v) = stack.pop() {
+                for &w in &self.adjacency[v] {
+                    if !seen[w] {
+                        seen[w] = true;
+                        group.push(w);
+                        stack.push(w);
+                    }
+                }
+            }
+            group.sort_unstable();
+            groups.push(group);
+        }
+        groups
+    }
+}
+
 /// Calculates Manhattan distance between two points
 ///
 /// $$d = |x_1 - x_2| + |y_1 - y_2|$$
@@ -271,6 +509,14 @@ where
 
 /// Checks if two rectangles satisfy minimum spacing requirements
 ///
+/// Side-by-side rectangles are measured between their facing edges;
+/// diagonal neighbours corner to corner:
+///
+/// $$d = \sqrt{dx^2 + dy^2}$$
+///
+/// Only gaps below `min_spacing` are squared, so any coordinates may be
+/// used as long as `2 * min_spacing^2` fits in `T`.
+///
 /// # Arguments
 ///
 /// * `rect1` - First rectangle
@@ -294,31 +540,49 @@ where
 /// ```
 pub fn check_spacing<T>(rect1: &Rectangle<T>, rect2: &Rectangle<T>, min_spacing: T) -> bool
 where
-    T: Ord + Copy + std::ops::Sub<Output = T> + std::ops::Add<Output = T>,
+    T: Ord
+        + Copy
+        + std::ops::Sub<Output = T>
+        + std::ops::Add<Output = T>
+        + std::ops::Mul<Output = T>,
 {
     if rect1.overlaps(rect2) {
         return false;
     }
 
-    // Calculate horizontal spacing
-    let horiz_spacing = if rect1.max.xcoord <= rect2.min.xcoord {
-        rect2.min.xcoord - rect1.max.xcoord
-    } else if rect2.max.xcoord <= rect1.min.xcoord {
-        rect1.min.xcoord - rect2.max.xcoord
-    } else {
-        T::clone(&min_spacing) // Overlapping in x, check y
-    };
-
-    // Calculate vertical spacing
-    let vert_spacing = if rect1.max.ycoord <= rect2.min.ycoord {
-        rect2.min.ycoord - rect1.max.ycoord
-    } else if rect2.max.ycoord <= rect1.min.ycoord {
-        rect1.min.ycoord - rect2.max.ycoord
-    } else {
-        T::clone(&min_spacing) // Overlapping in y, check x
+    // Gap along each axis, `None` where the projections overlap
+    let gap = |lo1: T, hi1: T, lo2: T, hi2: T| {
+        if hi1 < lo2 {
+            Some(lo2 - hi1)
+        } else if hi2 < lo1 {
+            Some(lo1 - hi2)
+        } else {
+            None
+        }
     };
-
-    horiz_spacing >= min_spacing && vert_spacing >= min_spacing
+    let horiz = gap(
+        rect1.min.xcoord,
+        rect1.max.xcoord,
+        rect2.min.xcoord,
+        rect2.max.xcoord,
+    );
+    let vert = gap(
+        rect1.min.ycoord,
+        rect1.max.ycoord,
+        rect2.min.ycoord,
+        rect2.max.ycoord,
+    );
+
+    match (horiz, vert) {
+        // Diagonal neighbours: Euclidean corner-to-corner distance. A gap of
+        // `min_spacing` along one axis already suffices; otherwise both gaps
+        // are below `min_spacing`, so only values up to its square arise.
+        (Some(dx), Some(dy)) => {
+            dx >= min_spacing || dy >= min_spacing || dx * dx + dy * dy >= min_spacing * min_spacing
+        }
+        (Some(d), None) | (None, Some(d)) => d >= min_spacing,
+        (None, None) => false,
+    }
 }
 
 /// Computes the minimum bounding rectangle of a set of rectangles
@@ -369,7 +633,65 @@ where
     ))
 }
 
-/// Computes total area covered by rectangles, accounting for overlaps
+/// Covered length of a set of y-intervals under insertion and removal: a
+/// segment tree over the elementary intervals between sorted coordinates,
+/// with a cover count and the covered length per node.
+struct CoverTree<T> {
+    coords: Vec<T>,
+    count: Vec<u32>,
+    covered: Vec<T>,
+}
+
+impl<T: PrimInt> CoverTree<T> {
+    fn new(coords: Vec<T>) -> Self {
+        let n = 4 * coords.len().max(1);
+        CoverTree {
+            coords,
+            count: vec![0; n],
+            covered: vec![T::zero(); n],
+        }
+    }
+
+    /// Adds `delta` to the cover count of `[lo, hi]`.
+    fn update(&mut self, lo: T, hi: T, delta: i32) {
+        if self.coords.len() > 1 && lo < hi {
+            self.update_node(1, 0, self.coords.len() - 1, lo, hi, delta);
+        }
+    }
+
+    fn update_node(&mut self, node: usize, first: usize, last: usize, lo: T, hi: T, delta: i32) {
+        if hi <= self.coords[first] || self.coords[last] <= lo {
+            return;
+        }
+        if lo <= self.coords[first] && self.coords[last] <= hi {
+            self.count[node] = (self.count[node] as i32 + delta) as u32;
+        } else {
+            let mid = (first + last) / 2;
+            self.update_node(2 * node, first, mid, lo, hi, delta);
+            self.update_node(2 * node + 1, mid, last, lo, hi, delta);
+        }
+        self.covered[node] = if self.count[node] > 0 {
+            self.coords[last] - self.coords[first]
+        } else if last - first == 1 {
+            T::zero()
+        } else {
+            self.covered[2 * node] + self.covered[2 * node + 1]
+        };
+    }
+
+    fn covered_length(&self) -> T {
+        self.covered[1]
+    }
+}
+
+/// Computes the exact area covered by the union of rectangles
+///
+/// A sweep over x keeps the covered length of the sweep line in a segment
+/// tree on y; the area is the covered length integrated between events:
+///
+/// $$A = \sum_i L(x_i) \cdot (x_{i+1} - x_i)$$
+///
+/// This takes $O(n \log n)$ time for `n` rectangles.
 ///
 /// # Examples
 ///
@@ -385,23 +707,207 @@ where
 /// // First rect: 100, second rect: 100, overlap: 25, total: 175
 /// assert_eq!(area, 175);
 /// ```
-pub fn total_area(rects: &[Rectangle<i32>]) -> i32 {
-    if rects.is_empty() {
-        return 0;
+pub fn total_area<T: PrimInt>(rects: &[Rectangle<T>]) -> T {
+    let mut ys: Vec<T> = rects
+        .iter()
+        .flat_map(|r| [r.min.ycoord, r.max.ycoord])
+        .collect();
+    ys.sort_unstable();
+    ys.dedup();
+    let mut cover = CoverTree::new(ys);
+
+    // (x, delta, index)
+    let mut events: Vec<(T, i32, usize)> = Vec::with_capacity(rects.len() * 2);
+    for (idx, rect) in rects.iter().enumerate() {
+        events.push((rect.min.xcoord, 1, idx));
+        events.push((rect.max.xcoord, -1, idx));
+    }
+    events.sort_unstable();
+
+    let mut total = T::zero();
+    let mut prev_x = events.first().map_or(T::zero(), |e| e.0);
+    for (x, delta, idx) in events {
+        total = total + cover.covered_length() * (x - prev_x);
+        prev_x = x;
+        cover.update(rects[idx].min.ycoord, rects[idx].max.ycoord, delta);
     }
+    total
+}
 
-    let mut total = 0;
-    for (i, rect) in rects.iter().enumerate() {
-        total += rect.area();
-        // Subtract overlaps with previously counted rectangles
-        for other in &rects[..i] {
-            if let Some(intersection) = rect.intersect(other) {
-                total -= intersection.area();
+/// Computes the union of rectangles as non-overlapping rectangles
+///
+/// The union is cut at every rectangle x-coordinate into vertical slabs;
+/// within a slab the covered y-intervals are merged, and a rectangle is
+/// extended into the next slab while its y-interval stays the same. The
+/// pieces have disjoint interiors and positive area, and their total area
+/// equals `total_area(rects)`.
+///
+/// # Examples
+///
+/// ```
+/// use physdes::{Point, vlsi_ops::{Rectangle, union_rectangles}};
+///
+/// let rects = vec![
+///     Rectangle::new(Point::new(0, 0), Point::new(10, 10)),
+///     Rectangle::new(Point::new(10, 0), Point::new(20, 10)),
+///     Rectangle::new(Point::new(5, 5), Point::new(8, 8)),
+/// ];
+/// let union = union_rectangles(&rects);
+/// assert_eq!(union, vec![Rectangle::new(Point::new(0, 0), Point::new(20, 10))]);
+/// ```
+pub fn union_rectangles<T: PrimInt>(rects: &[Rectangle<T>]) -> Vec<Rectangle<T>> {
+    let mut xs: Vec<T> = rects
+        .iter()
+        .flat_map(|r| [r.min.xcoord, r.max.xcoord])
+        .collect();
+    xs.sort_unstable();
+    xs.dedup();
+    let mut order: Vec<&Rectangle<T>> = rects.iter().collect();
+    order.sort_by_key(|r| r.min.xcoord);
+
+    let mut pieces: Vec<Rectangle<T>> = Vec::new();
+    // Pieces reaching the current slab, by y-interval
+    let mut open: Vec<usize> = Vec::new();
+    let mut next = 0;
+    let mut active: Vec<&Rectangle<T>> = Vec::new();
+    for slab in xs.windows(2) {
+        let (xl, xr) = (slab[0], slab[1]);
+        while next < order.len() && order[next].min.xcoord <= xl {
+            active.push(order[next]);
+            next += 1;
+        }
+        active.retain(|r| r.max.xcoord > xl);
+
+        let mut spans: Vec<(T, T)> = active
+            .iter()
+            .filter(|r| r.min.ycoord < r.max.ycoord)
+            .map(|r| (r.min.ycoord, r.max.ycoord))
+            .collect();
+        spans.sort_unstable();
+        let mut merged: Vec<(T, T)> = Vec::new();
+        for (lo, hi) in spans {
+            match merged.last_mut() {
+                Some(last) if lo <= last.1 => last.1 = last.1.max(hi),
+                _ => merged.push((lo, hi)),
             }
         }
+
+        let mut next_open = Vec::with_capacity(merged.len());
+        for (lo, hi) in merged {
+            match open
+                .iter()
+                .find(|&&k| pieces[k].min.ycoord == lo && pieces[k].max.ycoord == hi)
+            {
+                Some(&k) => {
+                    pieces[k].max.xcoord = xr;
+                    next_open.push(k);
+                }
+                None => {
+                    next_open.push(pieces.len());
+                    pieces.push(Rectangle::new(Point::new(xl, lo), Point::new(xr, hi)));
+                }
+            }
+        }
+        open = next_open;
     }
+    pieces
+}
 
-    total
+/// Fraction of `window` covered by the union of `rects`, in `[0, 1]`
+///
+/// # Panics
+///
+/// Panics if `window` has zero area.
+pub fn window_density<T: PrimInt>(rects: &[Rectangle<T>], window: &Rectangle<T>) -> f64 {
+    let area = window.area();
+    assert!(area > T::zero(), "Window must have positive area");
+    let clipped: Vec<Rectangle<T>> = rects.iter().filter_map(|r| r.intersect(window)).collect();
+    total_area(&clipped).to_f64().unwrap() / area.to_f64().unwrap()
+}
+
+/// Densities of square windows of side `size` stepped by `step` over `region`
+///
+/// Windows start at the lower-left corner of `region`; the last window of a
+/// row or column ends at the region boundary, shifted back if needed, so every
+/// window lies inside the region (windows are clipped to a region smaller than
+/// `size`). Returns `(window, density)` row by row, bottom row first.
+///
+/// # Panics
+///
+/// Panics if `size` or `step` is not positive or `region` has zero area.
+///
+/// # Examples
+///
+/// ```
+/// use physdes::{Point, vlsi_ops::{Rectangle, density_windows}};
+///
+/// let metal = vec![Rectangle::new(Point::new(0, 0), Point::new(10, 20))];
+/// let region = Rectangle::new(Point::new(0, 0), Point::new(20, 20));
+/// let windows = density_windows(&metal, &region, 10, 10);
+/// let densities: Vec<f64> = windows.iter().map(|(_, d)| *d).collect();
+/// assert_eq!(densities, vec![1.0, 0.0, 1.0, 0.0]);
+/// ```
+pub fn density_windows<T: PrimInt>(
+    rects: &[Rectangle<T>],
+    region: &Rectangle<T>,
+    size: T,
+    step: T,
+) -> Vec<(Rectangle<T>, f64)> {
+    assert!(
+        size > T::zero() && step > T::zero(),
+        "Window size and step must be positive"
+    );
+    let starts = |lo: T, hi: T| {
+        let mut result = vec![lo];
+        if hi - lo > size {
+            let last = hi - size;
+            let mut s = lo + step;
+            while s < last {
+                result.push(s);
+                s = s + step;
+            }
+            result.push(last);
+        }
+        result
+    };
+    let xs = starts(region.min.xcoord, region.max.xcoord);
+    let ys = starts(region.min.ycoord, region.max.ycoord);
+    let mut result = Vec::with_capacity(xs.len() * ys.len());
+    for &y in &ys {
+        for &x in &xs {
+            let window = Rectangle::new(
+                Point::new(x, y),
+                Point::new(
+                    (x + size).min(region.max.xcoord),
+                    (y + size).min(region.max.ycoord),
+                ),
+            );
+            let density = window_density(rects, &window);
+            result.push((window, density));
+        }
+    }
+    result
+}
+
+/// A deterministic scatter of `n` rectangles for tests: lower-left corners
+/// in `[0, span)` and sides in `[0, size)`, from a linear congruential
+/// generator started at `seed`.
+#[cfg(test)]
+pub(crate) fn scatter_rects(seed: u32, n: usize, span: i32, size: i32) -> Vec<Rectangle<i32>> {
+    let mut seed = seed;
+    let mut next = move || {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        (seed >> 16) as i32
+    };
+    (0..n)
+        .map(|_| {
+            let (x, y) = (next() % span, next() % span);
+            Rectangle::new(
+                Point::new(x, y),
+                Point::new(x + next() % size, y + next() % size),
+            )
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -631,4 +1137,131 @@ mod tests {
         assert!(check_spacing(&r1, &r2, 10));
         assert!(!check_spacing(&r1, &r2, 11));
     }
+
+    #[test]
+    fn test_find_all_overlaps_matches_brute_force() {
+        let rects = scatter_rects(7, 300, 200, 15);
+        let mut expected = Vec::new();
+        for i in 0..rects.len() {
+            for j in i + 1..rects.len() {
+                if let Some(region) = rects[i].intersect(&rects[j]) {
+                    expected.push((i, j, region));
+                }
+            }
+        }
+        assert!(!expected.is_empty());
+        assert_eq!(find_all_overlaps(&rects), expected);
+        assert!(find_all_overlaps::<i32>(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_overlap_graph_components() {
+        let rects = vec![
+            Rectangle::new(Point::new(0, 0), Point::new(10, 10)),
+            Rectangle::new(Point::new(20, 0), Point::new(30, 10)),
+            Rectangle::new(Point::new(10, 10), Point::new(12, 12)), // touches 0 at a corner
+            Rectangle::new(Point::new(11, 0), Point::new(19, 11)),  // meets 2
+            Rectangle::new(Point::new(50, 50), Point::new(60, 60)),
+        ];
+        let graph = OverlapGraph::new(&rects);
+        assert_eq!(graph.len(), 5);
+        assert_eq!(graph.overlaps().len(), 2);
+        assert_eq!(
+            graph.overlaps()[0],
+            (0, 2, Rectangle::new(Point::new(10, 10), Point::new(10, 10)))
+        );
+        assert_eq!(graph.neighbors(2), &[0, 3]);
+        assert_eq!(graph.components(), vec![vec![0, 2, 3], vec![1], vec![4]]);
+    }
+
+    #[test]
+    fn test_total_area_three_way_overlap() {
+        // The old pairwise formula subtracted the triple overlap once too often
+        let rects = vec![
+            Rectangle::new(Point::new(0, 0), Point::new(10, 10)),
+            Rectangle::new(Point::new(5, 0), Point::new(15, 10)),
+            Rectangle::new(Point::new(2, 2), Point::new(12, 8)),
+        ];
+        assert_eq!(total_area(&rects), 150);
+        let rects64: Vec<Rectangle<i64>> = vec![Rectangle::new(
+            Point::new(0, 0),
+            Point::new(100_000, 100_000),
+        )];
+        assert_eq!(total_area(&rects64), 10_000_000_000);
+    }
+
+    #[test]
+    fn test_union_rectangles_and_density() {
+        let rects = vec![
+            Rectangle::new(Point::new(0, 0), Point::new(10, 10)),
+            Rectangle::new(Point::new(5, 5), Point::new(15, 15)),
+            Rectangle::new(Point::new(2, 2), Point::new(3, 3)),
+            Rectangle::new(Point::new(20, 0), Point::new(20, 30)), // zero area
+        ];
+        let union = union_rectangles(&rects);
+        assert_eq!(
+            union,
+            vec![
+                Rectangle::new(Point::new(0, 0), Point::new(5, 10)),
+                Rectangle::new(Point::new(5, 0), Point::new(10, 15)),
+                Rectangle::new(Point::new(10, 5), Point::new(15, 15)),
+            ]
+        );
+        assert_eq!(
+            find_all_overlaps(&union)
+                .iter()
+                .filter(|(_, _, r)| r.area() > 0)
+                .count(),
+            0
+        );
+        assert_eq!(
+            union.iter().map(|r| r.area()).sum::<i32>(),
+            total_area(&rects)
+        );
+
+        let window = Rectangle::new(Point::new(0, 0), Point::new(10, 20));
+        assert_eq!(window_density(&rects, &window), 125.0 / 200.0);
+        let region = Rectangle::new(Point::new(0, 0), Point::new(25, 15));
+        let windows = density_windows(&rects, &region, 10, 10);
+        assert_eq!(windows.len(), 6);
+        assert_eq!(
+            windows[2].0,
+            Rectangle::new(Point::new(15, 0), Point::new(25, 10))
+        );
+        assert_eq!(
+            windows[3].0,
+            Rectangle::new(Point::new(0, 5), Point::new(10, 15))
+        );
+        assert_eq!(windows[0].1, 1.0);
+        assert_eq!(windows[2].1, 0.0);
+    }
+
+    #[test]
+    fn test_check_spacing_diagonal_corners() {
+        let r1 = Rectangle::new(Point::new(0, 0), Point::new(10, 10));
+        // Corners 3 apart in x and 4 in y: distance 5
+        let r2 = Rectangle::new(Point::new(13, 14), Point::new(20, 20));
+        assert!(check_spacing(&r1, &r2, 5));
+        assert!(!check_spacing(&r1, &r2, 6));
+        assert!(check_spacing(&r2, &r1, 5));
+    }
+
+    #[test]
+    fn test_check_spacing_large_coordinates() {
+        // Squaring these gaps would overflow i32.
+        let r1 = Rectangle::new(Point::new(-1_000_000_000, -1_000_000_000), Point::new(0, 0));
+        let r2 = Rectangle::new(
+            Point::new(1_000_000_000, 1_000_000_000),
+            Point::new(1_100_000_000, 1_100_000_000),
+        );
+        assert!(check_spacing(&r1, &r2, 100));
+        assert!(check_spacing(&r1, &r2, 1_000_000_000));
+        // Diagonal neighbours far from the origin, corners 5 apart
+        let r4 = Rectangle::new(
+            Point::new(1_100_000_003, 1_100_000_004),
+            Point::new(1_100_000_010, 1_100_000_010),
+        );
+        assert!(check_spacing(&r2, &r4, 5));
+        assert!(!check_spacing(&r2, &r4, 6));
+    }
 }